@@ -34,7 +34,8 @@ async fn main() -> anyhow::Result<()> {
     tokio::spawn(async move {
         while let Ok(event) = rx.recv().await {
             match event {
-                KeyerEvent::CharacterSent(ch) => print!("{ch}"),
+                KeyerEvent::CharacterSent { ch, .. } => print!("{ch}"),
+                KeyerEvent::PaddleWord(word) => println!("\n[sent by hand: {word}]"),
                 KeyerEvent::PaddleBreakIn => println!("\n[BREAK-IN - message aborted]"),
                 _ => {}
             }
@@ -2,7 +2,7 @@
 //!
 //! Runs through a checklist of features and reports pass/fail for each.
 //!
-//! Usage: cargo run --example hwtest -- /dev/ttyUSB0 [--speed 20] [--no-sidetone]
+//! Usage: cargo run --example hwtest -- /dev/ttyUSB0 [--speed 20] [--no-sidetone] [--paddle]
 
 use std::time::Duration;
 
@@ -62,7 +62,7 @@ async fn main() -> anyhow::Result<()> {
 
     let args: Vec<String> = std::env::args().collect();
     if args.len() < 2 {
-        eprintln!("Usage: {} <port> [--speed <wpm>] [--no-sidetone]", args[0]);
+        eprintln!("Usage: {} <port> [--speed <wpm>] [--no-sidetone] [--paddle]", args[0]);
         eprintln!("Example: {} /dev/ttyUSB0 --speed 25", args[0]);
         std::process::exit(1);
     }
@@ -75,6 +75,7 @@ async fn main() -> anyhow::Result<()> {
         .and_then(|s| s.parse().ok())
         .unwrap_or(20);
     let no_sidetone = args.iter().any(|a| a == "--no-sidetone");
+    let paddle = args.iter().any(|a| a == "--paddle");
 
     let mut t = TestRunner::new();
 
@@ -88,7 +89,7 @@ async fn main() -> anyhow::Result<()> {
     println!();
 
     // ── Test 1: Connect ─────────────────────────────────────────
-    println!("[1/10] Connect and version detection");
+    println!("[1/11] Connect and version detection");
     let mut builder = WinKeyerBuilder::new(port).speed(speed);
     if no_sidetone {
         // No sidetone: enable PTT + KeyOut1, omit SIDETONE_ENABLE
@@ -109,7 +110,7 @@ async fn main() -> anyhow::Result<()> {
     };
 
     // ── Test 2: Echo test ───────────────────────────────────────
-    println!("[2/10] Echo test");
+    println!("[2/11] Echo test");
     match keyer.echo_test(0x55).await {
         Ok(v) if v == 0x55 => t.pass("echo test (0x55)"),
         Ok(v) => t.fail("echo test", &format!("expected 0x55, got 0x{v:02X}")),
@@ -117,7 +118,7 @@ async fn main() -> anyhow::Result<()> {
     }
 
     // ── Test 3: Speed set/get ───────────────────────────────────
-    println!("[3/10] Speed set/get");
+    println!("[3/11] Speed set/get");
     match keyer.set_speed(25).await {
         Ok(()) => match keyer.get_speed().await {
             Ok(25) => t.pass("speed set/get (25 WPM)"),
@@ -130,14 +131,14 @@ async fn main() -> anyhow::Result<()> {
     let _ = keyer.set_speed(speed).await;
 
     // ── Test 4: Invalid speed rejected ──────────────────────────
-    println!("[4/10] Invalid speed rejection");
+    println!("[4/11] Invalid speed rejection");
     match keyer.set_speed(3).await {
         Err(_) => t.pass("invalid speed rejected (3 WPM)"),
         Ok(()) => t.fail("invalid speed", "accepted speed 3, should reject"),
     }
 
     // ── Test 5: Send CW + echo events ──────────────────────────
-    println!("[5/10] Send CW and verify echo events");
+    println!("[5/11] Send CW and verify echo events");
     {
         let mut rx = keyer.subscribe();
         if let Err(e) = keyer.send_message("E").await {
@@ -148,7 +149,7 @@ async fn main() -> anyhow::Result<()> {
             let deadline = tokio::time::Instant::now() + Duration::from_secs(3);
             while tokio::time::Instant::now() < deadline {
                 match tokio::time::timeout(Duration::from_millis(500), rx.recv()).await {
-                    Ok(Ok(KeyerEvent::CharacterSent(ch))) => {
+                    Ok(Ok(KeyerEvent::CharacterSent { ch, .. })) => {
                         println!("        echo: '{ch}'");
                         got_echo = true;
                         break;
@@ -168,7 +169,7 @@ async fn main() -> anyhow::Result<()> {
     }
 
     // ── Test 6: Tune on/off ─────────────────────────────────────
-    println!("[6/10] Tune mode (key down 500ms)");
+    println!("[6/11] Tune mode (key down 500ms)");
     match keyer.set_tune(true).await {
         Ok(()) => {
             tokio::time::sleep(Duration::from_millis(500)).await;
@@ -182,7 +183,7 @@ async fn main() -> anyhow::Result<()> {
     tokio::time::sleep(Duration::from_millis(200)).await;
 
     // ── Test 7: Abort ───────────────────────────────────────────
-    println!("[7/10] Send + abort");
+    println!("[7/11] Send + abort");
     if let Err(e) = keyer.send_message("TESTING ABORT").await {
         t.fail("abort (send)", &e.to_string());
     } else {
@@ -195,7 +196,7 @@ async fn main() -> anyhow::Result<()> {
     tokio::time::sleep(Duration::from_millis(200)).await;
 
     // ── Test 8: Prosign ─────────────────────────────────────────
-    println!("[8/10] Prosign (AR)");
+    println!("[8/11] Prosign (AR)");
     match keyer.send_prosign(b'A', b'R').await {
         Ok(()) => {
             tokio::time::sleep(Duration::from_secs(1)).await;
@@ -205,7 +206,7 @@ async fn main() -> anyhow::Result<()> {
     }
 
     // ── Test 9: Buffered speed change ───────────────────────────
-    println!("[9/10] Buffered speed change");
+    println!("[9/11] Buffered speed change");
     match keyer.set_buffered_speed(15).await {
         Ok(()) => {
             if let Err(e) = keyer.send_message("E").await {
@@ -222,7 +223,7 @@ async fn main() -> anyhow::Result<()> {
     }
 
     // ── Test 10: Speed pot read ─────────────────────────────────
-    println!("[10/10] Speed pot event (2s window)");
+    println!("[10/11] Speed pot event (2s window)");
     {
         let mut rx = keyer.subscribe();
         let mut got_pot = false;
@@ -248,6 +249,38 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
+    // ── Test 11: Paddle contacts ────────────────────────────────
+    println!("[11/11] Paddle contact A/D (--paddle)");
+    if paddle {
+        for contact in ["dit", "dah"] {
+            println!("        hold the {contact} paddle closed...");
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            let name = format!("{contact} contact");
+            match keyer.sample_paddle_a2d(50, Duration::from_millis(20)).await {
+                Ok(s) if s.is_unsupported() => {
+                    t.skip(&name, "firmware returns 0 for paddle A/D");
+                }
+                Ok(s) => {
+                    println!(
+                        "        {contact}: min {} max {} mean {:.1}, {} crossings",
+                        s.min(),
+                        s.max(),
+                        s.mean(),
+                        s.crossings(0x80)
+                    );
+                    if s.is_noisy(8) {
+                        t.fail(&name, "noisy readings, clean or adjust the contact");
+                    } else {
+                        t.pass(&name);
+                    }
+                }
+                Err(e) => t.fail(&name, &e.to_string()),
+            }
+        }
+    } else {
+        t.skip("paddle contacts", "pass --paddle to check them");
+    }
+
     // ── Cleanup ─────────────────────────────────────────────────
     println!();
     println!("Closing connection...");
@@ -50,39 +50,44 @@ async fn main() -> anyhow::Result<()> {
     println!();
 
     // Spawn event monitor
-    let mut event_rx = keyer.subscribe();
+    let mut events = keyer.events();
     tokio::spawn(async move {
-        loop {
-            match event_rx.recv().await {
-                Ok(event) => match event {
-                    KeyerEvent::StatusChanged(s) => {
-                        if s.busy || s.keydown || s.xoff {
-                            eprint!(
-                                "\r  [status: busy={} key={} xoff={}]\r\n> ",
-                                s.busy, s.keydown, s.xoff
-                            );
-                            let _ = std::io::stderr().flush();
-                        }
-                    }
-                    KeyerEvent::SpeedPotChanged { wpm } => {
-                        eprint!("\r  [pot: {wpm} WPM]\r\n> ");
-                        let _ = std::io::stderr().flush();
-                    }
-                    KeyerEvent::CharacterSent(ch) => {
-                        eprint!("{ch}");
+        while let Some(event) = events.recv().await {
+            match event {
+                KeyerEvent::StatusChanged(s) => {
+                    if s.busy || s.keydown || s.xoff {
+                        eprint!(
+                            "\r  [status: busy={} key={} xoff={}]\r\n> ",
+                            s.busy, s.keydown, s.xoff
+                        );
                         let _ = std::io::stderr().flush();
                     }
-                    KeyerEvent::PaddleBreakIn => {
-                        eprint!("\r  [PADDLE BREAK-IN]\r\n> ");
-                        let _ = std::io::stderr().flush();
-                    }
-                    KeyerEvent::Disconnected => {
-                        eprintln!("\r  [DISCONNECTED]");
-                        break;
-                    }
-                    KeyerEvent::Connected => {}
-                },
-                Err(_) => break,
+                }
+                KeyerEvent::SpeedPotChanged { wpm } => {
+                    eprint!("\r  [pot: {wpm} WPM]\r\n> ");
+                    let _ = std::io::stderr().flush();
+                }
+                KeyerEvent::CharacterSent { ch, .. } => {
+                    eprint!("{ch}");
+                    let _ = std::io::stderr().flush();
+                }
+                KeyerEvent::PaddleBreakIn => {
+                    eprint!("\r  [PADDLE BREAK-IN]\r\n> ");
+                    let _ = std::io::stderr().flush();
+                }
+                KeyerEvent::PaddleWord(word) => {
+                    eprint!("\r  [paddle: {word}]\r\n> ");
+                    let _ = std::io::stderr().flush();
+                }
+                KeyerEvent::EventsDropped { count } => {
+                    eprint!("\r  [{count} events dropped]\r\n> ");
+                    let _ = std::io::stderr().flush();
+                }
+                KeyerEvent::Disconnected => {
+                    eprintln!("\r  [DISCONNECTED]");
+                    break;
+                }
+                KeyerEvent::Connected => {}
             }
         }
     });
@@ -25,13 +25,15 @@ async fn main() -> anyhow::Result<()> {
     println!("Monitoring events (Ctrl-C to quit)...\n");
 
     let mut rx = keyer.subscribe();
+    let start = tokio::time::Instant::now();
 
     loop {
-        match rx.recv().await {
-            Ok(event) => match event {
+        match rx.recv_timed().await {
+            Ok(timed) => match timed.event {
                 KeyerEvent::StatusChanged(status) => {
                     println!(
-                        "Status: busy={} keydown={} xoff={} breakin={} waiting={}",
+                        "[{:>8.3}s #{}] Status: busy={} keydown={} xoff={} breakin={} waiting={}",
+                        (timed.at - start).as_secs_f64(), timed.seq,
                         status.busy, status.keydown, status.xoff,
                         status.breakin, status.waiting
                     );
@@ -39,9 +41,12 @@ async fn main() -> anyhow::Result<()> {
                 KeyerEvent::SpeedPotChanged { wpm } => {
                     println!("Speed pot: {wpm} WPM");
                 }
-                KeyerEvent::CharacterSent(ch) => {
+                KeyerEvent::CharacterSent { ch, .. } => {
                     print!("{ch}");
                 }
+                KeyerEvent::PaddleWord(word) => {
+                    println!("\n[PADDLE] {word}");
+                }
                 KeyerEvent::PaddleBreakIn => {
                     println!("\n[PADDLE BREAK-IN]");
                 }
@@ -52,6 +57,9 @@ async fn main() -> anyhow::Result<()> {
                     println!("[DISCONNECTED]");
                     break;
                 }
+                KeyerEvent::EventsDropped { count } => {
+                    println!("\n[{count} EVENTS DROPPED]");
+                }
             },
             Err(e) => {
                 eprintln!("Event error: {e}");
@@ -0,0 +1,22 @@
+//! Print an annotated timeline of a captured session.
+//!
+//! Record one with `WinKeyerBuilder::capture`, then:
+//!
+//! Usage: cargo run --example trace -- session.txt
+
+use winkey::capture::Capture;
+use winkey::trace::annotate;
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 2 {
+        eprintln!("Usage: {} <capture>", args[0]);
+        std::process::exit(1);
+    }
+
+    let capture = Capture::load(&args[1])?;
+    for entry in annotate(&capture) {
+        println!("{entry}");
+    }
+    Ok(())
+}
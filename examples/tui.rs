@@ -17,7 +17,9 @@ use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Paragraph, Row, Table};
 use tokio::sync::mpsc;
 
-use winkey::{Keyer, KeyerEvent, KeyerStatus, PaddleMode, PinConfig, WinKeyerBuilder};
+use winkey::{
+    Keyer, KeyerEvent, KeyerSettings, KeyerStatus, PaddleMode, PinConfig, WinKeyerBuilder,
+};
 
 // ---------------------------------------------------------------------------
 // App state
@@ -29,68 +31,42 @@ enum Focus {
     Input,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum PaddleModeValue {
-    IambicA,
-    IambicB,
-    Ultimatic,
-    Bug,
-}
-
-impl PaddleModeValue {
-    fn label(self) -> &'static str {
-        match self {
-            Self::IambicA => "Iambic A",
-            Self::IambicB => "Iambic B",
-            Self::Ultimatic => "Ultimatic",
-            Self::Bug => "Bug",
-        }
+fn paddle_label(mode: PaddleMode) -> &'static str {
+    match mode {
+        PaddleMode::IambicA => "Iambic A",
+        PaddleMode::IambicB => "Iambic B",
+        PaddleMode::Ultimatic => "Ultimatic",
+        PaddleMode::Bug => "Bug",
     }
+}
 
-    fn next(self) -> Self {
-        match self {
-            Self::IambicA => Self::IambicB,
-            Self::IambicB => Self::Ultimatic,
-            Self::Ultimatic => Self::Bug,
-            Self::Bug => Self::IambicA,
-        }
+fn paddle_next(mode: PaddleMode) -> PaddleMode {
+    match mode {
+        PaddleMode::IambicA => PaddleMode::IambicB,
+        PaddleMode::IambicB => PaddleMode::Ultimatic,
+        PaddleMode::Ultimatic => PaddleMode::Bug,
+        PaddleMode::Bug => PaddleMode::IambicA,
     }
+}
 
-    fn prev(self) -> Self {
-        match self {
-            Self::IambicA => Self::Bug,
-            Self::IambicB => Self::IambicA,
-            Self::Ultimatic => Self::IambicB,
-            Self::Bug => Self::Ultimatic,
-        }
+fn paddle_prev(mode: PaddleMode) -> PaddleMode {
+    match mode {
+        PaddleMode::IambicA => PaddleMode::Bug,
+        PaddleMode::IambicB => PaddleMode::IambicA,
+        PaddleMode::Ultimatic => PaddleMode::IambicB,
+        PaddleMode::Bug => PaddleMode::Ultimatic,
     }
+}
 
-    fn to_protocol(self) -> PaddleMode {
-        match self {
-            Self::IambicA => PaddleMode::IambicA,
-            Self::IambicB => PaddleMode::IambicB,
-            Self::Ultimatic => PaddleMode::Ultimatic,
-            Self::Bug => PaddleMode::Bug,
-        }
-    }
+/// Hang time (0-3) from pin config bits 5-4.
+fn hang_time(pins: PinConfig) -> u8 {
+    (pins.bits() >> 4) & 0x03
 }
 
 struct App {
-    // Settings values
-    speed: u8,
-    weight: u8,
-    sidetone: u16,
-    sidetone_vol: u8,
-    sidetone_on: bool,
-    farnsworth: u8,
-    ratio: u8,
-    paddle_mode: PaddleModeValue,
-    ptt_on: bool,
-    ptt_lead_in: u8,
-    ptt_tail: u8,
-    hang_time: u8,
+    // Settings as last sent to the keyer (from its watch channel)
+    settings: KeyerSettings,
     tune: bool,
-    pause: bool,
 
     // UI state
     focus: Focus,
@@ -100,7 +76,6 @@ struct App {
 
     // Status
     status: KeyerStatus,
-    speed_pot: Option<u8>,
 
     // Keyer info
     keyer_name: String,
@@ -112,22 +87,10 @@ struct App {
 const NUM_SETTINGS: usize = 14;
 
 impl App {
-    fn new(keyer_name: String, keyer_port: String, speed: u8) -> Self {
+    fn new(keyer_name: String, keyer_port: String, settings: KeyerSettings) -> Self {
         Self {
-            speed,
-            weight: 50,
-            sidetone: 800,
-            sidetone_vol: 4,
-            sidetone_on: true,
-            farnsworth: 0,
-            ratio: 50,
-            paddle_mode: PaddleModeValue::IambicB,
-            ptt_on: true,
-            ptt_lead_in: 0,
-            ptt_tail: 0,
-            hang_time: 0,
+            settings,
             tune: false,
-            pause: false,
 
             focus: Focus::Settings,
             selected: 0,
@@ -141,7 +104,6 @@ impl App {
                 keydown: false,
                 waiting: false,
             },
-            speed_pot: None,
 
             keyer_name,
             keyer_port,
@@ -171,35 +133,37 @@ impl App {
     }
 
     fn setting_value(&self, idx: usize) -> String {
+        let s = &self.settings;
         match idx {
-            0 => format!("{} WPM", self.speed),
-            1 => format!("{}", self.weight),
-            2 => format!("{} Hz", self.sidetone),
-            3 => match self.sidetone_vol {
-                1..=2 => format!("{} (low)", self.sidetone_vol),
-                _ => format!("{} (normal)", self.sidetone_vol),
+            0 => format!("{} WPM", s.speed_wpm),
+            1 => format!("{}", s.weight),
+            2 => format!("{} Hz", s.sidetone_hz),
+            3 => match s.sidetone_volume {
+                None => "--".into(),
+                Some(v @ 1..=2) => format!("{v} (low)"),
+                Some(v) => format!("{v} (normal)"),
             },
-            4 => if self.sidetone_on { "ON" } else { "OFF" }.into(),
+            4 => if s.pin_config.contains(PinConfig::SIDETONE_ENABLE) { "ON" } else { "OFF" }.into(),
             5 => {
-                if self.farnsworth == 0 {
+                if s.farnsworth_wpm == 0 {
                     "0 (off)".into()
                 } else {
-                    format!("{}", self.farnsworth)
+                    format!("{}", s.farnsworth_wpm)
                 }
             }
-            6 => format!("{}", self.ratio),
-            7 => self.paddle_mode.label().into(),
-            8 => if self.ptt_on { "ON" } else { "OFF" }.into(),
-            9 => format!("{} ({}ms)", self.ptt_lead_in, self.ptt_lead_in as u16 * 10),
-            10 => format!("{} ({}ms)", self.ptt_tail, self.ptt_tail as u16 * 10),
-            11 => match self.hang_time {
+            6 => format!("{}", s.dit_dah_ratio),
+            7 => paddle_label(s.paddle_mode()).into(),
+            8 => if s.pin_config.contains(PinConfig::PTT_ENABLE) { "ON" } else { "OFF" }.into(),
+            9 => format!("{} ({}ms)", s.ptt_lead_in, s.ptt_lead_in as u16 * 10),
+            10 => format!("{} ({}ms)", s.ptt_tail, s.ptt_tail as u16 * 10),
+            11 => match hang_time(s.pin_config) {
                 0 => "1.0 wordspace".into(),
                 1 => "1.33 wordspace".into(),
                 2 => "1.67 wordspace".into(),
                 _ => "2.0 wordspace".into(),
             },
             12 => if self.tune { "ON" } else { "OFF" }.into(),
-            13 => if self.pause { "ON" } else { "OFF" }.into(),
+            13 => if s.paused { "ON" } else { "OFF" }.into(),
             _ => String::new(),
         }
     }
@@ -212,6 +176,7 @@ impl App {
 enum AppEvent {
     Terminal(Event),
     Keyer(KeyerEvent),
+    Settings(KeyerSettings),
     Tick,
 }
 
@@ -270,7 +235,7 @@ fn ui(frame: &mut Frame, app: &App) {
 
     // Status panel
     let yn = |b: bool| if b { "yes" } else { "no " };
-    let pot_str = match app.speed_pot {
+    let pot_str = match app.settings.speed_pot_wpm {
         Some(w) => format!("{w} WPM"),
         None => "-- WPM".into(),
     };
@@ -397,22 +362,22 @@ enum Adjustment {
     Decrement,
 }
 
-fn current_pin_config(app: &App) -> PinConfig {
-    let mut cfg = PinConfig::KEY_OUTPUT; // Always enable primary key output
-    if app.ptt_on {
-        cfg |= PinConfig::PTT_ENABLE;
-    }
-    if app.sidetone_on {
-        cfg |= PinConfig::SIDETONE_ENABLE;
-    }
-    // Hang time: bits 5-4 encode 0-3
-    if app.hang_time & 0x02 != 0 {
-        cfg |= PinConfig::HANG_TIME_1;
-    }
-    if app.hang_time & 0x01 != 0 {
-        cfg |= PinConfig::HANG_TIME_0;
+/// Step a numeric setting, clamped to `min..=max`.
+fn step(value: u8, dir: &Adjustment, by: u8, min: u8, max: u8) -> u8 {
+    match dir {
+        Adjustment::Increment => value.saturating_add(by).min(max),
+        Adjustment::Decrement => value.saturating_sub(by).max(min),
     }
-    cfg
+}
+
+/// Pin config with one flag flipped.
+fn toggled(pins: PinConfig, flag: PinConfig) -> PinConfig {
+    pins ^ flag
+}
+
+/// Pin config with a new hang time (0-3) in bits 5-4.
+fn with_hang_time(pins: PinConfig, hang: u8) -> PinConfig {
+    PinConfig::from_bits_retain((pins.bits() & !0x30) | ((hang & 0x03) << 4))
 }
 
 async fn adjust_setting(
@@ -420,140 +385,104 @@ async fn adjust_setting(
     keyer: &winkey::WinKeyer,
     dir: Adjustment,
 ) {
+    let s = app.settings.clone();
     match app.selected {
         0 => {
-            // Speed 5-99
-            app.speed = match dir {
-                Adjustment::Increment => app.speed.saturating_add(1).min(99),
-                Adjustment::Decrement => app.speed.saturating_sub(1).max(5),
-            };
-            let _ = keyer.set_speed(app.speed).await;
+            let _ = keyer.set_speed(step(s.speed_wpm, &dir, 1, 5, 99)).await;
         }
         1 => {
-            // Weight 10-90
-            app.weight = match dir {
-                Adjustment::Increment => app.weight.saturating_add(1).min(90),
-                Adjustment::Decrement => app.weight.saturating_sub(1).max(10),
-            };
-            let _ = keyer.set_weight(app.weight).await;
+            let _ = keyer.set_weight(step(s.weight, &dir, 1, 10, 90)).await;
         }
         2 => {
             // Sidetone freq 500-4000 Hz (50 Hz steps)
-            app.sidetone = match dir {
-                Adjustment::Increment => app.sidetone.saturating_add(50).min(4000),
-                Adjustment::Decrement => app.sidetone.saturating_sub(50).max(500),
+            let hz = match dir {
+                Adjustment::Increment => s.sidetone_hz.saturating_add(50).min(4000),
+                Adjustment::Decrement => s.sidetone_hz.saturating_sub(50).max(500),
             };
-            let _ = keyer.set_sidetone(app.sidetone).await;
+            let _ = keyer.set_sidetone(hz).await;
         }
         3 => {
-            // Sidetone volume 1-4
-            app.sidetone_vol = match dir {
-                Adjustment::Increment => app.sidetone_vol.saturating_add(1).min(4),
-                Adjustment::Decrement => app.sidetone_vol.saturating_sub(1).max(1),
-            };
-            let _ = keyer.set_sidetone_volume(app.sidetone_vol).await;
+            let vol = step(s.sidetone_volume.unwrap_or(4), &dir, 1, 1, 4);
+            let _ = keyer.set_sidetone_volume(vol).await;
         }
         4 => {
-            // Sidetone on/off
-            app.sidetone_on = !app.sidetone_on;
-            let _ = keyer.set_pin_config(current_pin_config(app)).await;
+            let _ = keyer
+                .set_pin_config(toggled(s.pin_config, PinConfig::SIDETONE_ENABLE))
+                .await;
         }
         5 => {
-            // Farnsworth 0-99
-            app.farnsworth = match dir {
-                Adjustment::Increment => app.farnsworth.saturating_add(1).min(99),
-                Adjustment::Decrement => app.farnsworth.saturating_sub(1),
-            };
-            let _ = keyer.set_farnsworth(app.farnsworth).await;
+            let _ = keyer.set_farnsworth(step(s.farnsworth_wpm, &dir, 1, 0, 99)).await;
         }
         6 => {
-            // Dit/Dah ratio 33-66
-            app.ratio = match dir {
-                Adjustment::Increment => app.ratio.saturating_add(1).min(66),
-                Adjustment::Decrement => app.ratio.saturating_sub(1).max(33),
-            };
-            let _ = keyer.set_ratio(app.ratio).await;
+            let _ = keyer.set_ratio(step(s.dit_dah_ratio, &dir, 1, 33, 66)).await;
         }
         7 => {
-            // Paddle mode enum
-            app.paddle_mode = match dir {
-                Adjustment::Increment => app.paddle_mode.next(),
-                Adjustment::Decrement => app.paddle_mode.prev(),
+            let mode = match dir {
+                Adjustment::Increment => paddle_next(s.paddle_mode()),
+                Adjustment::Decrement => paddle_prev(s.paddle_mode()),
             };
-            let _ = keyer.set_paddle_mode(app.paddle_mode.to_protocol()).await;
+            let _ = keyer.set_paddle_mode(mode).await;
         }
         8 => {
-            // PTT on/off
-            app.ptt_on = !app.ptt_on;
-            let _ = keyer.set_pin_config(current_pin_config(app)).await;
+            let _ = keyer
+                .set_pin_config(toggled(s.pin_config, PinConfig::PTT_ENABLE))
+                .await;
         }
         9 => {
-            // PTT lead-in 0-250
-            app.ptt_lead_in = match dir {
-                Adjustment::Increment => app.ptt_lead_in.saturating_add(1).min(250),
-                Adjustment::Decrement => app.ptt_lead_in.saturating_sub(1),
-            };
-            let _ = keyer.set_ptt_timing(app.ptt_lead_in, app.ptt_tail).await;
+            let lead_in = step(s.ptt_lead_in, &dir, 1, 0, 250);
+            let _ = keyer.set_ptt_timing(lead_in, s.ptt_tail).await;
         }
         10 => {
-            // PTT tail 0-250
-            app.ptt_tail = match dir {
-                Adjustment::Increment => app.ptt_tail.saturating_add(1).min(250),
-                Adjustment::Decrement => app.ptt_tail.saturating_sub(1),
-            };
-            let _ = keyer.set_ptt_timing(app.ptt_lead_in, app.ptt_tail).await;
+            let tail = step(s.ptt_tail, &dir, 1, 0, 250);
+            let _ = keyer.set_ptt_timing(s.ptt_lead_in, tail).await;
         }
         11 => {
-            // Hang time 0-3
-            app.hang_time = match dir {
-                Adjustment::Increment => (app.hang_time + 1).min(3),
-                Adjustment::Decrement => app.hang_time.saturating_sub(1),
-            };
-            let _ = keyer.set_pin_config(current_pin_config(app)).await;
+            let hang = step(hang_time(s.pin_config), &dir, 1, 0, 3);
+            let _ = keyer.set_pin_config(with_hang_time(s.pin_config, hang)).await;
         }
         12 => {
-            // Tune toggle
             app.tune = !app.tune;
             let _ = keyer.set_tune(app.tune).await;
         }
         13 => {
-            // Pause toggle
-            app.pause = !app.pause;
-            let _ = keyer.set_pause(app.pause).await;
+            let _ = keyer.set_pause(!s.paused).await;
         }
         _ => {}
     }
+    app.settings = keyer.settings();
 }
 
 async fn toggle_setting(app: &mut App, keyer: &winkey::WinKeyer) {
+    let s = app.settings.clone();
     match app.selected {
         4 => {
-            app.sidetone_on = !app.sidetone_on;
-            let _ = keyer.set_pin_config(current_pin_config(app)).await;
+            let _ = keyer
+                .set_pin_config(toggled(s.pin_config, PinConfig::SIDETONE_ENABLE))
+                .await;
         }
         7 => {
-            app.paddle_mode = app.paddle_mode.next();
-            let _ = keyer.set_paddle_mode(app.paddle_mode.to_protocol()).await;
+            let _ = keyer.set_paddle_mode(paddle_next(s.paddle_mode())).await;
         }
         8 => {
-            app.ptt_on = !app.ptt_on;
-            let _ = keyer.set_pin_config(current_pin_config(app)).await;
+            let _ = keyer
+                .set_pin_config(toggled(s.pin_config, PinConfig::PTT_ENABLE))
+                .await;
         }
         11 => {
             // Cycle hang time forward on Enter
-            app.hang_time = (app.hang_time + 1) % 4;
-            let _ = keyer.set_pin_config(current_pin_config(app)).await;
+            let hang = (hang_time(s.pin_config) + 1) % 4;
+            let _ = keyer.set_pin_config(with_hang_time(s.pin_config, hang)).await;
         }
         12 => {
             app.tune = !app.tune;
             let _ = keyer.set_tune(app.tune).await;
         }
         13 => {
-            app.pause = !app.pause;
-            let _ = keyer.set_pause(app.pause).await;
+            let _ = keyer.set_pause(!s.paused).await;
         }
         _ => {}
     }
+    app.settings = keyer.settings();
 }
 
 // ---------------------------------------------------------------------------
@@ -567,10 +496,11 @@ async fn handle_event(
 ) {
     match ev {
         AppEvent::Tick => {}
+        AppEvent::Settings(settings) => app.settings = settings,
         AppEvent::Keyer(keyer_ev) => match keyer_ev {
             KeyerEvent::StatusChanged(s) => app.status = s,
-            KeyerEvent::SpeedPotChanged { wpm } => app.speed_pot = Some(wpm),
-            KeyerEvent::CharacterSent(ch) => {
+            KeyerEvent::SpeedPotChanged { .. } => {}
+            KeyerEvent::CharacterSent { ch, .. } => {
                 app.echo_buf.push(ch);
                 // Keep echo buffer from growing unbounded
                 if app.echo_buf.len() > 200 {
@@ -579,6 +509,8 @@ async fn handle_event(
                 }
             }
             KeyerEvent::PaddleBreakIn => {}
+            KeyerEvent::PaddleWord(_) => {}
+            KeyerEvent::EventsDropped { .. } => {}
             KeyerEvent::Connected => {}
             KeyerEvent::Disconnected => app.quit = true,
         },
@@ -680,10 +612,7 @@ async fn main() -> anyhow::Result<()> {
     let backend = CrosstermBackend::new(stdout());
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new(keyer_name, keyer_port, speed);
-    if no_sidetone {
-        app.sidetone_on = false;
-    }
+    let mut app = App::new(keyer_name, keyer_port, keyer.settings());
 
     // Unified event channel
     let (ev_tx, mut ev_rx) = mpsc::unbounded_channel::<AppEvent>();
@@ -703,20 +632,26 @@ async fn main() -> anyhow::Result<()> {
     let tx2 = ev_tx.clone();
     let mut keyer_rx = keyer.subscribe();
     tokio::spawn(async move {
-        loop {
-            match keyer_rx.recv().await {
-                Ok(event) => {
-                    if tx2.send(AppEvent::Keyer(event)).is_err() {
-                        break;
-                    }
-                }
-                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
-                Err(_) => break,
+        while let Ok(event) = keyer_rx.recv().await {
+            if tx2.send(AppEvent::Keyer(event)).is_err() {
+                break;
+            }
+        }
+    });
+
+    // Task 3: Settings changes
+    let tx_settings = ev_tx.clone();
+    let mut settings_rx = keyer.watch_settings();
+    tokio::spawn(async move {
+        while settings_rx.changed().await.is_ok() {
+            let settings = settings_rx.borrow_and_update().clone();
+            if tx_settings.send(AppEvent::Settings(settings)).is_err() {
+                break;
             }
         }
     });
 
-    // Task 3: Tick timer
+    // Task 4: Tick timer
     let tx3 = ev_tx.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(Duration::from_millis(250));
@@ -0,0 +1,440 @@
+//! CW beacon scheduler.
+//!
+//! [`Beacon`] sends a message on a schedule through any [`Keyer`]: every
+//! fixed interval, or aligned to the wall clock ("every 10 minutes at
+//! :00"). Each cycle can be wrapped in PTT and followed by a long key-down
+//! carrier. A failed cycle (keyer unplugged, remote link down) is reported
+//! and the next one is tried on schedule, so the beacon carries on once the
+//! keyer is back. A keyer that had to be rebuilt after reconnecting is
+//! handed over with [`Beacon::set_keyer`].
+//!
+//! ```no_run
+//! # use std::sync::Arc;
+//! # use std::time::Duration;
+//! # use winkey::{Keyer, WinKeyerBuilder};
+//! use winkey::beacon::{Beacon, BeaconConfig, BeaconSchedule};
+//!
+//! # async fn example() -> winkey::Result<()> {
+//! let keyer = Arc::new(WinKeyerBuilder::new("/dev/ttyUSB0").build().await?);
+//! let config = BeaconConfig::new(
+//!     "VVV DE K1EL/B FN31",
+//!     BeaconSchedule::aligned(Duration::from_secs(600)),
+//! )
+//! .key_down(Duration::from_secs(10))
+//! .ptt(true);
+//! let beacon = Beacon::start(keyer, config)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, warn};
+
+use crate::error::{Error, Result};
+use crate::keyer::Keyer;
+
+/// When beacon cycles start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BeaconSchedule {
+    /// First cycle immediately, then every `period` after the previous
+    /// cycle started.
+    Interval(Duration),
+    /// On wall-clock multiples of `period` (counted from midnight UTC),
+    /// shifted by `offset`. Ten minutes with a 2 minute offset sends at
+    /// :02, :12, :22 and so on.
+    Aligned { period: Duration, offset: Duration },
+}
+
+impl BeaconSchedule {
+    /// Fixed interval.
+    pub fn every(period: Duration) -> Self {
+        Self::Interval(period)
+    }
+
+    /// Wall-clock aligned with no offset.
+    pub fn aligned(period: Duration) -> Self {
+        Self::Aligned {
+            period,
+            offset: Duration::ZERO,
+        }
+    }
+
+    fn period(&self) -> Duration {
+        match *self {
+            Self::Interval(period) | Self::Aligned { period, .. } => period,
+        }
+    }
+
+    /// Time from `now` until the next aligned slot (zero if `now` is on a
+    /// slot). Interval schedules have no fixed slots and return zero.
+    pub fn delay_from(&self, now: SystemTime) -> Duration {
+        let Self::Aligned { period, offset } = *self else {
+            return Duration::ZERO;
+        };
+        let period = period.as_millis().max(1);
+        let since = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        let into_slot = (since + period - offset.as_millis() % period) % period;
+        let wait = (period - into_slot) % period;
+        Duration::from_millis(wait as u64)
+    }
+}
+
+/// What a beacon sends and when.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BeaconConfig {
+    /// Message sent each cycle, usually the identification.
+    pub message: String,
+    pub schedule: BeaconSchedule,
+    /// Carrier keyed after the message, if any.
+    pub key_down: Option<Duration>,
+    /// Hold PTT for the whole cycle.
+    pub ptt: bool,
+}
+
+impl BeaconConfig {
+    /// Send `message` on `schedule`, without PTT or key-down.
+    pub fn new(message: impl Into<String>, schedule: BeaconSchedule) -> Self {
+        Self {
+            message: message.into(),
+            schedule,
+            key_down: None,
+            ptt: false,
+        }
+    }
+
+    /// Key a carrier for `duration` after the message.
+    pub fn key_down(mut self, duration: Duration) -> Self {
+        self.key_down = Some(duration);
+        self
+    }
+
+    /// Assert PTT before the message and release it after the key-down.
+    pub fn ptt(mut self, enabled: bool) -> Self {
+        self.ptt = enabled;
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        crate::protocol::command::validate_cw_text(&self.message)
+            .map_err(Error::InvalidParameter)?;
+        if self.schedule.period().is_zero() {
+            return Err(Error::InvalidParameter(
+                "beacon period must be non-zero".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Beacon progress, from [`Beacon::subscribe`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BeaconEvent {
+    /// A cycle started (`cycle` counts from 1).
+    CycleStarted { cycle: u64 },
+    /// A cycle finished: message, key-down and PTT release all done.
+    CycleSent { cycle: u64 },
+    /// A cycle failed; the beacon tries again at the next slot.
+    CycleFailed { cycle: u64, error: String },
+}
+
+/// A running beacon. Dropping it stops the schedule; a cycle in progress is
+/// cut off and tune and PTT released, as with [`stop`](Self::stop).
+pub struct Beacon {
+    keyer: watch::Sender<Arc<dyn Keyer>>,
+    event_tx: broadcast::Sender<BeaconEvent>,
+    sent: Arc<AtomicU64>,
+    task: JoinHandle<()>,
+}
+
+impl std::fmt::Debug for Beacon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Beacon")
+            .field("keyer", &self.keyer.borrow().info().name)
+            .field("sent", &self.cycles_sent())
+            .finish()
+    }
+}
+
+impl Beacon {
+    /// Validate `config` and start sending on `keyer`. Must be called
+    /// inside a tokio runtime.
+    pub fn start(keyer: Arc<dyn Keyer>, config: BeaconConfig) -> Result<Self> {
+        config.validate()?;
+        let (event_tx, _) = broadcast::channel(64);
+        let sent = Arc::new(AtomicU64::new(0));
+        let (keyer, keyer_rx) = watch::channel(keyer);
+        let task = tokio::spawn(run(
+            keyer_rx,
+            config,
+            event_tx.clone(),
+            sent.clone(),
+        ));
+        Ok(Self {
+            keyer,
+            event_tx,
+            sent,
+            task,
+        })
+    }
+
+    /// Send later cycles through `keyer`, e.g. one rebuilt after the old
+    /// one lost its connection. A cycle in progress finishes on the keyer it
+    /// started on.
+    pub fn set_keyer(&self, keyer: Arc<dyn Keyer>) {
+        self.keyer.send_replace(keyer);
+    }
+
+    /// Subscribe to cycle events.
+    pub fn subscribe(&self) -> broadcast::Receiver<BeaconEvent> {
+        self.event_tx.subscribe()
+    }
+
+    /// Number of cycles completed.
+    pub fn cycles_sent(&self) -> u64 {
+        self.sent.load(Ordering::Acquire)
+    }
+
+    /// Stop the schedule, cutting off a cycle in progress: the keyer is
+    /// aborted and tune and PTT released.
+    pub async fn stop(self) -> Result<()> {
+        self.task.abort();
+        let keyer = self.keyer.borrow().clone();
+        release(&*keyer).await
+    }
+}
+
+/// Abort sending and drop tune and PTT.
+async fn release(keyer: &dyn Keyer) -> Result<()> {
+    let result = keyer.abort().await;
+    let _ = keyer.set_tune(false).await;
+    let _ = keyer.set_ptt(false).await;
+    result
+}
+
+/// Resolves once the [`Beacon`] is dropped.
+async fn dropped(keyer: &mut watch::Receiver<Arc<dyn Keyer>>) {
+    while keyer.changed().await.is_ok() {}
+}
+
+async fn run(
+    keyer_rx: watch::Receiver<Arc<dyn Keyer>>,
+    config: BeaconConfig,
+    event_tx: broadcast::Sender<BeaconEvent>,
+    sent: Arc<AtomicU64>,
+) {
+    let mut stop_rx = keyer_rx.clone();
+    let mut interval = match config.schedule {
+        BeaconSchedule::Interval(period) => {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            Some(interval)
+        }
+        BeaconSchedule::Aligned { .. } => None,
+    };
+
+    for cycle in 1.. {
+        let next_slot = async {
+            match interval.as_mut() {
+                Some(interval) => {
+                    interval.tick().await;
+                }
+                None => {
+                    // Sleep past a slot we are already on so a fast cycle
+                    // doesn't run twice in it.
+                    let delay = config.schedule.delay_from(SystemTime::now());
+                    let delay = if delay.is_zero() {
+                        config.schedule.period()
+                    } else {
+                        delay
+                    };
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        };
+        tokio::select! {
+            _ = next_slot => {}
+            _ = dropped(&mut stop_rx) => return,
+        }
+
+        let keyer = keyer_rx.borrow().clone();
+        debug!(cycle, "beacon cycle");
+        let _ = event_tx.send(BeaconEvent::CycleStarted { cycle });
+        let result = tokio::select! {
+            result = send_cycle(&*keyer, &config) => result,
+            _ = dropped(&mut stop_rx) => {
+                // Don't leave the transmitter keyed
+                let _ = release(&*keyer).await;
+                return;
+            }
+        };
+        match result {
+            Ok(()) => {
+                sent.fetch_add(1, Ordering::AcqRel);
+                let _ = event_tx.send(BeaconEvent::CycleSent { cycle });
+            }
+            Err(e) => {
+                warn!(cycle, "beacon cycle failed: {e}");
+                let _ = release(&*keyer).await;
+                let _ = event_tx.send(BeaconEvent::CycleFailed {
+                    cycle,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+}
+
+/// One beacon cycle: PTT on, message, key-down, PTT off.
+async fn send_cycle(keyer: &dyn Keyer, config: &BeaconConfig) -> Result<()> {
+    let mut events = keyer.events();
+    if config.ptt {
+        keyer.set_ptt(true).await?;
+    }
+    keyer.send_message(&config.message).await?;
+    events.host_text_until_idle().await?;
+    if let Some(duration) = config.key_down {
+        keyer.set_tune(true).await?;
+        tokio::time::sleep(duration).await;
+        keyer.set_tune(false).await?;
+    }
+    if config.ptt {
+        keyer.set_ptt(false).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timing::MorseTiming;
+    use crate::virtual_keyer::{TimelineEvent, VirtualKeyer};
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn aligned_delay() {
+        let ten_min = BeaconSchedule::aligned(Duration::from_secs(600));
+        assert_eq!(ten_min.delay_from(at(600)), Duration::ZERO);
+        assert_eq!(ten_min.delay_from(at(601)), Duration::from_secs(599));
+        assert_eq!(ten_min.delay_from(at(1140)), Duration::from_secs(60));
+
+        let offset = BeaconSchedule::Aligned {
+            period: Duration::from_secs(600),
+            offset: Duration::from_secs(120),
+        };
+        assert_eq!(offset.delay_from(at(600)), Duration::from_secs(120));
+        assert_eq!(offset.delay_from(at(720)), Duration::ZERO);
+        assert_eq!(offset.delay_from(at(721)), Duration::from_secs(599));
+
+        let every = BeaconSchedule::every(Duration::from_secs(60));
+        assert_eq!(every.delay_from(at(7)), Duration::ZERO);
+    }
+
+    #[test]
+    fn config_validation() {
+        let bad_text = BeaconConfig::new("VVV DE K1EL ^", BeaconSchedule::every(Duration::from_secs(60)));
+        assert!(bad_text.validate().is_err());
+        let no_period = BeaconConfig::new("VVV", BeaconSchedule::every(Duration::ZERO));
+        assert!(no_period.validate().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn interval_cycles_with_ptt_and_carrier() {
+        let keyer = Arc::new(VirtualKeyer::new(MorseTiming::default()));
+        let config = BeaconConfig::new("E", BeaconSchedule::every(Duration::from_secs(30)))
+            .key_down(Duration::from_secs(2))
+            .ptt(true);
+        let beacon = Beacon::start(keyer.clone(), config).unwrap();
+        let mut rx = beacon.subscribe();
+
+        let mut sent = Vec::new();
+        while sent.len() < 2 {
+            if let BeaconEvent::CycleSent { cycle } = rx.recv().await.unwrap() {
+                sent.push((cycle, tokio::time::Instant::now()));
+            }
+        }
+        assert_eq!(sent[0].0, 1);
+        assert_eq!(sent[1].0, 2);
+        assert_eq!(sent[1].1 - sent[0].1, Duration::from_secs(30));
+        assert_eq!(beacon.cycles_sent(), 2);
+
+        let timeline = keyer.timeline();
+        assert_eq!(timeline.text(), "EE");
+        let ptt: Vec<bool> = timeline
+            .events
+            .iter()
+            .filter_map(|(_, e)| match e {
+                TimelineEvent::Ptt(on) => Some(*on),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ptt, [true, false, true, false]);
+        // Dit plus a 2 s carrier each cycle
+        let carrier = timeline
+            .key_intervals()
+            .into_iter()
+            .filter(|r| r.end - r.start >= Duration::from_secs(2))
+            .count();
+        assert_eq!(carrier, 2);
+
+        beacon.stop().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn drop_releases_ptt_and_carrier() {
+        let keyer = Arc::new(VirtualKeyer::new(MorseTiming::default()));
+        let config = BeaconConfig::new("E", BeaconSchedule::every(Duration::from_secs(30)))
+            .key_down(Duration::from_secs(10))
+            .ptt(true);
+        let beacon = Beacon::start(keyer.clone(), config).unwrap();
+        let mut rx = beacon.subscribe();
+        assert_eq!(rx.recv().await.unwrap(), BeaconEvent::CycleStarted { cycle: 1 });
+
+        // Partway through the carrier
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        drop(beacon);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let timeline = keyer.timeline();
+        let last = |want: fn(&TimelineEvent) -> Option<bool>| {
+            timeline.events.iter().rev().find_map(|(_, e)| want(e))
+        };
+        let ptt = last(|e| match e {
+            TimelineEvent::Ptt(on) => Some(*on),
+            _ => None,
+        });
+        let key = last(|e| match e {
+            TimelineEvent::Key(down) => Some(*down),
+            _ => None,
+        });
+        assert_eq!((ptt, key), (Some(false), Some(false)));
+        assert!(timeline.key_intervals().iter().all(|r| r.end - r.start < Duration::from_secs(10)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn set_keyer_moves_later_cycles() {
+        let first = Arc::new(VirtualKeyer::new(MorseTiming::default()));
+        let second = Arc::new(VirtualKeyer::new(MorseTiming::default()));
+        let config = BeaconConfig::new("E", BeaconSchedule::every(Duration::from_secs(30)));
+        let beacon = Beacon::start(first.clone(), config).unwrap();
+        let mut rx = beacon.subscribe();
+
+        let mut sent = 0;
+        while sent < 2 {
+            if let BeaconEvent::CycleSent { .. } = rx.recv().await.unwrap() {
+                sent += 1;
+                beacon.set_keyer(second.clone());
+            }
+        }
+        assert_eq!(first.timeline().text(), "E");
+        assert_eq!(second.timeline().text(), "E");
+    }
+}
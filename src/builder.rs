@@ -1,19 +1,24 @@
 //! WinKeyerBuilder: fluent configuration and init handshake.
 
-use std::sync::atomic::AtomicU8;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
-use tokio::sync::broadcast;
+use tokio::sync::watch;
 use tracing::{debug, info};
 
+use crate::capture::{CapturePort, CaptureWriter};
 use crate::error::{Error, Result};
 use crate::event::KeyerEvent;
 use crate::io::spawn_io_task;
 use crate::keyer::{KeyerCapabilities, KeyerInfo};
+use crate::profile::KeyerProfile;
 use crate::protocol::types::{
     LoadDefaults, ModeRegister, PaddleMode, PinConfig, WinKeyerVersion,
 };
+use crate::settings::KeyerSettings;
+use crate::speed_pot::{spawn_follower, PotFollower, SpeedPotPolicy};
 use crate::transport;
 use crate::winkeyer::WinKeyer;
 
@@ -50,7 +55,14 @@ pub struct WinKeyerBuilder {
     wpm_range: u8,
     farnsworth_wpm: u8,
     dit_dah_ratio: u8,
+    key_compensation: u8,
+    first_extension: u8,
+    paddle_switchpoint: u8,
+    x1_mode: u8,
+    x2_mode: u8,
     prefer_wk3: bool,
+    capture_path: Option<PathBuf>,
+    speed_pot_policy: SpeedPotPolicy,
 }
 
 impl WinKeyerBuilder {
@@ -70,10 +82,41 @@ impl WinKeyerBuilder {
             wpm_range: 25,
             farnsworth_wpm: 0,
             dit_dah_ratio: 50,
+            key_compensation: 0,
+            first_extension: 0,
+            paddle_switchpoint: 50,
+            x1_mode: 0,
+            x2_mode: 0,
             prefer_wk3: true,
+            capture_path: None,
+            speed_pot_policy: SpeedPotPolicy::default(),
         }
     }
 
+    /// Create a builder with every setting taken from `profile`.
+    pub fn from_profile(port_path: &str, profile: &KeyerProfile) -> Self {
+        Self::new(port_path)
+            .speed(profile.speed_wpm)
+            .paddle_mode(profile.paddle_mode)
+            .contest_spacing(profile.contest_spacing)
+            .auto_space(profile.auto_space)
+            .swap_paddles(profile.swap_paddles)
+            .sidetone(profile.sidetone_hz)
+            .weight(profile.weight)
+            .dit_dah_ratio(profile.dit_dah_ratio)
+            .farnsworth(profile.farnsworth_wpm)
+            .min_wpm(profile.min_wpm)
+            .wpm_range(profile.wpm_range)
+            .ptt_lead_in_ms(profile.ptt_lead_in_ms)
+            .ptt_tail_ms(profile.ptt_tail_ms)
+            .pin_config(profile.pin_config)
+            .key_compensation(profile.key_compensation_ms)
+            .first_extension(profile.first_extension_ms)
+            .paddle_switchpoint(profile.paddle_switchpoint)
+            .x1_mode(profile.x1_mode)
+            .x2_mode(profile.x2_mode)
+    }
+
     /// Set the initial CW speed in WPM (5-99).
     pub fn speed(mut self, wpm: u8) -> Self {
         self.speed_wpm = wpm;
@@ -164,18 +207,65 @@ impl WinKeyerBuilder {
         self
     }
 
+    /// Set key compensation in milliseconds (0-250, default 0), added to
+    /// every dit and dah. See [`QskTuning`](crate::timing::QskTuning).
+    pub fn key_compensation(mut self, ms: u8) -> Self {
+        self.key_compensation = ms;
+        self
+    }
+
+    /// Set first extension in milliseconds (0-250, default 0), added to the
+    /// first element after the keyer has been idle.
+    pub fn first_extension(mut self, ms: u8) -> Self {
+        self.first_extension = ms;
+        self
+    }
+
+    /// Set the paddle switchpoint (10-90, default 50): how far into an
+    /// element's trailing space the opposite paddle is still latched.
+    pub fn paddle_switchpoint(mut self, value: u8) -> Self {
+        self.paddle_switchpoint = value;
+        self
+    }
+
     /// Set pin configuration.
     pub fn pin_config(mut self, config: PinConfig) -> Self {
         self.pin_config = config;
         self
     }
 
+    /// Set the X1MODE extension register (default 0).
+    pub fn x1_mode(mut self, value: u8) -> Self {
+        self.x1_mode = value;
+        self
+    }
+
+    /// Set the X2MODE extension register (WK3, default 0).
+    pub fn x2_mode(mut self, value: u8) -> Self {
+        self.x2_mode = value;
+        self
+    }
+
     /// Whether to prefer WK3 mode if hardware supports it (default true).
     pub fn prefer_wk3(mut self, enabled: bool) -> Self {
         self.prefer_wk3 = enabled;
         self
     }
 
+    /// Record all serial traffic, including the init handshake, to a
+    /// capture file for later replay with [`ReplayPort`](crate::capture::ReplayPort).
+    pub fn capture(mut self, path: impl Into<PathBuf>) -> Self {
+        self.capture_path = Some(path.into());
+        self
+    }
+
+    /// What speed pot moves do to the sending speed (default: report only).
+    /// See [`speed_pot`](crate::speed_pot).
+    pub fn speed_pot_policy(mut self, policy: SpeedPotPolicy) -> Self {
+        self.speed_pot_policy = policy;
+        self
+    }
+
     /// Build the WinKeyer connection using a real serial port.
     pub async fn build(self) -> Result<WinKeyer> {
         let port = transport::open_serial(&self.port_path, 1200)?;
@@ -184,50 +274,28 @@ impl WinKeyerBuilder {
 
     /// Validate builder parameters against WinKeyer protocol limits.
     fn validate(&self) -> Result<()> {
-        if !(5..=99).contains(&self.speed_wpm) {
-            return Err(Error::InvalidParameter(format!(
-                "speed_wpm must be 5-99, got {}",
-                self.speed_wpm
-            )));
-        }
-        if !(10..=90).contains(&self.weight) {
-            return Err(Error::InvalidParameter(format!(
-                "weight must be 10-90, got {}",
-                self.weight
-            )));
-        }
-        if !(33..=66).contains(&self.dit_dah_ratio) {
-            return Err(Error::InvalidParameter(format!(
-                "dit_dah_ratio must be 33-66, got {}",
-                self.dit_dah_ratio
-            )));
-        }
-        if !(5..=99).contains(&self.min_wpm) {
-            return Err(Error::InvalidParameter(format!(
-                "min_wpm must be 5-99, got {}",
-                self.min_wpm
-            )));
-        }
-        if self.wpm_range < 1 {
-            return Err(Error::InvalidParameter(
-                "wpm_range must be at least 1".to_string(),
-            ));
-        }
-        if self.min_wpm.saturating_add(self.wpm_range) > 99 {
-            return Err(Error::InvalidParameter(format!(
-                "min_wpm ({}) + wpm_range ({}) exceeds max speed 99",
-                self.min_wpm, self.wpm_range
-            )));
-        }
-        Ok(())
+        validate_settings(
+            self.speed_wpm,
+            self.weight,
+            self.dit_dah_ratio,
+            self.min_wpm,
+            self.wpm_range,
+        )?;
+        validate_paddle_timing(
+            self.key_compensation,
+            self.first_extension,
+            self.paddle_switchpoint,
+        )
     }
 
     /// Build using a pre-opened port (for testing with MockPort).
-    pub async fn build_with_port<P>(self, mut port: P) -> Result<WinKeyer>
+    pub async fn build_with_port<P>(self, port: P) -> Result<WinKeyer>
     where
         P: AsyncRead + AsyncWrite + Send + Unpin + 'static,
     {
         self.validate()?;
+        let writer = self.capture_path.as_ref().map(CaptureWriter::create).transpose()?;
+        let mut port = CapturePort::new(port, writer);
 
         // Step 1: Defensive close + wait
         debug!("sending defensive host close");
@@ -286,11 +354,11 @@ impl WinKeyerBuilder {
                 Error::Transport(format!("failed to set WK3 mode: {e}"))
             })?;
 
-            // Step 4b: Clear X2MODE to disable paddle-only sidetone and other
-            // extended flags that may be stored in EEPROM from previous sessions.
-            debug!("clearing X2MODE");
-            port.write_all(&[0x00, 0x16, 0x00]).await.map_err(|e| {
-                Error::Transport(format!("failed to clear X2MODE: {e}"))
+            // Step 4b: Set X2MODE explicitly so paddle-only sidetone and other
+            // extended flags stored in EEPROM from previous sessions don't leak in.
+            debug!("setting X2MODE: 0x{:02X}", self.x2_mode);
+            port.write_all(&[0x00, 0x16, self.x2_mode]).await.map_err(|e| {
+                Error::Transport(format!("failed to set X2MODE: {e}"))
             })?;
 
         } else {
@@ -311,13 +379,13 @@ impl WinKeyerBuilder {
             tail_time: self.ptt_tail,
             min_wpm: self.min_wpm,
             wpm_range: self.wpm_range,
-            x2_mode: 0,
-            key_compensation: 0,
+            x2_mode: self.x2_mode,
+            key_compensation: self.key_compensation,
             farnsworth_wpm: self.farnsworth_wpm,
-            paddle_setpoint: 50,
+            paddle_setpoint: self.paddle_switchpoint,
             dit_dah_ratio: self.dit_dah_ratio,
             pin_config: self.pin_config.bits(),
-            x1_mode: 0,
+            x1_mode: self.x1_mode,
         };
 
         let cmd = crate::protocol::command::load_defaults(&defaults);
@@ -370,11 +438,33 @@ impl WinKeyerBuilder {
                 Error::Transport(format!("failed to set sidetone: {e}"))
             })?;
 
+        // First extension isn't part of Load Defaults.
+        if self.first_extension > 0 {
+            debug!("setting first extension: {}ms", self.first_extension);
+            port.write_all(&[0x10, self.first_extension])
+                .await
+                .map_err(|e| {
+                    Error::Transport(format!("failed to set first extension: {e}"))
+                })?;
+        }
+
         // Step 8: Spawn IO task
-        let (event_tx, _) = broadcast::channel::<KeyerEvent>(256);
+        let (event_tx, _) = crate::event::channel(256);
         let _ = event_tx.send(KeyerEvent::Connected);
 
         let io = spawn_io_task(port, event_tx.clone(), self.min_wpm);
+        let (settings, _) = watch::channel(KeyerSettings {
+            sidetone_hz: self.sidetone_hz,
+            first_extension: self.first_extension,
+            ..KeyerSettings::from_load_defaults(&defaults, version)
+        });
+        let speed_pot = Arc::new(Mutex::new(PotFollower::new(self.speed_pot_policy)));
+        let settings_task = spawn_follower(
+            event_tx.subscribe(),
+            settings.clone(),
+            speed_pot.clone(),
+            io.rt_tx.clone(),
+        );
 
         let version_str = format!(
             "WinKeyer {} (v{})",
@@ -402,15 +492,100 @@ impl WinKeyerBuilder {
                 buffered_speed: true,
                 farnsworth: true,
                 contest_spacing: true,
+                pause: true,
+                weight: true,
+                // WK2 mode reuses the keydown bit for pushbutton status
+                key_state: false,
             },
             version,
             event_tx,
-            speed: AtomicU8::new(self.speed_wpm),
-            mode_register: AtomicU8::new(defaults.mode_register),
+            settings,
+            settings_task,
+            speed_pot,
         })
     }
 }
 
+/// Range checks shared by [`WinKeyerBuilder`] and
+/// [`KeyerProfile::validate`](crate::profile::KeyerProfile::validate).
+pub(crate) fn validate_settings(
+    speed_wpm: u8,
+    weight: u8,
+    dit_dah_ratio: u8,
+    min_wpm: u8,
+    wpm_range: u8,
+) -> Result<()> {
+    if !(5..=99).contains(&speed_wpm) {
+        return Err(Error::InvalidParameter(format!(
+            "speed_wpm must be 5-99, got {}",
+            speed_wpm
+        )));
+    }
+    if !(10..=90).contains(&weight) {
+        return Err(Error::InvalidParameter(format!(
+            "weight must be 10-90, got {}",
+            weight
+        )));
+    }
+    if !(33..=66).contains(&dit_dah_ratio) {
+        return Err(Error::InvalidParameter(format!(
+            "dit_dah_ratio must be 33-66, got {}",
+            dit_dah_ratio
+        )));
+    }
+    validate_speed_pot_range(min_wpm, wpm_range)
+}
+
+/// Limits for key compensation, first extension (both 0-250ms) and the
+/// paddle switchpoint (10-90).
+pub(crate) fn validate_paddle_timing(
+    key_compensation: u8,
+    first_extension: u8,
+    paddle_switchpoint: u8,
+) -> Result<()> {
+    if key_compensation > 250 {
+        return Err(Error::InvalidParameter(format!(
+            "key_compensation must be 0-250 ms, got {}",
+            key_compensation
+        )));
+    }
+    if first_extension > 250 {
+        return Err(Error::InvalidParameter(format!(
+            "first_extension must be 0-250 ms, got {}",
+            first_extension
+        )));
+    }
+    if !(10..=90).contains(&paddle_switchpoint) {
+        return Err(Error::InvalidParameter(format!(
+            "paddle_switchpoint must be 10-90, got {}",
+            paddle_switchpoint
+        )));
+    }
+    Ok(())
+}
+
+/// Speed pot limits: the pot must span at least 1 WPM and top out at 99.
+pub(crate) fn validate_speed_pot_range(min_wpm: u8, wpm_range: u8) -> Result<()> {
+    if !(5..=99).contains(&min_wpm) {
+        return Err(Error::InvalidParameter(format!(
+            "min_wpm must be 5-99, got {}",
+            min_wpm
+        )));
+    }
+    if wpm_range < 1 {
+        return Err(Error::InvalidParameter(
+            "wpm_range must be at least 1".to_string(),
+        ));
+    }
+    if min_wpm.saturating_add(wpm_range) > 99 {
+        return Err(Error::InvalidParameter(format!(
+            "min_wpm ({}) + wpm_range ({}) exceeds max speed 99",
+            min_wpm, wpm_range
+        )));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,480 @@
+//! Wire-level session capture and replay.
+//!
+//! [`WinKeyerBuilder::capture`](crate::WinKeyerBuilder::capture) wraps the
+//! port owned by the IO task in a capture tap, which logs every byte
+//! written and read with a monotonic timestamp. [`ReplayPort`] plays such a
+//! log back as a transport with the original timing, so a session reported
+//! by a user can be reproduced against the crate with
+//! [`build_with_port`](crate::WinKeyerBuilder::build_with_port).
+//!
+//! Captures are plain text, one record per line:
+//!
+//! ```text
+//! # winkey capture v1
+//! 0.000000 > 00 03
+//! 0.312004 < 1F
+//! 0.845120 < C4
+//! ```
+//!
+//! `>` is host → keyer, `<` is keyer → host; the time is seconds since the
+//! capture started.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{mpsc, Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::time::{Instant, Sleep};
+
+use crate::error::{Error, Result};
+
+const HEADER: &str = "# winkey capture v1";
+
+/// Which way a captured chunk travelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Written by the host to the keyer.
+    Host,
+    /// Read by the host from the keyer.
+    Keyer,
+}
+
+/// One chunk of bytes seen on the wire.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureRecord {
+    /// Time since the capture started.
+    pub at: Duration,
+    pub direction: Direction,
+    pub data: Vec<u8>,
+}
+
+impl fmt::Display for CaptureRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arrow = match self.direction {
+            Direction::Host => '>',
+            Direction::Keyer => '<',
+        };
+        write!(f, "{:.6} {arrow}", self.at.as_secs_f64())?;
+        for byte in &self.data {
+            write!(f, " {byte:02X}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A recorded session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capture {
+    pub records: Vec<CaptureRecord>,
+}
+
+impl Capture {
+    /// Read a capture file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse capture text. Blank lines and `#` comments are skipped.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut records = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let bad = || Error::Protocol(format!("capture line {}: {line:?}", n + 1));
+            let mut fields = line.split_whitespace();
+            let at = fields
+                .next()
+                .and_then(|t| t.parse().ok())
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                .ok_or_else(bad)?;
+            let direction = match fields.next() {
+                Some(">") => Direction::Host,
+                Some("<") => Direction::Keyer,
+                _ => return Err(bad()),
+            };
+            let data = fields
+                .map(|h| u8::from_str_radix(h, 16))
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|_| bad())?;
+            records.push(CaptureRecord {
+                at,
+                direction,
+                data,
+            });
+        }
+        Ok(Self { records })
+    }
+
+    /// All bytes in one direction, concatenated.
+    pub fn bytes(&self, direction: Direction) -> Vec<u8> {
+        self.records
+            .iter()
+            .filter(|r| r.direction == direction)
+            .flat_map(|r| r.data.iter().copied())
+            .collect()
+    }
+}
+
+impl fmt::Display for Capture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        for record in &self.records {
+            writeln!(f, "{record}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Appends records to a capture file from a background thread, so the IO
+/// task never blocks on disk.
+pub(crate) struct CaptureWriter {
+    tx: mpsc::Sender<CaptureRecord>,
+}
+
+impl CaptureWriter {
+    /// Create (or truncate) `path` and start the writer thread.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "{HEADER}")?;
+        out.flush()?;
+        let (tx, rx) = mpsc::channel::<CaptureRecord>();
+        std::thread::spawn(move || {
+            for record in rx {
+                // Flush per record so a crash still leaves a usable capture.
+                if writeln!(out, "{record}").and_then(|_| out.flush()).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self { tx })
+    }
+
+    fn record(&self, at: Duration, direction: Direction, data: &[u8]) {
+        let _ = self.tx.send(CaptureRecord {
+            at,
+            direction,
+            data: data.to_vec(),
+        });
+    }
+}
+
+/// Transport wrapper that records traffic through the inner port.
+///
+/// With no writer it passes bytes straight through.
+pub(crate) struct CapturePort<P> {
+    inner: P,
+    writer: Option<CaptureWriter>,
+    start: Instant,
+}
+
+impl<P> CapturePort<P> {
+    pub fn new(inner: P, writer: Option<CaptureWriter>) -> Self {
+        Self {
+            inner,
+            writer,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl<P: AsyncRead + Unpin> AsyncRead for CapturePort<P> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let this = &mut *self;
+        let result = Pin::new(&mut this.inner).poll_read(cx, buf);
+        if let (Poll::Ready(Ok(())), Some(writer)) = (&result, &this.writer) {
+            let data = &buf.filled()[before..];
+            if !data.is_empty() {
+                writer.record(this.start.elapsed(), Direction::Keyer, data);
+            }
+        }
+        result
+    }
+}
+
+impl<P: AsyncWrite + Unpin> AsyncWrite for CapturePort<P> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let result = Pin::new(&mut this.inner).poll_write(cx, data);
+        if let (Poll::Ready(Ok(n @ 1..)), Some(writer)) = (&result, &this.writer) {
+            writer.record(this.start.elapsed(), Direction::Host, &data[..*n]);
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Shared state for the replay port.
+struct ReplayState {
+    /// Keyer → host records still to deliver.
+    pending: std::collections::VecDeque<CaptureRecord>,
+    /// Host → keyer bytes from the capture, for comparison.
+    expected: Vec<u8>,
+    /// Bytes the host wrote during replay.
+    written: Vec<u8>,
+    start: Option<Instant>,
+    waker: Option<Waker>,
+}
+
+/// Transport that replays the keyer side of a [`Capture`].
+///
+/// Bytes the keyer sent are returned by reads at their recorded offsets
+/// from the first read or write; after the last one, reads stay pending
+/// like a quiet port. Writes are accepted and compared against what the host originally
+/// wrote (see [`first_mismatch`](Self::first_mismatch)).
+pub struct ReplayPort {
+    state: Arc<Mutex<ReplayState>>,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl Clone for ReplayPort {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            sleep: None,
+        }
+    }
+}
+
+impl fmt::Debug for ReplayPort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("ReplayPort")
+            .field("pending", &state.pending.len())
+            .field("written", &state.written.len())
+            .finish()
+    }
+}
+
+impl ReplayPort {
+    /// Replay `capture`.
+    pub fn new(capture: &Capture) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ReplayState {
+                pending: capture
+                    .records
+                    .iter()
+                    .filter(|r| r.direction == Direction::Keyer)
+                    .cloned()
+                    .collect(),
+                expected: capture.bytes(Direction::Host),
+                written: Vec::new(),
+                start: None,
+                waker: None,
+            })),
+            sleep: None,
+        }
+    }
+
+    /// Replay a capture file.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(&Capture::load(path)?))
+    }
+
+    /// Bytes written by the host so far.
+    pub fn written_data(&self) -> Vec<u8> {
+        self.state.lock().unwrap().written.clone()
+    }
+
+    /// Offset of the first written byte that differs from the capture, if
+    /// the replayed host has diverged from the original.
+    pub fn first_mismatch(&self) -> Option<usize> {
+        let state = self.state.lock().unwrap();
+        state
+            .written
+            .iter()
+            .zip(&state.expected)
+            .position(|(a, b)| a != b)
+            .or_else(|| (state.written.len() > state.expected.len()).then_some(state.expected.len()))
+    }
+
+    /// True once every recorded keyer byte has been delivered.
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().unwrap().pending.is_empty()
+    }
+}
+
+impl AsyncRead for ReplayPort {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let due = {
+            let mut state = this.state.lock().unwrap();
+            let start = *state.start.get_or_insert_with(Instant::now);
+            let Some(next) = state.pending.front_mut() else {
+                state.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            };
+            let due = start + next.at;
+            if Instant::now() >= due {
+                let n = next.data.len().min(buf.remaining());
+                buf.put_slice(&next.data[..n]);
+                next.data.drain(..n);
+                if next.data.is_empty() {
+                    state.pending.pop_front();
+                }
+                this.sleep = None;
+                return Poll::Ready(Ok(()));
+            }
+            due
+        };
+
+        let sleep = this.sleep.get_or_insert_with(|| Box::pin(tokio::time::sleep_until(due)));
+        sleep.as_mut().reset(due);
+        match sleep.as_mut().poll(cx) {
+            Poll::Ready(()) => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl AsyncWrite for ReplayPort {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut state = self.state.lock().unwrap();
+        state.start.get_or_insert_with(Instant::now);
+        state.written.extend_from_slice(data);
+        Poll::Ready(Ok(data.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MockPort;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let capture = Capture {
+            records: vec![
+                CaptureRecord {
+                    at: ms(0),
+                    direction: Direction::Host,
+                    data: vec![0x00, 0x02],
+                },
+                CaptureRecord {
+                    at: Duration::from_micros(312_004),
+                    direction: Direction::Keyer,
+                    data: vec![0x1F],
+                },
+            ],
+        };
+        let text = capture.to_string();
+        assert_eq!(text, "# winkey capture v1\n0.000000 > 00 02\n0.312004 < 1F\n");
+        assert_eq!(Capture::parse(&text).unwrap(), capture);
+        assert!(Capture::parse("0.1 ? 00").is_err());
+        assert!(Capture::parse("0.1 > ZZ").is_err());
+        for time in ["-1", "inf", "NaN"] {
+            assert!(Capture::parse(&format!("{time} > 00")).is_err(), "{time}");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn capture_port_records_both_directions() {
+        let path = std::env::temp_dir().join(format!("winkey-capture-{}.txt", std::process::id()));
+        let mock = MockPort::new();
+        let mut port = CapturePort::new(mock.clone(), Some(CaptureWriter::create(&path).unwrap()));
+
+        port.write_all(&[0x00, 0x02]).await.unwrap();
+        tokio::time::sleep(ms(250)).await;
+        mock.queue_read(&[0x1F]);
+        let mut byte = [0u8; 1];
+        port.read_exact(&mut byte).await.unwrap();
+        drop(port);
+
+        // Give the writer thread a moment (of real time) to drain.
+        tokio::time::resume();
+        let mut capture = Capture::default();
+        for _ in 0..100 {
+            capture = Capture::load(&path).unwrap();
+            if capture.records.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(ms(5)).await;
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(capture.records.len(), 2);
+        assert_eq!(capture.records[0].direction, Direction::Host);
+        assert_eq!(capture.records[0].data, vec![0x00, 0x02]);
+        assert_eq!(capture.records[1].direction, Direction::Keyer);
+        assert_eq!(capture.records[1].at, ms(250));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn replay_keeps_original_timing() {
+        let capture = Capture::parse("0.000 > 00 02\n0.200 < 1F\n0.500 < C4 43\n").unwrap();
+        let mut port = ReplayPort::new(&capture);
+        let start = Instant::now();
+
+        port.write_all(&[0x00, 0x02]).await.unwrap();
+        let mut buf = [0u8; 8];
+        let n = port.read(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], &[0x1F]);
+        assert_eq!(start.elapsed(), ms(200));
+        let n = port.read(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], &[0xC4, 0x43]);
+        assert_eq!(start.elapsed(), ms(500));
+        assert!(port.is_finished());
+        assert_eq!(port.first_mismatch(), None);
+
+        // Quiet after the last record
+        let quiet = tokio::time::timeout(Duration::from_secs(10), port.read(&mut buf)).await;
+        assert!(quiet.is_err());
+    }
+
+    #[tokio::test]
+    async fn replay_reports_divergence() {
+        let capture = Capture::parse("0.0 > 02 14\n").unwrap();
+        let mut port = ReplayPort::new(&capture);
+        port.write_all(&[0x02, 0x19]).await.unwrap();
+        assert_eq!(port.first_mismatch(), Some(1));
+    }
+}
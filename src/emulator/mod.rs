@@ -0,0 +1,460 @@
+//! Virtual WinKeyer for legacy host software.
+//!
+//! [`WinKeyerEmulator`] speaks the WinKeyer host protocol on a byte stream
+//! (on Linux, typically the pseudo-terminal from [`PtyBridge`]) so a
+//! third-party program such as N1MM under Wine can drive the physical keyer
+//! while this crate keeps its own connection open. Commands from the legacy
+//! program are parsed and forwarded through the shared [`WinKeyer`]; status,
+//! speed pot and echo bytes are mirrored back from its events.
+//!
+//! Commands that would disturb the shared session (reset, host close, baud
+//! and mode switches, EEPROM and firmware access) are answered locally or
+//! ignored. The legacy program's echo settings only apply to what it is
+//! sent: serial and paddle echo stay on at the keyer, since this crate's
+//! echo tracking depends on them.
+
+#[cfg(target_os = "linux")]
+mod pty;
+
+#[cfg(target_os = "linux")]
+pub use pty::PtyBridge;
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::error::Result;
+use crate::event::{EchoSource, EventReceiver, KeyerEvent, KeyerStatus};
+use crate::keyer::Keyer;
+use crate::protocol::decoder::{Command, CommandDecoder};
+use crate::winkeyer::WinKeyer;
+
+/// Mode register bit enabling serial echo-back.
+const MODE_SERIAL_ECHO: u8 = 0x04;
+/// Mode register bit enabling paddle echo-back.
+const MODE_PADDLE_ECHO: u8 = 0x40;
+
+/// Bytes the legacy program will see, derived from keyer state.
+#[derive(Debug, Clone, Copy)]
+struct Mirror {
+    open: bool,
+    echo: bool,
+    paddle_echo: bool,
+    status: KeyerStatus,
+}
+
+impl Mirror {
+    fn set_mode(&mut self, mode: u8) {
+        self.echo = mode & MODE_SERIAL_ECHO != 0;
+        self.paddle_echo = mode & MODE_PADDLE_ECHO != 0;
+    }
+
+    /// Whether the legacy program asked for echoes from `source`.
+    fn echoes(&self, source: EchoSource) -> bool {
+        match source {
+            EchoSource::Host => self.echo,
+            EchoSource::Paddle => self.paddle_echo,
+        }
+    }
+}
+
+fn status_byte(s: &KeyerStatus) -> u8 {
+    0xC0 | s.xoff as u8
+        | (s.breakin as u8) << 1
+        | (s.busy as u8) << 2
+        | (s.keydown as u8) << 3
+        | (s.waiting as u8) << 4
+}
+
+/// Serves the WinKeyer host protocol to a legacy program, multiplexed onto
+/// a shared [`WinKeyer`].
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use winkey::{WinKeyerBuilder, emulator::WinKeyerEmulator};
+/// # async fn example(stream: tokio::io::DuplexStream) -> winkey::Result<()> {
+/// let keyer = Arc::new(WinKeyerBuilder::new("/dev/ttyUSB0").build().await?);
+/// WinKeyerEmulator::new(keyer.clone()).serve(stream).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WinKeyerEmulator {
+    keyer: Arc<WinKeyer>,
+}
+
+impl WinKeyerEmulator {
+    /// Emulate on behalf of `keyer`.
+    pub fn new(keyer: Arc<WinKeyer>) -> Self {
+        Self { keyer }
+    }
+
+    /// Serve one legacy client until its stream closes.
+    ///
+    /// The shared keyer stays open afterwards, even if the client sent a
+    /// host close.
+    pub async fn serve<S>(&self, stream: S) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (mut rd, mut wr) = tokio::io::split(stream);
+        let mirror = Arc::new(std::sync::Mutex::new(Mirror {
+            open: false,
+            echo: false,
+            paddle_echo: false,
+            status: KeyerStatus::from_status_byte(0xC0),
+        }));
+
+        let (out_tx, mut out_rx) = mpsc::channel::<Vec<u8>>(64);
+        let writer = tokio::spawn(async move {
+            while let Some(bytes) = out_rx.recv().await {
+                if wr.write_all(&bytes).await.is_err() || wr.flush().await.is_err() {
+                    break;
+                }
+            }
+        });
+        let mirrorer = tokio::spawn(mirror_events(
+            self.keyer.clone(),
+            self.keyer.subscribe(),
+            mirror.clone(),
+            out_tx.clone(),
+        ));
+        let (rt_tx, rt_worker) = spawn_worker(self.keyer.clone(), out_tx.clone(), false);
+        let (bg_tx, bg_worker) = spawn_worker(self.keyer.clone(), out_tx.clone(), true);
+
+        let mut decoder = CommandDecoder::new();
+        let mut chunk = [0u8; 256];
+        let result = 'outer: loop {
+            let n = match rd.read(&mut chunk).await {
+                Ok(0) => break Ok(()),
+                Ok(n) => n,
+                Err(e) => break Err(e.into()),
+            };
+            decoder.push(&chunk[..n]);
+            while let Some(mut cmd) = decoder.next_command() {
+                let queue = match self.handle(&mut cmd, &mirror, &out_tx).await {
+                    Route::Local => continue,
+                    Route::Immediate => &rt_tx,
+                    Route::Buffered => &bg_tx,
+                };
+                self.keyer.note_command(&cmd);
+                if queue.send(cmd).await.is_err() {
+                    break 'outer Ok(());
+                }
+            }
+        };
+
+        mirrorer.abort();
+        rt_worker.abort();
+        bg_worker.abort();
+        writer.abort();
+        result
+    }
+
+    /// Apply local effects of `cmd`, adjust it for the shared keyer, and
+    /// decide where it goes.
+    async fn handle(
+        &self,
+        cmd: &mut Command,
+        mirror: &std::sync::Mutex<Mirror>,
+        out_tx: &mpsc::Sender<Vec<u8>>,
+    ) -> Route {
+        let open = mirror.lock().unwrap().open;
+        match cmd {
+            Command::HostOpen => {
+                mirror.lock().unwrap().open = true;
+                let version = self.keyer.info().version.parse::<u8>().unwrap_or(23);
+                debug!("legacy host open");
+                let _ = out_tx.send(vec![version]).await;
+                Route::Local
+            }
+            Command::HostClose => {
+                mirror.lock().unwrap().open = false;
+                debug!("legacy host close");
+                Route::Local
+            }
+            Command::EchoTest(byte) => {
+                let _ = out_tx.send(vec![*byte]).await;
+                Route::Local
+            }
+            Command::Reset
+            | Command::SetWk1Mode
+            | Command::SetWk2Mode
+            | Command::SetWk3Mode
+            | Command::SetLowBaud
+            | Command::SetHighBaud
+            | Command::DumpEeprom
+            | Command::LoadEeprom(_)
+            | Command::FirmwareUpdate
+            | Command::AdminReserved
+            | Command::UnknownAdmin(_)
+            | Command::Unknown(_) => {
+                debug!(?cmd, "legacy command not passed to shared keyer");
+                Route::Local
+            }
+            _ if !open => {
+                debug!(?cmd, "legacy command ignored while host closed");
+                Route::Local
+            }
+            Command::SetSpeed(wpm) if (5..=99).contains(wpm) => {
+                // Keep the shared keyer's cached speed in step.
+                if let Err(e) = self.keyer.set_speed(*wpm).await {
+                    warn!("legacy set speed failed: {e}");
+                }
+                Route::Local
+            }
+            Command::SetSpeedPot { min, .. } => {
+                self.keyer.io.min_wpm.store(*min, Ordering::Release);
+                Route::Immediate
+            }
+            Command::SetModeRegister(mode) => {
+                mirror.lock().unwrap().set_mode(*mode);
+                *mode |= MODE_SERIAL_ECHO | MODE_PADDLE_ECHO;
+                Route::Immediate
+            }
+            Command::LoadDefaults(defaults) => {
+                mirror.lock().unwrap().set_mode(defaults.mode_register);
+                defaults.mode_register |= MODE_SERIAL_ECHO | MODE_PADDLE_ECHO;
+                self.keyer.io.min_wpm.store(defaults.min_wpm, Ordering::Release);
+                Route::Immediate
+            }
+            Command::RequestStatus => {
+                let byte = status_byte(&mirror.lock().unwrap().status);
+                let _ = out_tx.send(vec![byte]).await;
+                Route::Local
+            }
+            cmd if cmd.is_buffered() => Route::Buffered,
+            _ => Route::Immediate,
+        }
+    }
+}
+
+/// Where a parsed command is sent.
+enum Route {
+    /// Fully handled by the emulator.
+    Local,
+    /// Real-time path to the keyer.
+    Immediate,
+    /// Buffered path (waits for XOFF to clear).
+    Buffered,
+}
+
+/// Forward commands from one queue in order.
+fn spawn_worker(
+    keyer: Arc<WinKeyer>,
+    out_tx: mpsc::Sender<Vec<u8>>,
+    buffered: bool,
+) -> (mpsc::Sender<Command>, JoinHandle<()>) {
+    let (tx, mut rx) = mpsc::channel::<Command>(64);
+    let task = tokio::spawn(async move {
+        while let Some(cmd) = rx.recv().await {
+            let bytes = cmd.encode();
+            let result = if buffered {
+                keyer.raw_write(&bytes).await
+            } else if let Some(len) = cmd.reply_len() {
+                // The reply is routed back to the legacy program.
+                match keyer.io.rt_command_read_binary(bytes, len).await {
+                    Ok(reply) => {
+                        let _ = out_tx.send(reply).await;
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                }
+            } else {
+                keyer.raw_write_rt(&bytes).await
+            };
+            if let Err(e) = result {
+                warn!(?cmd, "legacy command failed: {e}");
+            }
+        }
+    });
+    (tx, task)
+}
+
+/// Translate keyer events into the bytes a real WinKeyer would send.
+async fn mirror_events(
+    keyer: Arc<WinKeyer>,
+    mut events: EventReceiver,
+    mirror: Arc<std::sync::Mutex<Mirror>>,
+    out_tx: mpsc::Sender<Vec<u8>>,
+) {
+    loop {
+        let event = match events.recv().await {
+            Ok(KeyerEvent::EventsDropped { count }) => {
+                warn!("emulator lagged by {count} events");
+                continue;
+            }
+            Ok(event) => event,
+            Err(_) => return,
+        };
+        let byte = {
+            let mut m = mirror.lock().unwrap();
+            if let KeyerEvent::StatusChanged(status) = event {
+                m.status = status;
+            }
+            if !m.open {
+                continue;
+            }
+            match event {
+                KeyerEvent::StatusChanged(status) => status_byte(&status),
+                KeyerEvent::SpeedPotChanged { wpm } => {
+                    let min = keyer.io.min_wpm.load(Ordering::Acquire);
+                    0x80 | (wpm.saturating_sub(min) & 0x3F)
+                }
+                KeyerEvent::CharacterSent { ch, source } if m.echoes(source) && ch.is_ascii() => {
+                    ch as u8
+                }
+                _ => continue,
+            }
+        };
+        // Drop bytes rather than stall if the client stopped reading.
+        if let Err(mpsc::error::TrySendError::Closed(_)) = out_tx.try_send(vec![byte]) {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+    use super::*;
+    use crate::builder::WinKeyerBuilder;
+    use crate::transport::MockPort;
+
+    async fn shared_keyer() -> (Arc<WinKeyer>, MockPort) {
+        let mock = MockPort::new();
+        let clone = mock.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            clone.queue_read(&[31]);
+        });
+        let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+            .build_with_port(mock.clone())
+            .await
+            .unwrap();
+        (Arc::new(keyer), mock)
+    }
+
+    async fn legacy_client(keyer: Arc<WinKeyer>) -> DuplexStream {
+        let (ours, theirs) = tokio::io::duplex(1024);
+        let emulator = WinKeyerEmulator::new(keyer);
+        tokio::spawn(async move { emulator.serve(ours).await });
+        theirs
+    }
+
+    async fn read_byte(client: &mut DuplexStream) -> u8 {
+        tokio::time::timeout(Duration::from_secs(1), client.read_u8())
+            .await
+            .expect("no reply from emulator")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn host_open_and_echo_answered_locally() {
+        let (keyer, mock) = shared_keyer().await;
+        let before = mock.written_data().len();
+        let mut client = legacy_client(keyer).await;
+
+        client.write_all(&[0x00, 0x04, 0xA5]).await.unwrap();
+        assert_eq!(read_byte(&mut client).await, 0xA5);
+        client.write_all(&[0x00, 0x02]).await.unwrap();
+        assert_eq!(read_byte(&mut client).await, 31);
+        client.write_all(&[0x00, 0x03]).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(mock.written_data().len(), before);
+    }
+
+    #[tokio::test]
+    async fn commands_forwarded_to_shared_keyer() {
+        let (keyer, mock) = shared_keyer().await;
+        let mut client = legacy_client(keyer.clone()).await;
+
+        client.write_all(&[0x00, 0x02]).await.unwrap();
+        read_byte(&mut client).await;
+        client.write_all(&[0x02, 32, 0x0E, 0x44]).await.unwrap();
+        client.write_all(b"CQ").await.unwrap();
+        client.write_all(&[0x1B, b'A', b'R', 0x0A]).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let written = mock.written_data();
+        assert!(written.windows(2).any(|w| w == [0x02, 32]));
+        assert!(written.windows(2).any(|w| w == [0x0E, 0x44]));
+        assert!(written.windows(2).any(|w| w == b"CQ"));
+        assert!(written.windows(3).any(|w| w == [0x1B, b'A', b'R']));
+        assert!(written.contains(&0x0A));
+        assert_eq!(keyer.get_speed().await.unwrap(), 32);
+        assert_eq!(keyer.settings().mode_register, 0x44);
+    }
+
+    #[tokio::test]
+    async fn keyer_events_mirrored_to_client() {
+        let (keyer, mock) = shared_keyer().await;
+        let mut client = legacy_client(keyer).await;
+
+        client.write_all(&[0x00, 0x02, 0x0E, MODE_SERIAL_ECHO]).await.unwrap();
+        read_byte(&mut client).await;
+        client.write_all(b"K").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Busy status, an echoed character and a speed pot reading
+        mock.queue_read(&[0xC4, b'K', 0x85]);
+        assert_eq!(read_byte(&mut client).await, 0xC4);
+        assert_eq!(read_byte(&mut client).await, b'K');
+        assert_eq!(read_byte(&mut client).await, 0x85);
+
+        client.write_all(&[0x15]).await.unwrap();
+        assert_eq!(read_byte(&mut client).await, 0xC4);
+    }
+
+    #[tokio::test]
+    async fn client_echo_off_keeps_keyer_echo() {
+        let (keyer, mock) = shared_keyer().await;
+        let mut client = legacy_client(keyer.clone()).await;
+        let mut rx = keyer.subscribe();
+
+        client.write_all(&[0x00, 0x02, 0x0E, 0x00]).await.unwrap();
+        read_byte(&mut client).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(mock.written_data().windows(2).any(|w| w == [0x0E, 0x44]));
+        assert_eq!(keyer.settings().mode_register & 0x44, 0x44);
+
+        mock.queue_read(b"K");
+        loop {
+            let event = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            if let KeyerEvent::CharacterSent { ch, .. } = event {
+                assert_eq!(ch, 'K');
+                break;
+            }
+        }
+        // Not echoed to the client: the next byte it sees is the status
+        client.write_all(&[0x15]).await.unwrap();
+        assert_eq!(read_byte(&mut client).await & 0xC0, 0xC0);
+    }
+
+    #[tokio::test]
+    async fn disruptive_admin_commands_ignored() {
+        let (keyer, mock) = shared_keyer().await;
+        let mut client = legacy_client(keyer).await;
+        client.write_all(&[0x00, 0x02]).await.unwrap();
+        read_byte(&mut client).await;
+        let before = mock.written_data().len();
+
+        // Reset, WK1 mode, high baud
+        client.write_all(&[0x00, 0x01, 0x00, 0x0A, 0x00, 0x12]).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(mock.written_data().len(), before);
+    }
+}
@@ -0,0 +1,223 @@
+//! Pseudo-terminal transport for the emulator (Linux).
+
+use std::io;
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+use nix::fcntl::{fcntl, FcntlArg, OFlag};
+use nix::pty::openpty;
+use nix::sys::termios::{cfmakeraw, tcgetattr, tcsetattr, SetArg};
+use tokio::io::unix::AsyncFd;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use super::WinKeyerEmulator;
+use crate::error::{Error, Result};
+use crate::winkeyer::WinKeyer;
+
+/// A PTY whose slave end looks like a WinKeyer serial port.
+///
+/// Point the legacy program at [`path`](PtyBridge::path) (under Wine, link
+/// `~/.wine/dosdevices/comN` to it, or use [`symlink`](PtyBridge::symlink)
+/// for a stable name). The program can open and close the port as often as
+/// it likes; the bridge keeps serving until dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use winkey::{WinKeyerBuilder, emulator::PtyBridge};
+/// # async fn example() -> winkey::Result<()> {
+/// let keyer = Arc::new(WinKeyerBuilder::new("/dev/ttyUSB0").build().await?);
+/// let mut bridge = PtyBridge::open(keyer.clone())?;
+/// bridge.symlink("/tmp/winkeyer")?;
+/// println!("legacy port: {}", bridge.path().display());
+/// # Ok(())
+/// # }
+/// ```
+pub struct PtyBridge {
+    path: PathBuf,
+    link: Option<PathBuf>,
+    task: JoinHandle<()>,
+    /// Held open so the master never sees EIO between client sessions.
+    _slave: OwnedFd,
+}
+
+impl std::fmt::Debug for PtyBridge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PtyBridge")
+            .field("path", &self.path)
+            .field("link", &self.link)
+            .finish()
+    }
+}
+
+impl PtyBridge {
+    /// Create the PTY and start emulating on behalf of `keyer`.
+    pub fn open(keyer: Arc<WinKeyer>) -> Result<Self> {
+        let pty = openpty(None, None).map_err(|e| Error::Transport(format!("openpty: {e}")))?;
+        let path = nix::unistd::ttyname(&pty.slave)
+            .map_err(|e| Error::Transport(format!("ttyname: {e}")))?;
+
+        // Raw mode: no echo, no line discipline, 8-bit clean.
+        let mut termios =
+            tcgetattr(&pty.slave).map_err(|e| Error::Transport(format!("tcgetattr: {e}")))?;
+        cfmakeraw(&mut termios);
+        tcsetattr(&pty.slave, SetArg::TCSANOW, &termios)
+            .map_err(|e| Error::Transport(format!("tcsetattr: {e}")))?;
+
+        let master = PtyMaster::new(pty.master)?;
+        let emulator = WinKeyerEmulator::new(keyer);
+        let task = tokio::spawn(async move {
+            if let Err(e) = emulator.serve(master).await {
+                warn!("PTY emulator stopped: {e}");
+            }
+        });
+        debug!("virtual WinKeyer on {}", path.display());
+
+        Ok(Self {
+            path,
+            link: None,
+            task,
+            _slave: pty.slave,
+        })
+    }
+
+    /// Path of the slave device, e.g. `/dev/pts/4`.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Create a symlink at `link` pointing to the slave device, replacing
+    /// any existing symlink there. It is removed when the bridge is dropped.
+    pub fn symlink(&mut self, link: impl Into<PathBuf>) -> Result<()> {
+        let link = link.into();
+        if link.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+            std::fs::remove_file(&link)?;
+        }
+        std::os::unix::fs::symlink(&self.path, &link)?;
+        self.link = Some(link);
+        Ok(())
+    }
+}
+
+impl Drop for PtyBridge {
+    fn drop(&mut self) {
+        self.task.abort();
+        if let Some(link) = self.link.take() {
+            let _ = std::fs::remove_file(link);
+        }
+    }
+}
+
+/// Non-blocking async wrapper around the PTY master.
+struct PtyMaster {
+    fd: AsyncFd<OwnedFd>,
+}
+
+impl PtyMaster {
+    fn new(fd: OwnedFd) -> Result<Self> {
+        let flags = fcntl(fd.as_raw_fd(), FcntlArg::F_GETFL)
+            .map_err(|e| Error::Transport(format!("fcntl: {e}")))?;
+        let flags = OFlag::from_bits_truncate(flags) | OFlag::O_NONBLOCK;
+        fcntl(fd.as_raw_fd(), FcntlArg::F_SETFL(flags))
+            .map_err(|e| Error::Transport(format!("fcntl: {e}")))?;
+        Ok(Self {
+            fd: AsyncFd::new(fd)?,
+        })
+    }
+}
+
+impl AsyncRead for PtyMaster {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        loop {
+            let mut guard = ready!(self.fd.poll_read_ready(cx))?;
+            let unfilled = buf.initialize_unfilled();
+            match guard.try_io(|fd| {
+                nix::unistd::read(fd.as_raw_fd(), unfilled).map_err(io::Error::from)
+            }) {
+                Ok(Ok(n)) => {
+                    buf.advance(n);
+                    return Poll::Ready(Ok(()));
+                }
+                Ok(Err(e)) => return Poll::Ready(Err(e)),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
+
+impl AsyncWrite for PtyMaster {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            let mut guard = ready!(self.fd.poll_write_ready(cx))?;
+            match guard.try_io(|fd| nix::unistd::write(fd.get_ref(), data).map_err(io::Error::from)) {
+                Ok(result) => return Poll::Ready(result),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::builder::WinKeyerBuilder;
+    use crate::transport::MockPort;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn legacy_program_opens_pty() {
+        let mock = MockPort::new();
+        let clone = mock.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            clone.queue_read(&[30]);
+        });
+        let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+            .build_with_port(mock)
+            .await
+            .unwrap();
+        let bridge = PtyBridge::open(Arc::new(keyer)).unwrap();
+        let path = bridge.path().to_path_buf();
+
+        let version = tokio::task::spawn_blocking(move || {
+            let mut port = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(path)
+                .unwrap();
+            port.write_all(&[0x00, 0x02]).unwrap();
+            let mut byte = [0u8; 1];
+            port.read_exact(&mut byte).unwrap();
+            byte[0]
+        });
+        let version = tokio::time::timeout(Duration::from_secs(2), version)
+            .await
+            .expect("no host open reply over PTY")
+            .unwrap();
+        assert_eq!(version, 30);
+    }
+}
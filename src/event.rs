@@ -1,4 +1,16 @@
 //! Event types emitted by the keyer.
+//!
+//! Backends send events through an [`EventSender`], which stamps each one
+//! with a sequence number and a monotonic time as it is sent.
+//! [`Keyer::subscribe`](crate::Keyer::subscribe) hands out an
+//! [`EventReceiver`]; a subscriber that falls behind gets
+//! [`KeyerEvent::EventsDropped`] in place of the events it missed rather
+//! than an error.
+
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::broadcast::{self, error::RecvError, error::SendError, error::TryRecvError};
+use tokio::time::Instant;
 
 /// Current status of the keyer hardware, decoded from WinKeyer status bytes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -30,6 +42,15 @@ impl KeyerStatus {
     }
 }
 
+/// Where an echoed character came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EchoSource {
+    /// Text queued by the host (serial echo).
+    Host,
+    /// Sent by the operator on the paddles (paddle echo).
+    Paddle,
+}
+
 /// Events emitted by any keyer backend via broadcast channel.
 #[derive(Debug, Clone)]
 pub enum KeyerEvent {
@@ -40,7 +61,13 @@ pub enum KeyerEvent {
     SpeedPotChanged { wpm: u8 },
 
     /// A character was sent (echo-back from WinKeyer).
-    CharacterSent(char),
+    CharacterSent { ch: char, source: EchoSource },
+
+    /// A word sent on the paddles, complete at the word space or when
+    /// break-in ends. Its characters were also reported as
+    /// [`CharacterSent`](KeyerEvent::CharacterSent) with
+    /// [`EchoSource::Paddle`].
+    PaddleWord(String),
 
     /// Paddle break-in detected (breakin bit 0→1 transition).
     PaddleBreakIn,
@@ -50,12 +77,259 @@ pub enum KeyerEvent {
 
     /// Connection to keyer hardware lost.
     Disconnected,
+
+    /// `count` events were discarded because a subscriber fell behind.
+    /// Generated by [`EventReceiver`] for its own lag. Relays
+    /// ([`KeyerSwitch`](crate::KeyerSwitch),
+    /// [`RemoteKeyer`](crate::RemoteKeyer)) also pass on the losses they saw
+    /// upstream, as an ordinary event in their own sequence; `count` then
+    /// refers to the upstream stream.
+    EventsDropped { count: u64 },
+}
+
+/// An event with its place in the stream.
+#[derive(Debug, Clone)]
+pub struct TimedEvent {
+    /// Sequence number, counting up from 0 per sender. For
+    /// [`KeyerEvent::EventsDropped`], the number of the first event lost.
+    pub seq: u64,
+    /// When the event was sent (by the original backend, for events
+    /// passed on with [`EventSender::forward`]). For `EventsDropped`, when
+    /// the loss was noticed.
+    pub at: Instant,
+    pub event: KeyerEvent,
+}
+
+/// Sending half of a keyer's event stream. Cloning shares the sequence.
+#[derive(Debug, Clone)]
+pub struct EventSender {
+    tx: broadcast::Sender<TimedEvent>,
+    /// Next sequence number. Held while sending so that concurrent senders
+    /// broadcast in sequence order.
+    seq: Arc<Mutex<u64>>,
+}
+
+impl EventSender {
+    /// Stream holding up to `capacity` events per subscriber.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            tx: broadcast::channel(capacity).0,
+            seq: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    /// Stamp and send `event`. Fails only if nobody is subscribed.
+    pub fn send(&self, event: KeyerEvent) -> Result<usize, SendError<KeyerEvent>> {
+        self.send_stamped(event, None)
+    }
+
+    /// Pass on an event from another stream, keeping its timestamp. It is
+    /// numbered in this stream's sequence.
+    pub fn forward(&self, timed: TimedEvent) -> Result<usize, SendError<KeyerEvent>> {
+        self.send_stamped(timed.event, Some(timed.at))
+    }
+
+    fn send_stamped(
+        &self,
+        event: KeyerEvent,
+        at: Option<Instant>,
+    ) -> Result<usize, SendError<KeyerEvent>> {
+        let mut seq = self.seq.lock().unwrap();
+        let timed = TimedEvent {
+            seq: *seq,
+            at: at.unwrap_or_else(Instant::now),
+            event,
+        };
+        *seq += 1;
+        self.tx.send(timed).map_err(|e| SendError(e.0.event))
+    }
+
+    /// New subscriber, seeing events sent from now on.
+    pub fn subscribe(&self) -> EventReceiver {
+        let seq = self.seq.lock().unwrap();
+        EventReceiver {
+            rx: self.tx.subscribe(),
+            next_seq: *seq,
+        }
+    }
+
+    /// Number of live subscribers.
+    pub fn receiver_count(&self) -> usize {
+        self.tx.receiver_count()
+    }
+}
+
+/// Create an event stream, like [`broadcast::channel`].
+pub fn channel(capacity: usize) -> (EventSender, EventReceiver) {
+    let tx = EventSender::new(capacity);
+    let rx = tx.subscribe();
+    (tx, rx)
+}
+
+/// Receiving half of a keyer's event stream.
+///
+/// `recv` and `try_recv` work like their [`broadcast::Receiver`]
+/// counterparts, except that lag is reported in-band as
+/// [`KeyerEvent::EventsDropped`]; the only error is the stream closing.
+#[derive(Debug)]
+pub struct EventReceiver {
+    rx: broadcast::Receiver<TimedEvent>,
+    next_seq: u64,
+}
+
+impl EventReceiver {
+    /// Next event with its sequence number and timestamp.
+    pub async fn recv_timed(&mut self) -> Result<TimedEvent, RecvError> {
+        let result = self.rx.recv().await;
+        self.stamp(result)
+    }
+
+    /// Next event.
+    pub async fn recv(&mut self) -> Result<KeyerEvent, RecvError> {
+        self.recv_timed().await.map(|timed| timed.event)
+    }
+
+    /// Next event if one is waiting.
+    pub fn try_recv_timed(&mut self) -> Result<TimedEvent, TryRecvError> {
+        match self.rx.try_recv() {
+            Ok(timed) => Ok(self.accept(timed)),
+            Err(TryRecvError::Lagged(count)) => Ok(self.dropped(count)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Next event if one is waiting.
+    pub fn try_recv(&mut self) -> Result<KeyerEvent, TryRecvError> {
+        self.try_recv_timed().map(|timed| timed.event)
+    }
+
+    /// Another receiver starting from the current end of the stream.
+    pub fn resubscribe(&self) -> Self {
+        Self {
+            rx: self.rx.resubscribe(),
+            next_seq: self.next_seq,
+        }
+    }
+
+    fn stamp(&mut self, result: Result<TimedEvent, RecvError>) -> Result<TimedEvent, RecvError> {
+        match result {
+            Ok(timed) => Ok(self.accept(timed)),
+            Err(RecvError::Lagged(count)) => Ok(self.dropped(count)),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn accept(&mut self, timed: TimedEvent) -> TimedEvent {
+        self.next_seq = timed.seq + 1;
+        timed
+    }
+
+    fn dropped(&mut self, count: u64) -> TimedEvent {
+        let seq = self.next_seq;
+        self.next_seq += count;
+        TimedEvent {
+            seq,
+            at: Instant::now(),
+            event: KeyerEvent::EventsDropped { count },
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[tokio::test(start_paused = true)]
+    async fn events_are_stamped_in_order() {
+        let (tx, mut rx) = channel(8);
+        tx.send(KeyerEvent::Connected).unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        tx.clone().send(KeyerEvent::PaddleBreakIn).unwrap();
+
+        let first = rx.recv_timed().await.unwrap();
+        let second = rx.recv_timed().await.unwrap();
+        assert_eq!((first.seq, second.seq), (0, 1));
+        assert_eq!(second.at - first.at, std::time::Duration::from_millis(5));
+        assert!(matches!(second.event, KeyerEvent::PaddleBreakIn));
+    }
+
+    #[tokio::test]
+    async fn lag_becomes_events_dropped() {
+        let (tx, mut rx) = channel(2);
+        for wpm in 10..15 {
+            tx.send(KeyerEvent::SpeedPotChanged { wpm }).unwrap();
+        }
+        let dropped = rx.recv_timed().await.unwrap();
+        assert!(matches!(dropped.event, KeyerEvent::EventsDropped { count: 3 }));
+        assert_eq!(dropped.seq, 0);
+
+        let next = rx.recv_timed().await.unwrap();
+        assert_eq!(next.seq, 3);
+        assert!(matches!(next.event, KeyerEvent::SpeedPotChanged { wpm: 13 }));
+        assert!(matches!(rx.try_recv(), Ok(KeyerEvent::SpeedPotChanged { wpm: 14 })));
+        assert!(matches!(rx.try_recv(), Err(TryRecvError::Empty)));
+
+        drop(tx);
+        assert!(matches!(rx.recv().await, Err(RecvError::Closed)));
+    }
+
+    #[test]
+    fn late_subscriber_starts_at_current_seq() {
+        let tx = EventSender::new(4);
+        let _keep = tx.subscribe();
+        tx.send(KeyerEvent::Connected).unwrap();
+        tx.send(KeyerEvent::Connected).unwrap();
+        let mut late = tx.subscribe();
+        // Lag before anything is received is numbered from subscription
+        for _ in 0..6 {
+            tx.send(KeyerEvent::Connected).unwrap();
+        }
+        let dropped = late.try_recv_timed().unwrap();
+        assert_eq!(dropped.seq, 2);
+        assert!(matches!(dropped.event, KeyerEvent::EventsDropped { count: 2 }));
+        assert_eq!(late.try_recv_timed().unwrap().seq, 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn forward_keeps_time_and_renumbers() {
+        let (upstream, mut rx) = channel(8);
+        upstream.send(KeyerEvent::Connected).unwrap();
+        upstream.send(KeyerEvent::PaddleBreakIn).unwrap();
+        let _ = rx.recv_timed().await.unwrap();
+        let original = rx.recv_timed().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+        let (merged, mut merged_rx) = channel(8);
+        merged.forward(original.clone()).unwrap();
+        let forwarded = merged_rx.recv_timed().await.unwrap();
+        assert_eq!((original.seq, forwarded.seq), (1, 0));
+        assert_eq!(forwarded.at, original.at);
+        assert!(matches!(forwarded.event, KeyerEvent::PaddleBreakIn));
+    }
+
+    #[test]
+    fn concurrent_senders_keep_sequence_order() {
+        let tx = EventSender::new(4096);
+        let mut rx = tx.subscribe();
+        let senders: Vec<_> = (0..4)
+            .map(|_| {
+                let tx = tx.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        tx.send(KeyerEvent::Connected).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for sender in senders {
+            sender.join().unwrap();
+        }
+        for expected in 0..4000 {
+            assert_eq!(rx.try_recv_timed().unwrap().seq, expected);
+        }
+        assert!(matches!(rx.try_recv(), Err(TryRecvError::Empty)));
+    }
+
     #[test]
     fn decode_status_idle() {
         let status = KeyerStatus::from_status_byte(0xC0);
@@ -3,19 +3,30 @@
 //! Two priority channels (RT for abort/tune/PTT/speed/close, BG for text/config)
 //! ensure time-critical operations preempt queued text.
 
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 use std::sync::Arc;
 
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
-use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot};
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, trace, warn};
 
 use crate::error::{Error, Result};
-use crate::event::KeyerEvent;
+use crate::event::{EchoSource, EventSender, KeyerEvent};
+use crate::protocol::decoder::{Command, CommandDecoder};
 use crate::protocol::response::{self, ResponseByte};
 
+/// Host characters remembered while waiting for their echo. Bounds the
+/// queue when serial echo is off and nothing ever comes back.
+const MAX_PENDING_ECHO: usize = 512;
+
+/// How far into the pending host characters an echo may match. An echo lost
+/// on the serial line would otherwise leave every later host character
+/// classed as paddle input.
+const ECHO_RESYNC_WINDOW: usize = 4;
+
 /// Controls how `read_response_bytes` treats high-bit bytes (0x80+).
 #[derive(Debug, Clone, Copy, Default)]
 pub(crate) enum ResponseMode {
@@ -56,43 +67,19 @@ pub(crate) struct IoHandle {
     pub cancel: CancellationToken,
     pub task: JoinHandle<()>,
     pub xoff: Arc<AtomicBool>,
+    /// Speed pot minimum used to decode pot bytes into WPM.
+    pub min_wpm: Arc<AtomicU8>,
 }
 
 impl IoHandle {
     /// Send a command via the real-time (priority) channel.
     pub async fn rt_command(&self, data: Vec<u8>) -> Result<()> {
-        let (reply_tx, reply_rx) = oneshot::channel();
-        self.rt_tx
-            .send(Request::Write {
-                data,
-                reply: reply_tx,
-            })
-            .await
-            .map_err(|_| Error::NotConnected)?;
-
-        match tokio::time::timeout(std::time::Duration::from_secs(5), reply_rx).await {
-            Ok(Ok(result)) => result,
-            Ok(Err(_)) => Err(Error::NotConnected),
-            Err(_) => Err(Error::Timeout),
-        }
+        write_via(&self.rt_tx, data).await
     }
 
     /// Send a command via the background channel.
     pub async fn bg_command(&self, data: Vec<u8>) -> Result<()> {
-        let (reply_tx, reply_rx) = oneshot::channel();
-        self.bg_tx
-            .send(Request::Write {
-                data,
-                reply: reply_tx,
-            })
-            .await
-            .map_err(|_| Error::NotConnected)?;
-
-        match tokio::time::timeout(std::time::Duration::from_secs(5), reply_rx).await {
-            Ok(Ok(result)) => result,
-            Ok(Err(_)) => Err(Error::NotConnected),
-            Err(_) => Err(Error::Timeout),
-        }
+        write_via(&self.bg_tx, data).await
     }
 
     /// Send a command via RT and read back response bytes.
@@ -159,19 +146,120 @@ impl IoHandle {
     }
 }
 
+/// Send a write request on `tx` and wait for the IO task's ack.
+///
+/// For helpers that hold a channel clone rather than the [`IoHandle`].
+pub(crate) async fn write_via(tx: &mpsc::Sender<Request>, data: Vec<u8>) -> Result<()> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(Request::Write {
+        data,
+        reply: reply_tx,
+    })
+    .await
+    .map_err(|_| Error::NotConnected)?;
+
+    match tokio::time::timeout(std::time::Duration::from_secs(5), reply_rx).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err(Error::NotConnected),
+        Err(_) => Err(Error::Timeout),
+    }
+}
+
 /// Shared mutable state for the IO task, threaded through to request handlers
 /// so that interleaved status/speed-pot bytes can be properly dispatched even
 /// while waiting for a command response.
 struct IoState {
     xoff: Arc<AtomicBool>,
     prev_breakin: bool,
-    min_wpm: u8,
+    prev_busy: bool,
+    min_wpm: Arc<AtomicU8>,
+    echo: EchoTracker,
+}
+
+/// Tells host echo from paddle echo.
+///
+/// With both serial and paddle echo on, WinKeyer echoes everything it sends
+/// the same way. Host text is echoed in the order it was written, so an
+/// echo matching the oldest unechoed host character is host text and
+/// anything else came from the paddles. An echo matching a few characters
+/// further on means some were lost, and those are skipped. The keyer drops
+/// its buffer on Clear Buffer and on paddle break-in, and is done with it
+/// once idle, so the queue is dropped then too.
+#[derive(Default)]
+struct EchoTracker {
+    decoder: CommandDecoder,
+    pending: VecDeque<char>,
+    word: String,
+}
+
+impl EchoTracker {
+    /// Note host bytes written to the keyer.
+    fn on_write(&mut self, data: &[u8]) {
+        for cmd in self.decoder.decode(data) {
+            match cmd {
+                Command::Text(text) => text.chars().for_each(|ch| self.queue(ch)),
+                Command::Merge(a, b) => {
+                    self.queue(a as char);
+                    self.queue(b as char);
+                }
+                Command::Backspace => {
+                    self.pending.pop_back();
+                }
+                Command::ClearBuffer => self.pending.clear(),
+                _ => {}
+            }
+        }
+    }
+
+    fn queue(&mut self, ch: char) {
+        if self.pending.len() == MAX_PENDING_ECHO {
+            self.pending.pop_front();
+        }
+        self.pending.push_back(ch.to_ascii_uppercase());
+    }
+
+    /// The keyer's buffer was emptied; nothing queued will be echoed.
+    fn clear_host(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Classify an echoed character. Returns a finished paddle word, if any.
+    fn on_echo(&mut self, ch: char) -> (EchoSource, Option<String>) {
+        if ch != ' ' {
+            // Word spaces may not be echoed back
+            while self.pending.front() == Some(&' ') {
+                self.pending.pop_front();
+            }
+        }
+        let matched = self
+            .pending
+            .iter()
+            .take(ECHO_RESYNC_WINDOW)
+            .position(|&pending| pending == ch);
+        if let Some(skipped) = matched {
+            if skipped > 0 {
+                debug!(skipped, "host echo lost, resynchronised");
+            }
+            self.pending.drain(..=skipped);
+            return (EchoSource::Host, self.end_word());
+        }
+        if ch == ' ' {
+            return (EchoSource::Paddle, self.end_word());
+        }
+        self.word.push(ch);
+        (EchoSource::Paddle, None)
+    }
+
+    /// Finish the paddle word in progress, if any.
+    fn end_word(&mut self) -> Option<String> {
+        (!self.word.is_empty()).then(|| std::mem::take(&mut self.word))
+    }
 }
 
 /// Spawn the IO task that owns the serial port.
 pub(crate) fn spawn_io_task<P>(
     port: P,
-    event_tx: broadcast::Sender<KeyerEvent>,
+    event_tx: EventSender,
     min_wpm: u8,
 ) -> IoHandle
 where
@@ -181,6 +269,7 @@ where
     let (bg_tx, bg_rx) = mpsc::channel::<Request>(64);
     let cancel = CancellationToken::new();
     let xoff = Arc::new(AtomicBool::new(false));
+    let min_wpm = Arc::new(AtomicU8::new(min_wpm));
 
     let task = tokio::spawn(io_loop(
         port,
@@ -189,7 +278,7 @@ where
         cancel.clone(),
         event_tx,
         xoff.clone(),
-        min_wpm,
+        min_wpm.clone(),
     ));
 
     IoHandle {
@@ -198,6 +287,7 @@ where
         cancel,
         task,
         xoff,
+        min_wpm,
     }
 }
 
@@ -207,9 +297,9 @@ async fn io_loop<P>(
     mut rt_rx: mpsc::Receiver<Request>,
     mut bg_rx: mpsc::Receiver<Request>,
     cancel: CancellationToken,
-    event_tx: broadcast::Sender<KeyerEvent>,
+    event_tx: EventSender,
     xoff: Arc<AtomicBool>,
-    min_wpm: u8,
+    min_wpm: Arc<AtomicU8>,
 ) where
     P: AsyncRead + AsyncWrite + Send + Unpin + 'static,
 {
@@ -217,7 +307,9 @@ async fn io_loop<P>(
     let mut state = IoState {
         xoff,
         prev_breakin: false,
+        prev_busy: false,
         min_wpm,
+        echo: EchoTracker::default(),
     };
 
     debug!("IO task started");
@@ -308,7 +400,7 @@ async fn io_loop<P>(
 async fn handle_request<P>(
     req: Request,
     port: &mut P,
-    event_tx: &broadcast::Sender<KeyerEvent>,
+    event_tx: &EventSender,
     state: &mut IoState,
 ) where
     P: AsyncRead + AsyncWrite + Send + Unpin,
@@ -316,6 +408,7 @@ async fn handle_request<P>(
     match req {
         Request::Write { data, reply } => {
             trace!("writing {} bytes: {:02X?}", data.len(), data);
+            state.echo.on_write(&data);
             let result = port.write_all(&data).await.map_err(|e| {
                 error!("write error: {e}");
                 let _ = event_tx.send(KeyerEvent::Disconnected);
@@ -330,6 +423,7 @@ async fn handle_request<P>(
             reply,
         } => {
             trace!("write+read {} bytes, expecting {}", data.len(), expected);
+            state.echo.on_write(&data);
             let write_result = port.write_all(&data).await;
             if let Err(e) = write_result {
                 error!("write error: {e}");
@@ -377,7 +471,7 @@ async fn read_response_bytes<P>(
     port: &mut P,
     expected: usize,
     mode: ResponseMode,
-    event_tx: &broadcast::Sender<KeyerEvent>,
+    event_tx: &EventSender,
     state: &mut IoState,
 ) -> std::io::Result<Vec<u8>>
 where
@@ -418,29 +512,49 @@ where
 /// Process a single received byte from the WinKeyer.
 fn process_received_byte(
     byte: u8,
-    event_tx: &broadcast::Sender<KeyerEvent>,
+    event_tx: &EventSender,
     state: &mut IoState,
 ) {
     match response::classify_byte(byte) {
+        // In WK2 mode bit 3 marks a pushbutton status byte, which carries
+        // no keyer state
+        ResponseByte::Status(status) if status.keydown => {
+            trace!("pushbutton status {byte:#04X}");
+        }
         ResponseByte::Status(status) => {
             // Update XOFF atomic for fast-path checking
             state.xoff.store(status.xoff, Ordering::Release);
 
-            // Detect breakin edge (0→1 transition)
+            // Detect breakin edge (0→1 transition). Break-in flushes the
+            // keyer's buffer; its end finishes any paddle word.
             if status.breakin && !state.prev_breakin {
+                state.echo.clear_host();
                 let _ = event_tx.send(KeyerEvent::PaddleBreakIn);
+            } else if !status.breakin
+                && state.prev_breakin
+                && let Some(word) = state.echo.end_word()
+            {
+                let _ = event_tx.send(KeyerEvent::PaddleWord(word));
             }
             state.prev_breakin = status.breakin;
+            if !status.busy && state.prev_busy {
+                state.echo.clear_host();
+            }
+            state.prev_busy = status.busy;
 
             let _ = event_tx.send(KeyerEvent::StatusChanged(status));
         }
         ResponseByte::SpeedPot { value } => {
-            let wpm = state.min_wpm.saturating_add(value);
+            let wpm = state.min_wpm.load(Ordering::Acquire).saturating_add(value);
             let _ = event_tx.send(KeyerEvent::SpeedPotChanged { wpm });
         }
         ResponseByte::Echo(ch) => {
-            debug!("echo: '{ch}' (0x{:02X})", ch as u8);
-            let _ = event_tx.send(KeyerEvent::CharacterSent(ch));
+            let (source, word) = state.echo.on_echo(ch);
+            debug!("echo: '{ch}' (0x{:02X}) from {source:?}", ch as u8);
+            let _ = event_tx.send(KeyerEvent::CharacterSent { ch, source });
+            if let Some(word) = word {
+                let _ = event_tx.send(KeyerEvent::PaddleWord(word));
+            }
         }
     }
 }
@@ -450,10 +564,61 @@ mod tests {
     use super::*;
     use crate::transport::MockPort;
 
+    #[test]
+    fn echo_tracker_matches_host_text() {
+        let mut echo = EchoTracker::default();
+        echo.on_write(b"cq");
+        echo.on_write(&[0x1B, b'A', b'R']);
+        for ch in ['C', 'Q', 'A', 'R'] {
+            assert_eq!(echo.on_echo(ch), (EchoSource::Host, None));
+        }
+        assert_eq!(echo.on_echo('K'), (EchoSource::Paddle, None));
+    }
+
+    #[test]
+    fn echo_tracker_resyncs_after_lost_echo() {
+        let mut echo = EchoTracker::default();
+        echo.on_write(b"CQ TEST DE K1EL");
+        // The echo of the first 'E' never arrives
+        for ch in "CQ TST DE K1EL".chars() {
+            assert_eq!(echo.on_echo(ch), (EchoSource::Host, None), "{ch}");
+        }
+        assert!(echo.pending.is_empty());
+        assert_eq!(echo.on_echo('R'), (EchoSource::Paddle, None));
+    }
+
+    #[test]
+    fn echo_tracker_paddle_words() {
+        let mut echo = EchoTracker::default();
+        echo.on_write(b"TEST");
+        echo.clear_host(); // break-in
+        for ch in "K1EL".chars() {
+            assert_eq!(echo.on_echo(ch).0, EchoSource::Paddle);
+        }
+        assert_eq!(echo.on_echo(' '), (EchoSource::Paddle, Some("K1EL".into())));
+        echo.on_echo('5');
+        assert_eq!(echo.end_word().as_deref(), Some("5"));
+        assert_eq!(echo.end_word(), None);
+    }
+
+    #[test]
+    fn echo_tracker_buffer_edits() {
+        let mut echo = EchoTracker::default();
+        // Unechoed word space, backspace and Clear Buffer
+        echo.on_write(b"A BX");
+        echo.on_write(&[0x08]);
+        assert_eq!(echo.on_echo('A').0, EchoSource::Host);
+        assert_eq!(echo.on_echo('B').0, EchoSource::Host);
+        assert_eq!(echo.on_echo('X').0, EchoSource::Paddle);
+        echo.on_write(b"EE");
+        echo.on_write(&[0x0A]);
+        assert_eq!(echo.on_echo('E').0, EchoSource::Paddle);
+    }
+
     #[tokio::test]
     async fn io_task_write_command() {
         let mock = MockPort::new();
-        let (event_tx, _rx) = broadcast::channel(16);
+        let (event_tx, _rx) = crate::event::channel(16);
         let io = spawn_io_task(mock.clone(), event_tx, 10);
 
         // Send a command via RT channel
@@ -470,7 +635,7 @@ mod tests {
     #[tokio::test]
     async fn io_task_bg_command() {
         let mock = MockPort::new();
-        let (event_tx, _rx) = broadcast::channel(16);
+        let (event_tx, _rx) = crate::event::channel(16);
         let io = spawn_io_task(mock.clone(), event_tx, 10);
 
         // Send text via BG channel
@@ -486,7 +651,7 @@ mod tests {
     #[tokio::test]
     async fn io_task_receives_status() {
         let mock = MockPort::new();
-        let (event_tx, mut event_rx) = broadcast::channel(16);
+        let (event_tx, mut event_rx) = crate::event::channel(16);
 
         // Queue a status byte before spawning so the IO task reads it
         mock.queue_read(&[0xC0]); // status: all clear
@@ -511,10 +676,35 @@ mod tests {
         io.shutdown().await.unwrap();
     }
 
+    #[tokio::test]
+    async fn io_task_skips_pushbutton_status() {
+        let mock = MockPort::new();
+        let (event_tx, mut event_rx) = crate::event::channel(16);
+
+        // Pushbutton status (bit 3) with bit 0 set, then a busy status
+        mock.queue_read(&[0xC9, 0xC4]);
+        let io = spawn_io_task(mock.clone(), event_tx, 10);
+
+        let event = tokio::time::timeout(
+            std::time::Duration::from_millis(100),
+            event_rx.recv(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(
+            matches!(event, KeyerEvent::StatusChanged(s) if s.busy && !s.xoff),
+            "{event:?}"
+        );
+        assert!(!io.xoff.load(Ordering::Acquire));
+
+        io.shutdown().await.unwrap();
+    }
+
     #[tokio::test]
     async fn io_task_receives_echo() {
         let mock = MockPort::new();
-        let (event_tx, mut event_rx) = broadcast::channel(16);
+        let (event_tx, mut event_rx) = crate::event::channel(16);
 
         mock.queue_read(&[b'C', b'Q']);
         let io = spawn_io_task(mock.clone(), event_tx, 10);
@@ -526,7 +716,7 @@ mod tests {
         .await
         .unwrap()
         .unwrap();
-        assert!(matches!(ev1, KeyerEvent::CharacterSent('C')));
+        assert!(matches!(ev1, KeyerEvent::CharacterSent { ch: 'C', source: EchoSource::Paddle }));
 
         let ev2 = tokio::time::timeout(
             std::time::Duration::from_millis(100),
@@ -535,7 +725,7 @@ mod tests {
         .await
         .unwrap()
         .unwrap();
-        assert!(matches!(ev2, KeyerEvent::CharacterSent('Q')));
+        assert!(matches!(ev2, KeyerEvent::CharacterSent { ch: 'Q', source: EchoSource::Paddle }));
 
         io.shutdown().await.unwrap();
     }
@@ -543,7 +733,7 @@ mod tests {
     #[tokio::test]
     async fn io_task_speed_pot_event() {
         let mock = MockPort::new();
-        let (event_tx, mut event_rx) = broadcast::channel(16);
+        let (event_tx, mut event_rx) = crate::event::channel(16);
 
         // 0x8A = speed pot, value 10, min_wpm=10 → 20 WPM
         mock.queue_read(&[0x8A]);
@@ -565,7 +755,7 @@ mod tests {
     #[tokio::test]
     async fn io_task_xoff_tracking() {
         let mock = MockPort::new();
-        let (event_tx, _rx) = broadcast::channel(16);
+        let (event_tx, _rx) = crate::event::channel(16);
 
         // Queue status with XOFF set (bit 0)
         mock.queue_read(&[0xC1]); // xoff=true
@@ -586,7 +776,7 @@ mod tests {
     #[tokio::test]
     async fn io_task_breakin_edge_detection() {
         let mock = MockPort::new();
-        let (event_tx, mut event_rx) = broadcast::channel(16);
+        let (event_tx, mut event_rx) = crate::event::channel(16);
 
         // Queue breakin transition: no breakin → breakin (bit 1)
         mock.queue_read(&[0xC0, 0xC2]); // clear, then breakin
@@ -628,7 +818,7 @@ mod tests {
     #[tokio::test]
     async fn io_task_shutdown() {
         let mock = MockPort::new();
-        let (event_tx, _rx) = broadcast::channel(16);
+        let (event_tx, _rx) = crate::event::channel(16);
         let io = spawn_io_task(mock, event_tx, 10);
 
         let result = io.shutdown().await;
@@ -644,7 +834,7 @@ mod tests {
     #[tokio::test]
     async fn io_task_cancel() {
         let mock = MockPort::new();
-        let (event_tx, _rx) = broadcast::channel(16);
+        let (event_tx, _rx) = crate::event::channel(16);
         let io = spawn_io_task(mock, event_tx, 10);
 
         io.cancel.cancel();
@@ -658,7 +848,7 @@ mod tests {
     #[tokio::test]
     async fn io_task_write_and_read() {
         let mock = MockPort::new();
-        let (event_tx, _rx) = broadcast::channel(16);
+        let (event_tx, _rx) = crate::event::channel(16);
 
         // Queue response for echo test
         mock.queue_read(&[0x42]);
@@ -676,7 +866,7 @@ mod tests {
     #[tokio::test]
     async fn io_task_write_and_read_filters_status() {
         let mock = MockPort::new();
-        let (event_tx, mut event_rx) = broadcast::channel(16);
+        let (event_tx, mut event_rx) = crate::event::channel(16);
         let io = spawn_io_task(mock.clone(), event_tx, 10);
 
         // Queue the interleaved data AFTER spawning, with a small delay
@@ -724,7 +914,7 @@ mod tests {
     #[tokio::test]
     async fn io_task_write_and_read_filters_multiple_status() {
         let mock = MockPort::new();
-        let (event_tx, _rx) = broadcast::channel(16);
+        let (event_tx, _rx) = crate::event::channel(16);
         let io = spawn_io_task(mock.clone(), event_tx, 10);
 
         // Queue interleaved data after a delay to avoid the idle read arm
@@ -748,7 +938,7 @@ mod tests {
     #[tokio::test]
     async fn io_task_write_and_read_binary_accepts_high_bytes() {
         let mock = MockPort::new();
-        let (event_tx, _rx) = broadcast::channel(16);
+        let (event_tx, _rx) = crate::event::channel(16);
         let io = spawn_io_task(mock.clone(), event_tx, 10);
 
         // Queue a high-bit byte (0xC6 would be status in Ascii mode)
@@ -769,7 +959,7 @@ mod tests {
     #[tokio::test]
     async fn io_task_binary_mode_returns_0x80() {
         let mock = MockPort::new();
-        let (event_tx, _rx) = broadcast::channel(16);
+        let (event_tx, _rx) = crate::event::channel(16);
         let io = spawn_io_task(mock.clone(), event_tx, 10);
 
         // 0x80 would be classified as speed-pot in Ascii mode
@@ -789,7 +979,7 @@ mod tests {
     #[tokio::test]
     async fn io_task_binary_mode_returns_0xff() {
         let mock = MockPort::new();
-        let (event_tx, _rx) = broadcast::channel(16);
+        let (event_tx, _rx) = crate::event::channel(16);
         let io = spawn_io_task(mock.clone(), event_tx, 10);
 
         let mock_clone = mock.clone();
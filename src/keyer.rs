@@ -4,10 +4,10 @@
 //! keyer backends (WinKeyer, cwdaemon, rig-internal keyer).
 
 use async_trait::async_trait;
-use tokio::sync::broadcast;
 
-use crate::error::Result;
-use crate::event::KeyerEvent;
+use crate::error::{Error, Result};
+use crate::event::EventReceiver;
+use crate::stream::EventStream;
 
 /// Metadata about a keyer backend.
 #[derive(Debug, Clone)]
@@ -18,22 +18,41 @@ pub struct KeyerInfo {
 }
 
 /// Capability flags for a keyer backend.
+///
+/// Flags that gate optional [`Keyer`] methods are noted on each field; when
+/// a flag is false the method returns [`Error::Unsupported`].
 #[derive(Debug, Clone, Default)]
 pub struct KeyerCapabilities {
     pub speed_pot: bool,
+    /// Gates [`Keyer::set_sidetone`].
     pub sidetone: bool,
     pub ptt_control: bool,
     pub paddle_echo: bool,
+    /// Gates [`Keyer::send_prosign`].
     pub prosigns: bool,
+    /// Gates [`Keyer::set_buffered_speed`] and [`Keyer::cancel_buffered_speed`].
     pub buffered_speed: bool,
+    /// Gates [`Keyer::set_farnsworth`].
     pub farnsworth: bool,
     pub contest_spacing: bool,
+    /// Gates [`Keyer::set_pause`].
+    pub pause: bool,
+    /// Gates [`Keyer::set_weight`].
+    pub weight: bool,
+    /// Status events carry every key-down edge in
+    /// [`KeyerStatus::keydown`](crate::KeyerStatus::keydown). Gates
+    /// [`KeyingMonitor::start`](crate::KeyingMonitor::start).
+    pub key_state: bool,
 }
 
 /// Backend-agnostic keyer interface.
 ///
 /// No WinKeyer-specific types appear in this trait. Contest loggers program
 /// against `dyn Keyer`.
+///
+/// Methods after [`close`](Keyer::close) are optional: the default
+/// implementations return [`Error::Unsupported`], and backends that
+/// override them set the matching [`KeyerCapabilities`] flag.
 #[async_trait]
 pub trait Keyer: Send + Sync {
     /// Keyer metadata (name, version, port).
@@ -57,12 +76,131 @@ pub trait Keyer: Send + Sync {
     /// Enable or disable key-down tune mode.
     async fn set_tune(&self, on: bool) -> Result<()>;
 
-    /// Enable or disable PTT output.
+    /// Assert or release PTT. The change is queued behind any text already
+    /// sent, so `send_message("CQ")` then `set_ptt(false)` drops PTT after
+    /// the CQ, not before it. Abort clears anything still queued.
     async fn set_ptt(&self, on: bool) -> Result<()>;
 
     /// Subscribe to keyer events (status changes, echo, speed pot, etc.).
-    fn subscribe(&self) -> broadcast::Receiver<KeyerEvent>;
+    fn subscribe(&self) -> EventReceiver;
+
+    /// Subscribe to keyer events as a [`Stream`](futures_core::Stream).
+    fn events(&self) -> EventStream {
+        EventStream::new(self.subscribe())
+    }
 
     /// Close the connection and shut down the IO task.
     async fn close(&self) -> Result<()>;
+
+    /// Send a prosign (two letters merged without a letter gap) via the buffer.
+    async fn send_prosign(&self, _c1: u8, _c2: u8) -> Result<()> {
+        Err(Error::Unsupported("prosigns".into()))
+    }
+
+    /// Change speed in the buffer; takes effect for text queued after it.
+    async fn set_buffered_speed(&self, _wpm: u8) -> Result<()> {
+        Err(Error::Unsupported("buffered speed change".into()))
+    }
+
+    /// Restore the speed in effect before [`set_buffered_speed`](Keyer::set_buffered_speed).
+    async fn cancel_buffered_speed(&self) -> Result<()> {
+        Err(Error::Unsupported("buffered speed change".into()))
+    }
+
+    /// Pause or resume CW output without clearing the buffer.
+    async fn set_pause(&self, _paused: bool) -> Result<()> {
+        Err(Error::Unsupported("pause".into()))
+    }
+
+    /// Set keying weight (10-90, default 50).
+    async fn set_weight(&self, _weight: u8) -> Result<()> {
+        Err(Error::Unsupported("weight".into()))
+    }
+
+    /// Set Farnsworth speed (0 = disable).
+    async fn set_farnsworth(&self, _wpm: u8) -> Result<()> {
+        Err(Error::Unsupported("Farnsworth".into()))
+    }
+
+    /// Set sidetone frequency in Hz.
+    async fn set_sidetone(&self, _freq_hz: u16) -> Result<()> {
+        Err(Error::Unsupported("sidetone".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventSender;
+
+    struct Minimal {
+        info: KeyerInfo,
+        capabilities: KeyerCapabilities,
+        event_tx: EventSender,
+    }
+
+    #[async_trait]
+    impl Keyer for Minimal {
+        fn info(&self) -> &KeyerInfo {
+            &self.info
+        }
+
+        fn capabilities(&self) -> &KeyerCapabilities {
+            &self.capabilities
+        }
+
+        async fn send_message(&self, _text: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn abort(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn set_speed(&self, _wpm: u8) -> Result<()> {
+            Ok(())
+        }
+
+        async fn get_speed(&self) -> Result<u8> {
+            Ok(20)
+        }
+
+        async fn set_tune(&self, _on: bool) -> Result<()> {
+            Ok(())
+        }
+
+        async fn set_ptt(&self, _on: bool) -> Result<()> {
+            Ok(())
+        }
+
+        fn subscribe(&self) -> EventReceiver {
+            self.event_tx.subscribe()
+        }
+
+        async fn close(&self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn optional_methods_default_to_unsupported() {
+        let keyer = Minimal {
+            info: KeyerInfo {
+                name: "minimal".into(),
+                version: "0".into(),
+                port: None,
+            },
+            capabilities: KeyerCapabilities::default(),
+            event_tx: crate::event::channel(1).0,
+        };
+        let keyer: &dyn Keyer = &keyer;
+        let unsupported = |r: Result<()>| matches!(r, Err(Error::Unsupported(_)));
+        assert!(unsupported(keyer.send_prosign(b'A', b'R').await));
+        assert!(unsupported(keyer.set_buffered_speed(15).await));
+        assert!(unsupported(keyer.cancel_buffered_speed().await));
+        assert!(unsupported(keyer.set_pause(true).await));
+        assert!(unsupported(keyer.set_weight(50).await));
+        assert!(unsupported(keyer.set_farnsworth(10).await));
+        assert!(unsupported(keyer.set_sidetone(800).await));
+    }
 }
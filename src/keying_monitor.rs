@@ -0,0 +1,349 @@
+//! Keying analysis from status key-down edges.
+//!
+//! [`KeyingMonitor`] watches a keyer's [`KeyerStatus::keydown`] bit,
+//! timestamps each edge, and [`KeyingTimeline::stats`] turns the marks and
+//! spaces into element durations, speed, weight and dit/dah ratio. Use it to
+//! check that a keyer times what it was asked to, or to look at an
+//! operator's paddle fist:
+//!
+//! ```no_run
+//! # async fn example(keyer: &dyn winkey::Keyer) -> winkey::Result<()> {
+//! use winkey::KeyingMonitor;
+//!
+//! let monitor = KeyingMonitor::start(keyer)?;
+//! keyer.send_message("PARIS PARIS").await?;
+//! tokio::time::sleep(std::time::Duration::from_secs(8)).await;
+//! if let Some(stats) = monitor.timeline().stats() {
+//!     println!("{:.1} WPM, weight {:.0}", stats.wpm, stats.weight);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The software keyers report every edge as it happens. WinKeyer runs in WK2
+//! mode, where the keydown bit marks pushbutton status instead, so it
+//! doesn't set [`KeyerCapabilities::key_state`] and is refused.
+//!
+//! [`KeyerStatus::keydown`]: crate::KeyerStatus::keydown
+//! [`KeyerCapabilities::key_state`]: crate::KeyerCapabilities::key_state
+
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::error::{Error, Result};
+use crate::event::{EventReceiver, KeyerEvent};
+use crate::keyer::Keyer;
+
+/// Key-down and key-up edges, timed from when recording started.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyingTimeline {
+    /// `(time, key_down)` for each change of key state, in order.
+    pub edges: Vec<(Duration, bool)>,
+}
+
+/// A mark (key down) or space (key up) between two edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyingElement {
+    pub key_down: bool,
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+impl KeyingTimeline {
+    /// Timeline from key-down intervals, e.g.
+    /// [`Timeline::key_intervals`](crate::virtual_keyer::Timeline::key_intervals).
+    pub fn from_intervals(intervals: &[Range<Duration>]) -> Self {
+        Self {
+            edges: intervals
+                .iter()
+                .flat_map(|r| [(r.start, true), (r.end, false)])
+                .collect(),
+        }
+    }
+
+    /// Marks and spaces between consecutive edges. Silence before the
+    /// first mark and after the last is not included.
+    pub fn elements(&self) -> Vec<KeyingElement> {
+        let start = self.edges.iter().position(|&(_, down)| down).unwrap_or(self.edges.len());
+        self.edges[start..]
+            .windows(2)
+            .filter(|w| w[0].1 != w[1].1)
+            .map(|w| KeyingElement {
+                key_down: w[0].1,
+                start: w[0].0,
+                duration: w[1].0 - w[0].0,
+            })
+            .collect()
+    }
+
+    /// Durations of every complete mark.
+    pub fn marks(&self) -> Vec<Duration> {
+        self.elements()
+            .into_iter()
+            .filter(|e| e.key_down)
+            .map(|e| e.duration)
+            .collect()
+    }
+
+    /// Element statistics, or `None` until there are both dits and dahs.
+    pub fn stats(&self) -> Option<KeyingStats> {
+        KeyingStats::from_elements(&self.elements())
+    }
+}
+
+/// Timing measured from marks and spaces.
+///
+/// Speed and weight follow the WinKeyer model used by
+/// [`MorseTiming`](crate::timing::MorseTiming): weight moves time from each
+/// space into the mark before it, so a dit plus the gap after it is two
+/// units at any weight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyingStats {
+    pub dits: usize,
+    pub dahs: usize,
+    /// Mean dit mark.
+    pub dit: Duration,
+    /// Mean dah mark.
+    pub dah: Duration,
+    /// Mean gap between elements of one character.
+    pub element_gap: Duration,
+    /// Length of one unit.
+    pub unit: Duration,
+    /// Character speed (PARIS), from the unit.
+    pub wpm: f32,
+    /// Weight, 50 = standard.
+    pub weight: f32,
+    /// Dit/dah ratio, 50 = 3:1.
+    pub dit_dah_ratio: f32,
+}
+
+impl KeyingStats {
+    /// Analyze marks and spaces in order.
+    pub fn from_elements(elements: &[KeyingElement]) -> Option<Self> {
+        let marks: Vec<f64> = elements
+            .iter()
+            .filter(|e| e.key_down)
+            .map(|e| e.duration.as_secs_f64())
+            .collect();
+        let threshold = split_point(&marks)?;
+
+        let mut dits = Vec::new();
+        let mut dahs = Vec::new();
+        for &m in &marks {
+            if m < threshold { dits.push(m) } else { dahs.push(m) }
+        }
+        let dit = mean(&dits)?;
+        let dah = mean(&dahs)?;
+
+        // Spaces after a mark and shorter than a dah are inside a character.
+        let element_gaps: Vec<f64> = elements
+            .windows(2)
+            .filter(|w| w[0].key_down && !w[1].key_down)
+            .map(|w| w[1].duration.as_secs_f64())
+            .filter(|&gap| gap < threshold)
+            .collect();
+        let gap = mean(&element_gaps);
+
+        // Pair dits with the gap that follows; fall back to an unweighted dit.
+        let unit = match gap {
+            Some(gap) => (dit + gap) / 2.0,
+            None => dit,
+        };
+        let weight = 50.0 + 50.0 * (dit - unit) / unit;
+        let ratio = 50.0 * (dah - dit + unit) / (3.0 * unit);
+
+        Some(Self {
+            dits: dits.len(),
+            dahs: dahs.len(),
+            dit: Duration::from_secs_f64(dit),
+            dah: Duration::from_secs_f64(dah),
+            element_gap: Duration::from_secs_f64(gap.unwrap_or(unit)),
+            unit: Duration::from_secs_f64(unit),
+            wpm: (1.2 / unit) as f32,
+            weight: weight as f32,
+            dit_dah_ratio: ratio as f32,
+        })
+    }
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Duration separating dits from dahs: two-means clustering seeded with the
+/// shortest and longest mark. `None` unless both clusters are populated.
+fn split_point(marks: &[f64]) -> Option<f64> {
+    let (mut lo, mut hi) = marks
+        .iter()
+        .fold((f64::MAX, f64::MIN), |(lo, hi), &m| (lo.min(m), hi.max(m)));
+    // Anything closer than 1.5:1 is one kind of element.
+    if marks.is_empty() || hi < lo * 1.5 {
+        return None;
+    }
+    for _ in 0..8 {
+        let split = (lo + hi) / 2.0;
+        let (short, long): (Vec<f64>, Vec<f64>) = marks.iter().partition(|&&m| m < split);
+        lo = mean(&short)?;
+        hi = mean(&long)?;
+    }
+    Some((lo + hi) / 2.0)
+}
+
+/// Records key-down edges from a keyer's status events. Dropping it stops
+/// recording.
+#[derive(Debug)]
+pub struct KeyingMonitor {
+    timeline: Arc<Mutex<KeyingTimeline>>,
+    start: Arc<Mutex<Instant>>,
+    task: JoinHandle<()>,
+}
+
+impl KeyingMonitor {
+    /// Start recording edges from `keyer`. Must be called inside a tokio
+    /// runtime. Fails with [`Error::Unsupported`] unless the keyer reports
+    /// key state ([`KeyerCapabilities::key_state`](crate::KeyerCapabilities::key_state)).
+    pub fn start(keyer: &dyn Keyer) -> Result<Self> {
+        if !keyer.capabilities().key_state {
+            return Err(Error::Unsupported("key state".into()));
+        }
+        let timeline = Arc::new(Mutex::new(KeyingTimeline::default()));
+        let start = Arc::new(Mutex::new(Instant::now()));
+        let task = tokio::spawn(record(keyer.subscribe(), timeline.clone(), start.clone()));
+        Ok(Self {
+            timeline,
+            start,
+            task,
+        })
+    }
+
+    /// Edges recorded so far.
+    pub fn timeline(&self) -> KeyingTimeline {
+        self.timeline.lock().unwrap().clone()
+    }
+
+    /// Forget recorded edges and restart the clock.
+    pub fn clear(&self) {
+        *self.start.lock().unwrap() = Instant::now();
+        self.timeline.lock().unwrap().edges.clear();
+    }
+}
+
+impl Drop for KeyingMonitor {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn record(
+    mut rx: EventReceiver,
+    timeline: Arc<Mutex<KeyingTimeline>>,
+    start: Arc<Mutex<Instant>>,
+) {
+    let mut key_down = false;
+    loop {
+        match rx.recv().await {
+            Ok(KeyerEvent::StatusChanged(status)) if status.keydown != key_down => {
+                key_down = status.keydown;
+                let at = start.lock().unwrap().elapsed();
+                timeline.lock().unwrap().edges.push((at, key_down));
+            }
+            Ok(_) => {}
+            Err(_) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timing::MorseTiming;
+    use crate::virtual_keyer::{Timeline, VirtualKeyer};
+
+    fn analyze(text: &str, timing: MorseTiming) -> KeyingStats {
+        let rendered = Timeline::render(text.as_bytes(), timing);
+        KeyingTimeline::from_intervals(&rendered.key_intervals()).stats().unwrap()
+    }
+
+    #[test]
+    fn standard_timing() {
+        let stats = analyze("PARIS PARIS", MorseTiming::default());
+        assert_eq!(stats.dit, Duration::from_millis(60));
+        assert_eq!(stats.dah, Duration::from_millis(180));
+        assert_eq!(stats.element_gap, Duration::from_millis(60));
+        assert!((stats.wpm - 20.0).abs() < 0.01);
+        assert!((stats.weight - 50.0).abs() < 0.01);
+        assert!((stats.dit_dah_ratio - 50.0).abs() < 0.01);
+        assert_eq!((stats.dits, stats.dahs), (20, 8));
+    }
+
+    #[test]
+    fn weight_and_ratio_recovered() {
+        let timing = MorseTiming {
+            wpm: 30,
+            weight: 65,
+            dit_dah_ratio: 60,
+            ..MorseTiming::default()
+        };
+        let stats = analyze("CQ TEST DE K1EL", timing);
+        assert!((stats.wpm - 30.0).abs() < 0.1, "{stats:?}");
+        assert!((stats.weight - 65.0).abs() < 0.5, "{stats:?}");
+        assert!((stats.dit_dah_ratio - 60.0).abs() < 0.5, "{stats:?}");
+    }
+
+    #[test]
+    fn needs_dits_and_dahs() {
+        let stats = |text: &str| {
+            let rendered = Timeline::render(text.as_bytes(), MorseTiming::default());
+            KeyingTimeline::from_intervals(&rendered.key_intervals()).stats()
+        };
+        assert!(stats("").is_none());
+        assert!(stats("EISH").is_none());
+        assert!(stats("TMO").is_none());
+        assert!(stats("A").is_some());
+    }
+
+    #[test]
+    fn elements_skip_leading_silence() {
+        let timeline = KeyingTimeline {
+            edges: vec![
+                (Duration::from_millis(5), false),
+                (Duration::from_millis(10), true),
+                (Duration::from_millis(70), false),
+                (Duration::from_millis(130), true),
+            ],
+        };
+        let elements = timeline.elements();
+        assert_eq!(elements.len(), 2);
+        assert!(elements[0].key_down);
+        assert_eq!(elements[0].duration, Duration::from_millis(60));
+        assert_eq!(timeline.marks(), [Duration::from_millis(60)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn monitors_virtual_keyer() {
+        let keyer = VirtualKeyer::new(MorseTiming {
+            wpm: 25,
+            weight: 40,
+            ..MorseTiming::default()
+        });
+        let monitor = KeyingMonitor::start(&keyer).unwrap();
+        tokio::task::yield_now().await;
+
+        keyer.send_message("PARIS").await.unwrap();
+        tokio::time::sleep(Duration::from_secs(3)).await;
+
+        let recorded = monitor.timeline();
+        assert_eq!(recorded.marks().len(), 14);
+        let stats = recorded.stats().unwrap();
+        assert!((stats.wpm - 25.0).abs() < 0.1, "{stats:?}");
+        assert!((stats.weight - 40.0).abs() < 1.0, "{stats:?}");
+
+        monitor.clear();
+        assert!(monitor.timeline().edges.is_empty());
+        keyer.close().await.unwrap();
+    }
+}
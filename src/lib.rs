@@ -1,19 +1,51 @@
+pub mod beacon;
 pub mod builder;
+pub mod capture;
+pub mod emulator;
 pub mod error;
 pub mod event;
 pub(crate) mod io;
 pub mod keyer;
+pub mod keying_monitor;
 pub mod message;
+pub mod paddle_a2d;
+pub mod profile;
 pub mod protocol;
+pub mod remote;
+pub mod rtty;
+pub mod serial_line;
+pub mod settings;
+pub(crate) mod soft;
+pub mod speed_pot;
+pub mod stream;
+pub mod switch;
+pub mod timing;
+pub mod trace;
+pub mod trainer;
 pub mod transport;
+pub mod virtual_keyer;
 pub mod winkeyer;
 
+pub use beacon::{Beacon, BeaconConfig, BeaconEvent, BeaconSchedule};
 pub use builder::WinKeyerBuilder;
 pub use error::{Error, Result};
-pub use event::{KeyerEvent, KeyerStatus};
+pub use event::{EchoSource, EventReceiver, EventSender, KeyerEvent, KeyerStatus, TimedEvent};
 pub use keyer::{Keyer, KeyerCapabilities, KeyerInfo};
+pub use keying_monitor::{KeyingMonitor, KeyingStats, KeyingTimeline};
+pub use paddle_a2d::PaddleA2dSamples;
+pub use profile::KeyerProfile;
 pub use protocol::types::{
     LoadDefaults, ModeRegister, PaddleMode, PinConfig, WinKeyerVersion,
 };
+pub use remote::{KeyerServer, RemoteKeyer, RemoteSerialPort};
+pub use rtty::{RttyBaud, RttyConfig};
+pub use serial_line::{ControlLine, SerialLineKeyer, SerialLineKeyerBuilder};
+pub use settings::{KeyerSettings, SettingsChange};
+pub use soft::LineDriver;
+pub use speed_pot::{SpeedPotCalibration, SpeedPotMode, SpeedPotPolicy};
+pub use stream::EventStream;
+pub use switch::{KeyerSwitch, RadioEvent};
+pub use trainer::{DrillConfig, Trainer};
 pub use transport::MockPort;
+pub use virtual_keyer::VirtualKeyer;
 pub use winkeyer::WinKeyer;
@@ -1,9 +1,11 @@
 //! Prosign constants and contest message builder.
 //!
 //! Provides helpers for building CW messages with inline prosigns
-//! and speed changes, encoding them into WinKeyer command byte sequences.
+//! and speed changes (including HSCW), encoding them into WinKeyer
+//! command byte sequences.
 
 use crate::protocol::command;
+use crate::protocol::types::hscw_speed_byte;
 
 /// Prosign: AR (end of message) — merge 'A' + 'R'
 pub const PROSIGN_AR: (u8, u8) = (b'A', b'R');
@@ -28,6 +30,19 @@ fn parse_prosign(name: &str) -> Option<(u8, u8)> {
     }
 }
 
+/// Split a `{K10}`-style tag into its letter and number.
+fn split_tag(tag: &str) -> Option<(char, u8)> {
+    let mut chars = tag.chars();
+    let letter = chars.next()?.to_ascii_uppercase();
+    if !matches!(letter, 'K' | 'W' | 'P') {
+        return None;
+    }
+    // Bad or out-of-range numbers still count as the tag, so they get
+    // skipped rather than read as a speed change.
+    let arg = chars.as_str().trim().parse::<u8>().unwrap_or(u8::MAX);
+    Some((letter, arg))
+}
+
 /// Build a contest CW message from a template string.
 ///
 /// Supports:
@@ -35,6 +50,21 @@ fn parse_prosign(name: &str) -> Option<(u8, u8)> {
 /// - `<AR>`, `<SK>`, `<BT>`, `<KN>`, `<AS>`: prosign merge commands
 /// - `{20}`: buffered speed change to 20 WPM
 /// - `{0}` or `{}`: cancel buffered speed change (restore original)
+/// - `{H4000}`: buffered switch to HSCW at 4000 LPM (whole hundreds,
+///   1000-8000; other values are skipped)
+/// - `{K10}`: key down for 10 seconds, e.g. a tuning carrier (0-99)
+/// - `{W5}`: wait 5 seconds (0-99)
+/// - `{P1}` / `{P0}`: assert / release PTT
+///
+/// Timed key-down and wait values outside 0-99 are skipped. Everything
+/// is buffered, so a whole tune or beacon sequence can be queued with one
+/// write:
+///
+/// ```
+/// use winkey::message::build_contest_message;
+/// let bytes = build_contest_message("{P1}{W1}VVV DE K1EL{K5}{P0}");
+/// assert_eq!(&bytes[..4], &[0x18, 1, 0x1A, 1]);
+/// ```
 ///
 /// Returns the byte sequence ready for serial transmission.
 ///
@@ -61,10 +91,34 @@ pub fn build_contest_message(template: &str) -> Vec<u8> {
                 // If unknown prosign, silently skip
             }
             '{' => {
-                // Parse speed change: {20}, {0}, {}
+                // Parse speed change: {20}, {0}, {}, {H4000}, or a
+                // key/wait/PTT tag: {K10}, {W5}, {P1}
                 chars.next(); // consume '{'
                 let num_str: String = chars.by_ref().take_while(|&c| c != '}').collect();
-                let wpm: u8 = num_str.trim().parse().unwrap_or(0);
+                let num_str = num_str.trim();
+                if let Some(lpm) = num_str.strip_prefix(['H', 'h']) {
+                    let byte = lpm.trim().parse().ok().and_then(hscw_speed_byte);
+                    if let Some(byte) = byte {
+                        output.extend_from_slice(&command::buffered_hscw_speed(byte));
+                    }
+                    continue;
+                }
+                if let Some((tag, arg)) = split_tag(num_str) {
+                    match (tag, arg) {
+                        ('K', secs @ 0..=99) => {
+                            output.extend_from_slice(&command::key_buffered(secs))
+                        }
+                        ('W', secs @ 0..=99) => {
+                            output.extend_from_slice(&command::buffered_wait(secs))
+                        }
+                        ('P', on @ 0..=1) => {
+                            output.extend_from_slice(&command::buffered_ptt(on == 1))
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                let wpm: u8 = num_str.parse().unwrap_or(0);
                 if wpm == 0 {
                     output.extend_from_slice(&command::cancel_buffered_speed());
                 } else {
@@ -164,6 +218,41 @@ mod tests {
         assert_eq!(&bytes[20..23], &[0x1B, b'A', b'R']);
     }
 
+    #[test]
+    fn hscw_speed_change() {
+        let bytes = build_contest_message("QRZ{H4000}K1EL{h1500}{0}");
+        assert_eq!(&bytes[0..3], b"QRZ");
+        assert_eq!(&bytes[3..5], &[0x1D, 40]);
+        assert_eq!(&bytes[5..9], b"K1EL");
+        assert_eq!(&bytes[9..11], &[0x1D, 15]);
+        assert_eq!(&bytes[11..], &[0x1E]);
+    }
+
+    #[test]
+    fn invalid_hscw_speed_skipped() {
+        let bytes = build_contest_message("A{H950}B{H4050}C{HX}D");
+        assert_eq!(bytes, b"ABCD");
+    }
+
+    #[test]
+    fn tune_sequence() {
+        let bytes = build_contest_message("{P1}{W2}{K10}{p0}");
+        assert_eq!(bytes, [0x18, 1, 0x1A, 2, 0x19, 10, 0x18, 0]);
+    }
+
+    #[test]
+    fn beacon_with_text() {
+        let bytes = build_contest_message("VVV DE K1EL{K5}{W30}");
+        assert_eq!(&bytes[..11], b"VVV DE K1EL");
+        assert_eq!(&bytes[11..], &[0x19, 5, 0x1A, 30]);
+    }
+
+    #[test]
+    fn out_of_range_tags_skipped() {
+        let bytes = build_contest_message("A{K100}B{W999}C{P2}D{Kx}E");
+        assert_eq!(bytes, b"ABCDE");
+    }
+
     #[test]
     fn unknown_prosign_skipped() {
         let bytes = build_contest_message("CQ<XX>TEST");
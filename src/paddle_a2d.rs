@@ -0,0 +1,131 @@
+//! Paddle A/D sampling for diagnosing paddle contacts.
+//!
+//! [`WinKeyer::sample_paddle_a2d`](crate::WinKeyer::sample_paddle_a2d) polls
+//! the admin Paddle A2D reading while the operator holds one paddle closed.
+//! A clean contact reads steady; a dirty or loose one wanders or bounces
+//! across the open/closed midpoint. Run it once per contact:
+//!
+//! ```no_run
+//! # async fn example(keyer: &winkey::WinKeyer) -> winkey::Result<()> {
+//! use std::time::Duration;
+//!
+//! println!("hold the dit paddle");
+//! let dit = keyer.sample_paddle_a2d(100, Duration::from_millis(20)).await?;
+//! if dit.is_noisy(8) {
+//!     println!("dit contact noisy: {}..={}", dit.min(), dit.max());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Firmware without the Paddle A2D command answers 0 to every read; see
+//! [`PaddleA2dSamples::is_unsupported`].
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// A/D readings taken at regular intervals.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PaddleA2dSamples {
+    /// Readings with their time since sampling started.
+    pub trace: Vec<(Duration, u8)>,
+}
+
+impl PaddleA2dSamples {
+    /// Number of readings per value.
+    pub fn histogram(&self) -> BTreeMap<u8, usize> {
+        let mut counts = BTreeMap::new();
+        for &(_, value) in &self.trace {
+            *counts.entry(value).or_default() += 1;
+        }
+        counts
+    }
+
+    /// Lowest reading (0 if there are none).
+    pub fn min(&self) -> u8 {
+        self.values().min().unwrap_or(0)
+    }
+
+    /// Highest reading (0 if there are none).
+    pub fn max(&self) -> u8 {
+        self.values().max().unwrap_or(0)
+    }
+
+    /// Mean reading.
+    pub fn mean(&self) -> f32 {
+        if self.trace.is_empty() {
+            return 0.0;
+        }
+        self.values().map(f32::from).sum::<f32>() / self.trace.len() as f32
+    }
+
+    /// Difference between the highest and lowest reading.
+    pub fn spread(&self) -> u8 {
+        self.max() - self.min()
+    }
+
+    /// Times the reading crossed `threshold`: contact bounce while held.
+    pub fn crossings(&self, threshold: u8) -> usize {
+        self.values()
+            .map(|v| v >= threshold)
+            .collect::<Vec<_>>()
+            .windows(2)
+            .filter(|w| w[0] != w[1])
+            .count()
+    }
+
+    /// Readings wander by more than `tolerance`, or bounce across the
+    /// midpoint of the A/D range.
+    pub fn is_noisy(&self, tolerance: u8) -> bool {
+        self.spread() > tolerance || self.crossings(0x80) > 0
+    }
+
+    /// At least one reading was taken and every reading was 0: the firmware
+    /// doesn't implement Paddle A2D. An empty trace proves nothing.
+    pub fn is_unsupported(&self) -> bool {
+        !self.trace.is_empty() && self.values().all(|v| v == 0)
+    }
+
+    fn values(&self) -> impl Iterator<Item = u8> + '_ {
+        self.trace.iter().map(|&(_, v)| v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(values: &[u8]) -> PaddleA2dSamples {
+        PaddleA2dSamples {
+            trace: values
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| (Duration::from_millis(i as u64 * 20), v))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn steady_contact() {
+        let s = samples(&[12, 13, 12, 12, 14]);
+        assert_eq!((s.min(), s.max(), s.spread()), (12, 14, 2));
+        assert_eq!(s.histogram().get(&12), Some(&3));
+        assert!((s.mean() - 12.6).abs() < 1e-4);
+        assert!(!s.is_noisy(4));
+        assert!(!s.is_unsupported());
+    }
+
+    #[test]
+    fn bouncing_contact() {
+        let s = samples(&[10, 10, 200, 12, 11]);
+        assert_eq!(s.crossings(0x80), 2);
+        assert!(s.is_noisy(255));
+    }
+
+    #[test]
+    fn all_zero_is_unsupported() {
+        assert!(samples(&[0, 0, 0]).is_unsupported());
+        assert!(!samples(&[0, 0, 0]).is_noisy(0));
+        assert!(!samples(&[]).is_unsupported());
+    }
+}
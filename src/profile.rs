@@ -0,0 +1,242 @@
+//! Keyer profiles: a complete set of WinKeyer settings as plain data.
+//!
+//! With the `serde` feature, [`KeyerProfile`] can be loaded from any serde
+//! format. Missing fields take the builder defaults, so a TOML file only
+//! needs the settings it changes:
+//!
+//! ```toml
+//! speed_wpm = 28
+//! paddle_mode = "iambic_a"
+//! contest_spacing = true
+//! sidetone_hz = 650
+//! pin_config = "PTT_ENABLE | KEY_OUTPUT"
+//! ```
+//!
+//! Use [`WinKeyerBuilder::from_profile`](crate::WinKeyerBuilder::from_profile)
+//! to connect with a profile, or
+//! [`WinKeyer::apply_profile`](crate::WinKeyer::apply_profile) to switch a
+//! running keyer.
+
+use crate::builder::{validate_paddle_timing, validate_settings};
+use crate::error::{Error, Result};
+use crate::protocol::types::{
+    sidetone_byte, LoadDefaults, ModeRegister, PaddleMode, PinConfig, WinKeyerVersion,
+};
+
+/// Mode register bits a profile controls. The rest (echo, watchdog) belong
+/// to the crate and are preserved.
+const PROFILE_MODE_BITS: u8 = 0x3B;
+
+/// Every setting `WinKeyerBuilder` sends at connect time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct KeyerProfile {
+    /// CW speed in WPM (5-99).
+    pub speed_wpm: u8,
+    pub paddle_mode: PaddleMode,
+    pub contest_spacing: bool,
+    pub auto_space: bool,
+    pub swap_paddles: bool,
+    /// Sidetone frequency in Hz (500-4000).
+    pub sidetone_hz: u16,
+    /// Keying weight (10-90).
+    pub weight: u8,
+    /// Dit/dah ratio (33-66, 50 = 3:1).
+    pub dit_dah_ratio: u8,
+    /// Farnsworth speed (0 = disabled).
+    pub farnsworth_wpm: u8,
+    /// Speed pot minimum in WPM.
+    pub min_wpm: u8,
+    /// Speed pot range in WPM.
+    pub wpm_range: u8,
+    /// PTT lead-in in milliseconds (10ms resolution, max 2500).
+    pub ptt_lead_in_ms: u16,
+    /// PTT tail in milliseconds (10ms resolution, max 2500).
+    pub ptt_tail_ms: u16,
+    pub pin_config: PinConfig,
+    /// Key compensation in milliseconds (0-250).
+    pub key_compensation_ms: u8,
+    /// First extension in milliseconds (0-250).
+    pub first_extension_ms: u8,
+    /// Paddle switchpoint (10-90).
+    pub paddle_switchpoint: u8,
+    /// X1MODE extension register.
+    pub x1_mode: u8,
+    /// X2MODE extension register (WK3).
+    pub x2_mode: u8,
+}
+
+impl Default for KeyerProfile {
+    /// The same defaults as [`WinKeyerBuilder::new`](crate::WinKeyerBuilder::new).
+    fn default() -> Self {
+        Self {
+            speed_wpm: 20,
+            paddle_mode: PaddleMode::default(),
+            contest_spacing: false,
+            auto_space: false,
+            swap_paddles: false,
+            sidetone_hz: 800,
+            weight: 50,
+            dit_dah_ratio: 50,
+            farnsworth_wpm: 0,
+            min_wpm: 10,
+            wpm_range: 25,
+            ptt_lead_in_ms: 0,
+            ptt_tail_ms: 0,
+            pin_config: PinConfig::default(),
+            key_compensation_ms: 0,
+            first_extension_ms: 0,
+            paddle_switchpoint: 50,
+            x1_mode: 0,
+            x2_mode: 0,
+        }
+    }
+}
+
+impl KeyerProfile {
+    /// Check the profile against the same limits as the builder, plus the
+    /// sidetone and PTT timing ranges.
+    pub fn validate(&self) -> Result<()> {
+        validate_settings(
+            self.speed_wpm,
+            self.weight,
+            self.dit_dah_ratio,
+            self.min_wpm,
+            self.wpm_range,
+        )?;
+        validate_paddle_timing(
+            self.key_compensation_ms,
+            self.first_extension_ms,
+            self.paddle_switchpoint,
+        )?;
+        if !(500..=4000).contains(&self.sidetone_hz) {
+            return Err(Error::InvalidParameter(format!(
+                "sidetone must be 500-4000 Hz, got {}",
+                self.sidetone_hz
+            )));
+        }
+        for (name, ms) in [("lead-in", self.ptt_lead_in_ms), ("tail", self.ptt_tail_ms)] {
+            if ms > 2500 {
+                return Err(Error::InvalidParameter(format!(
+                    "PTT {name} must be at most 2500 ms, got {ms}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Mode register byte for this profile, keeping the echo and watchdog
+    /// bits of `current`.
+    pub fn mode_register(&self, current: u8) -> u8 {
+        let mut flags = ModeRegister::from_bits_truncate(current & !PROFILE_MODE_BITS);
+        flags.set(ModeRegister::CONTEST_SPACING, self.contest_spacing);
+        flags.set(ModeRegister::AUTO_SPACE, self.auto_space);
+        flags.set(ModeRegister::SWAP_PADDLES, self.swap_paddles);
+        flags.with_paddle_mode(self.paddle_mode)
+    }
+
+    /// The Load Defaults block for this profile on `version` hardware.
+    pub fn load_defaults(&self, version: WinKeyerVersion, current_mode: u8) -> LoadDefaults {
+        LoadDefaults {
+            mode_register: self.mode_register(current_mode),
+            speed_wpm: self.speed_wpm,
+            sidetone: sidetone_byte(self.sidetone_hz, version),
+            weight: self.weight,
+            lead_in_time: (self.ptt_lead_in_ms / 10).min(250) as u8,
+            tail_time: (self.ptt_tail_ms / 10).min(250) as u8,
+            min_wpm: self.min_wpm,
+            wpm_range: self.wpm_range,
+            x2_mode: self.x2_mode,
+            key_compensation: self.key_compensation_ms,
+            farnsworth_wpm: self.farnsworth_wpm,
+            paddle_setpoint: self.paddle_switchpoint,
+            dit_dah_ratio: self.dit_dah_ratio,
+            pin_config: self.pin_config.bits(),
+            x1_mode: self.x1_mode,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mode_register_keeps_echo_bits() {
+        let profile = KeyerProfile {
+            paddle_mode: PaddleMode::IambicA,
+            contest_spacing: true,
+            ..Default::default()
+        };
+        // Echo bits from the current register survive; old swap bit is cleared.
+        assert_eq!(profile.mode_register(0xC8), 0xC0 | 0x10 | 0x01);
+        assert_eq!(KeyerProfile::default().mode_register(0x44), 0x44);
+    }
+
+    #[test]
+    fn validate_uses_builder_limits() {
+        assert!(KeyerProfile::default().validate().is_ok());
+        let slow = KeyerProfile {
+            speed_wpm: 4,
+            ..Default::default()
+        };
+        assert!(slow.validate().is_err());
+        let wide = KeyerProfile {
+            min_wpm: 80,
+            wpm_range: 30,
+            ..Default::default()
+        };
+        assert!(wide.validate().is_err());
+    }
+
+    #[test]
+    fn validate_sidetone_and_ptt_timing() {
+        for hz in [499, 4001] {
+            let profile = KeyerProfile {
+                sidetone_hz: hz,
+                ..Default::default()
+            };
+            assert!(matches!(profile.validate(), Err(Error::InvalidParameter(_))), "{hz}");
+        }
+        let edges = KeyerProfile {
+            sidetone_hz: 4000,
+            ptt_lead_in_ms: 2500,
+            ptt_tail_ms: 2500,
+            ..Default::default()
+        };
+        assert!(edges.validate().is_ok());
+        let long_lead = KeyerProfile {
+            ptt_lead_in_ms: 2510,
+            ..Default::default()
+        };
+        assert!(matches!(long_lead.validate(), Err(Error::InvalidParameter(_))));
+        let long_tail = KeyerProfile {
+            ptt_tail_ms: 3000,
+            ..Default::default()
+        };
+        assert!(matches!(long_tail.validate(), Err(Error::InvalidParameter(_))));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn partial_toml_uses_defaults() {
+        let profile: KeyerProfile = toml::from_str(
+            r#"
+            speed_wpm = 28
+            paddle_mode = "iambic_a"
+            contest_spacing = true
+            pin_config = "PTT_ENABLE | KEY_OUTPUT"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(profile.speed_wpm, 28);
+        assert_eq!(profile.paddle_mode, PaddleMode::IambicA);
+        assert!(profile.contest_spacing);
+        assert_eq!(profile.pin_config, PinConfig::PTT_ENABLE | PinConfig::KEY_OUTPUT);
+        assert_eq!(profile.weight, 50);
+
+        let text = toml::to_string(&profile).unwrap();
+        assert_eq!(toml::from_str::<KeyerProfile>(&text).unwrap(), profile);
+    }
+}
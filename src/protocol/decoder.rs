@@ -0,0 +1,624 @@
+//! Command decoding: host → WinKeyer.
+//!
+//! The inverse of [`command`](super::command). [`Command`] is one typed host
+//! command; [`CommandDecoder`] turns a byte stream (possibly split at any
+//! point) back into commands. Used by the emulator, protocol traces and
+//! tests that assert on what was written to the port.
+
+use std::fmt;
+
+use crate::protocol::command;
+use crate::protocol::types::LoadDefaults;
+
+/// Size of the EEPROM image following `admin_load_eeprom`.
+pub const EEPROM_SIZE: usize = 256;
+
+/// A single host → WinKeyer command.
+///
+/// Decoding then [`encode`](Command::encode)-ing reproduces the input for
+/// every byte sequence the `command` module generates. Boolean arguments
+/// decode any non-zero byte as `true`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    // Admin (0x00 prefix)
+    Calibrate(u8),
+    Reset,
+    HostOpen,
+    HostClose,
+    EchoTest(u8),
+    PaddleA2d,
+    SpeedA2d,
+    GetValues,
+    AdminReserved,
+    GetFwMajorRev,
+    SetWk1Mode,
+    SetWk2Mode,
+    DumpEeprom,
+    /// Load EEPROM followed by its [`EEPROM_SIZE`]-byte image.
+    LoadEeprom(Vec<u8>),
+    SendStoredMessage(u8),
+    LoadX1Mode(u8),
+    FirmwareUpdate,
+    SetLowBaud,
+    SetHighBaud,
+    SetRttyRegisters(u8, u8),
+    SetWk3Mode,
+    ReadVcc,
+    LoadX2Mode(u8),
+    GetFwMinorRev,
+    GetIcType,
+    SetSidetoneVolume(u8),
+    /// Admin sub-command this crate does not know.
+    UnknownAdmin(u8),
+
+    // Immediate (0x01 - 0x15)
+    SidetoneControl(u8),
+    SetSpeed(u8),
+    SetWeight(u8),
+    SetPttTiming { lead_in: u8, tail: u8 },
+    SetSpeedPot { min: u8, range: u8 },
+    SetPause(bool),
+    GetSpeedPot,
+    Backspace,
+    SetPinConfig(u8),
+    ClearBuffer,
+    KeyImmediate(bool),
+    SetHscwSpeed(u8),
+    SetFarnsworth(u8),
+    SetModeRegister(u8),
+    LoadDefaults(LoadDefaults),
+    SetFirstExtension(u8),
+    SetKeyCompensation(u8),
+    SetPaddleSwitchpoint(u8),
+    Null,
+    SoftwarePaddle { dit: bool, dah: bool },
+    RequestStatus,
+
+    // Buffered (0x16 - 0x1F)
+    /// Pointer command: sub-command 0x00 has no argument, the others one.
+    Pointer { subcmd: u8, arg: Option<u8> },
+    SetRatio(u8),
+    BufferedPtt(bool),
+    KeyBuffered(u8),
+    BufferedWait(u8),
+    Merge(u8, u8),
+    BufferedSpeed(u8),
+    BufferedHscwSpeed(u8),
+    CancelBufferedSpeed,
+    BufferedNop,
+
+    /// A run of characters to send (0x20 - 0x7F).
+    Text(String),
+    /// A byte that is not a valid host command (0x80+).
+    Unknown(u8),
+}
+
+impl Command {
+    /// Decode the command at the start of `bytes`.
+    ///
+    /// Returns the command and how many bytes it used, or `None` if `bytes`
+    /// ends before the command is complete. Text runs stop at the first
+    /// non-text byte or the end of `bytes`.
+    pub fn decode(bytes: &[u8]) -> Option<(Command, usize)> {
+        let first = *bytes.first()?;
+        let arg = |i: usize| bytes.get(i).copied();
+        let one = |f: fn(u8) -> Command| Some((f(arg(1)?), 2));
+
+        match first {
+            0x00 => Self::decode_admin(bytes),
+            0x01 => one(Command::SidetoneControl),
+            0x02 => one(Command::SetSpeed),
+            0x03 => one(Command::SetWeight),
+            0x04 => Some((
+                Command::SetPttTiming {
+                    lead_in: arg(1)?,
+                    tail: arg(2)?,
+                },
+                3,
+            )),
+            0x05 => {
+                arg(3)?;
+                Some((
+                    Command::SetSpeedPot {
+                        min: arg(1)?,
+                        range: arg(2)?,
+                    },
+                    4,
+                ))
+            }
+            0x06 => Some((Command::SetPause(arg(1)? != 0), 2)),
+            0x07 => Some((Command::GetSpeedPot, 1)),
+            0x08 => Some((Command::Backspace, 1)),
+            0x09 => one(Command::SetPinConfig),
+            0x0A => Some((Command::ClearBuffer, 1)),
+            0x0B => Some((Command::KeyImmediate(arg(1)? != 0), 2)),
+            0x0C => one(Command::SetHscwSpeed),
+            0x0D => one(Command::SetFarnsworth),
+            0x0E => one(Command::SetModeRegister),
+            0x0F => {
+                let params: [u8; 15] = bytes.get(1..16)?.try_into().unwrap();
+                Some((Command::LoadDefaults(LoadDefaults::from_bytes(&params)), 16))
+            }
+            0x10 => one(Command::SetFirstExtension),
+            0x11 => one(Command::SetKeyCompensation),
+            0x12 => one(Command::SetPaddleSwitchpoint),
+            0x13 => Some((Command::Null, 1)),
+            0x14 => {
+                let state = arg(1)?;
+                Some((
+                    Command::SoftwarePaddle {
+                        dit: state & 0x01 != 0,
+                        dah: state & 0x02 != 0,
+                    },
+                    2,
+                ))
+            }
+            0x15 => Some((Command::RequestStatus, 1)),
+            0x16 => match arg(1)? {
+                0x00 => Some((Command::Pointer { subcmd: 0, arg: None }, 2)),
+                subcmd => Some((
+                    Command::Pointer {
+                        subcmd,
+                        arg: Some(arg(2)?),
+                    },
+                    3,
+                )),
+            },
+            0x17 => one(Command::SetRatio),
+            0x18 => Some((Command::BufferedPtt(arg(1)? != 0), 2)),
+            0x19 => one(Command::KeyBuffered),
+            0x1A => one(Command::BufferedWait),
+            0x1B => Some((Command::Merge(arg(1)?, arg(2)?), 3)),
+            0x1C => one(Command::BufferedSpeed),
+            0x1D => one(Command::BufferedHscwSpeed),
+            0x1E => Some((Command::CancelBufferedSpeed, 1)),
+            0x1F => Some((Command::BufferedNop, 1)),
+            0x20..=0x7F => {
+                let len = bytes
+                    .iter()
+                    .position(|b| !(0x20..=0x7F).contains(b))
+                    .unwrap_or(bytes.len());
+                let text = bytes[..len].iter().map(|&b| b as char).collect();
+                Some((Command::Text(text), len))
+            }
+            byte => Some((Command::Unknown(byte), 1)),
+        }
+    }
+
+    fn decode_admin(bytes: &[u8]) -> Option<(Command, usize)> {
+        let sub = *bytes.get(1)?;
+        let arg = |i: usize| bytes.get(i).copied();
+        let bare = |cmd: Command| Some((cmd, 2));
+        let one = |f: fn(u8) -> Command| Some((f(arg(2)?), 3));
+
+        match sub {
+            0x00 => one(Command::Calibrate),
+            0x01 => bare(Command::Reset),
+            0x02 => bare(Command::HostOpen),
+            0x03 => bare(Command::HostClose),
+            0x04 => one(Command::EchoTest),
+            0x05 => bare(Command::PaddleA2d),
+            0x06 => bare(Command::SpeedA2d),
+            0x07 => bare(Command::GetValues),
+            0x08 => bare(Command::AdminReserved),
+            0x09 => bare(Command::GetFwMajorRev),
+            0x0A => bare(Command::SetWk1Mode),
+            0x0B => bare(Command::SetWk2Mode),
+            0x0C => bare(Command::DumpEeprom),
+            0x0D => {
+                let image = bytes.get(2..2 + EEPROM_SIZE)?;
+                Some((Command::LoadEeprom(image.to_vec()), 2 + EEPROM_SIZE))
+            }
+            0x0E => one(Command::SendStoredMessage),
+            0x0F => one(Command::LoadX1Mode),
+            0x10 => bare(Command::FirmwareUpdate),
+            0x11 => bare(Command::SetLowBaud),
+            0x12 => bare(Command::SetHighBaud),
+            0x13 => Some((Command::SetRttyRegisters(arg(2)?, arg(3)?), 4)),
+            0x14 => bare(Command::SetWk3Mode),
+            0x15 => bare(Command::ReadVcc),
+            0x16 => one(Command::LoadX2Mode),
+            0x17 => bare(Command::GetFwMinorRev),
+            0x18 => bare(Command::GetIcType),
+            0x19 => one(Command::SetSidetoneVolume),
+            sub => bare(Command::UnknownAdmin(sub)),
+        }
+    }
+
+    /// Encode back to the bytes sent on the wire.
+    pub fn encode(&self) -> Vec<u8> {
+        use Command::*;
+        match self {
+            Calibrate(v) => command::admin_calibrate(*v).to_vec(),
+            Reset => command::admin_reset().to_vec(),
+            HostOpen => command::admin_host_open().to_vec(),
+            HostClose => command::admin_host_close().to_vec(),
+            EchoTest(v) => command::admin_echo_test(*v).to_vec(),
+            PaddleA2d => command::admin_paddle_a2d().to_vec(),
+            SpeedA2d => command::admin_speed_a2d().to_vec(),
+            GetValues => command::admin_get_values().to_vec(),
+            AdminReserved => command::admin_reserved().to_vec(),
+            GetFwMajorRev => command::admin_get_fw_major_rev().to_vec(),
+            SetWk1Mode => command::admin_set_wk1_mode().to_vec(),
+            SetWk2Mode => command::admin_set_wk2_mode().to_vec(),
+            DumpEeprom => command::admin_dump_eeprom().to_vec(),
+            LoadEeprom(image) => {
+                let mut out = command::admin_load_eeprom().to_vec();
+                out.extend_from_slice(image);
+                out
+            }
+            SendStoredMessage(slot) => command::admin_send_msg(*slot).to_vec(),
+            LoadX1Mode(v) => command::admin_load_x1mode(*v).to_vec(),
+            FirmwareUpdate => command::admin_firmware_update().to_vec(),
+            SetLowBaud => command::admin_set_low_baud().to_vec(),
+            SetHighBaud => command::admin_set_high_baud().to_vec(),
+            SetRttyRegisters(p1, p2) => command::admin_set_rtty_registers(*p1, *p2).to_vec(),
+            SetWk3Mode => command::admin_set_wk3_mode().to_vec(),
+            ReadVcc => command::admin_read_vcc().to_vec(),
+            LoadX2Mode(v) => command::admin_load_x2mode(*v).to_vec(),
+            GetFwMinorRev => command::admin_get_fw_minor_rev().to_vec(),
+            GetIcType => command::admin_get_ic_type().to_vec(),
+            SetSidetoneVolume(v) => command::admin_set_sidetone_volume(*v).to_vec(),
+            UnknownAdmin(sub) => vec![0x00, *sub],
+
+            SidetoneControl(v) => command::sidetone_control(*v).to_vec(),
+            SetSpeed(wpm) => command::set_speed(*wpm).to_vec(),
+            SetWeight(w) => command::set_weight(*w).to_vec(),
+            SetPttTiming { lead_in, tail } => command::set_ptt_timing(*lead_in, *tail).to_vec(),
+            SetSpeedPot { min, range } => command::set_speed_pot(*min, *range).to_vec(),
+            SetPause(on) => command::set_pause(*on).to_vec(),
+            GetSpeedPot => command::get_speed_pot().to_vec(),
+            Backspace => command::backspace().to_vec(),
+            SetPinConfig(v) => command::set_pin_config(*v).to_vec(),
+            ClearBuffer => command::clear_buffer().to_vec(),
+            KeyImmediate(down) => command::key_immediate(*down).to_vec(),
+            SetHscwSpeed(v) => command::set_hscw_speed(*v).to_vec(),
+            SetFarnsworth(wpm) => command::set_farnsworth(*wpm).to_vec(),
+            SetModeRegister(v) => command::set_mode_register(*v).to_vec(),
+            LoadDefaults(d) => command::load_defaults(d).to_vec(),
+            SetFirstExtension(v) => command::set_first_extension(*v).to_vec(),
+            SetKeyCompensation(v) => command::set_key_compensation(*v).to_vec(),
+            SetPaddleSwitchpoint(v) => command::set_paddle_switchpoint(*v).to_vec(),
+            Null => command::null_command().to_vec(),
+            SoftwarePaddle { dit, dah } => command::software_paddle(*dit, *dah).to_vec(),
+            RequestStatus => command::request_status().to_vec(),
+
+            Pointer { subcmd, arg } => match arg {
+                Some(a) => command::pointer_cmd_with_data(*subcmd, &[*a]),
+                None => command::pointer_cmd(*subcmd).to_vec(),
+            },
+            SetRatio(r) => command::set_ratio(*r).to_vec(),
+            BufferedPtt(on) => command::buffered_ptt(*on).to_vec(),
+            KeyBuffered(s) => command::key_buffered(*s).to_vec(),
+            BufferedWait(s) => command::buffered_wait(*s).to_vec(),
+            Merge(c1, c2) => command::buffered_merge(*c1, *c2).to_vec(),
+            BufferedSpeed(wpm) => command::buffered_speed_change(*wpm).to_vec(),
+            BufferedHscwSpeed(v) => command::buffered_hscw_speed(*v).to_vec(),
+            CancelBufferedSpeed => command::cancel_buffered_speed().to_vec(),
+            BufferedNop => command::buffered_nop().to_vec(),
+
+            Text(text) => text.bytes().collect(),
+            Unknown(byte) => vec![*byte],
+        }
+    }
+
+    /// True for commands WinKeyer queues in its buffer (0x16 - 0x1F and
+    /// text) rather than executing immediately.
+    pub fn is_buffered(&self) -> bool {
+        matches!(
+            self,
+            Command::Pointer { .. }
+                | Command::SetRatio(_)
+                | Command::BufferedPtt(_)
+                | Command::KeyBuffered(_)
+                | Command::BufferedWait(_)
+                | Command::Merge(..)
+                | Command::BufferedSpeed(_)
+                | Command::BufferedHscwSpeed(_)
+                | Command::CancelBufferedSpeed
+                | Command::BufferedNop
+                | Command::Text(_)
+        )
+    }
+
+    /// Number of reply bytes WinKeyer sends back for a query, if any.
+    ///
+    /// Status and speed pot bytes are unsolicited and not counted here.
+    pub fn reply_len(&self) -> Option<usize> {
+        match self {
+            Command::GetValues => Some(15),
+            Command::DumpEeprom => Some(EEPROM_SIZE),
+            Command::HostOpen
+            | Command::EchoTest(_)
+            | Command::PaddleA2d
+            | Command::SpeedA2d
+            | Command::GetFwMajorRev
+            | Command::ReadVcc
+            | Command::GetFwMinorRev
+            | Command::GetIcType => Some(1),
+            _ => None,
+        }
+    }
+}
+
+/// Mnemonic form for traces, e.g. `SET SPEED 25`.
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Command::*;
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        match self {
+            Calibrate(v) => write!(f, "CALIBRATE {v}"),
+            Reset => f.write_str("RESET"),
+            HostOpen => f.write_str("HOST OPEN"),
+            HostClose => f.write_str("HOST CLOSE"),
+            EchoTest(v) => write!(f, "ECHO TEST 0x{v:02X}"),
+            PaddleA2d => f.write_str("PADDLE A2D"),
+            SpeedA2d => f.write_str("SPEED A2D"),
+            GetValues => f.write_str("GET VALUES"),
+            AdminReserved => f.write_str("ADMIN RESERVED"),
+            GetFwMajorRev => f.write_str("GET FW MAJOR REV"),
+            SetWk1Mode => f.write_str("SET WK1 MODE"),
+            SetWk2Mode => f.write_str("SET WK2 MODE"),
+            DumpEeprom => f.write_str("DUMP EEPROM"),
+            LoadEeprom(image) => write!(f, "LOAD EEPROM ({} bytes)", image.len()),
+            SendStoredMessage(slot) => write!(f, "SEND MESSAGE {slot}"),
+            LoadX1Mode(v) => write!(f, "LOAD X1MODE 0x{v:02X}"),
+            FirmwareUpdate => f.write_str("FIRMWARE UPDATE"),
+            SetLowBaud => f.write_str("SET LOW BAUD"),
+            SetHighBaud => f.write_str("SET HIGH BAUD"),
+            SetRttyRegisters(p1, p2) => write!(f, "SET RTTY REGISTERS 0x{p1:02X} 0x{p2:02X}"),
+            SetWk3Mode => f.write_str("SET WK3 MODE"),
+            ReadVcc => f.write_str("READ VCC"),
+            LoadX2Mode(v) => write!(f, "LOAD X2MODE 0x{v:02X}"),
+            GetFwMinorRev => f.write_str("GET FW MINOR REV"),
+            GetIcType => f.write_str("GET IC TYPE"),
+            SetSidetoneVolume(v) => write!(f, "SET SIDETONE VOLUME {v}"),
+            UnknownAdmin(sub) => write!(f, "ADMIN 0x{sub:02X}"),
+
+            SidetoneControl(v) => write!(f, "SIDETONE 0x{v:02X}"),
+            SetSpeed(wpm) => write!(f, "SET SPEED {wpm}"),
+            SetWeight(w) => write!(f, "SET WEIGHT {w}"),
+            SetPttTiming { lead_in, tail } => {
+                write!(f, "SET PTT TIMING lead-in {lead_in} tail {tail}")
+            }
+            SetSpeedPot { min, range } => write!(f, "SET SPEED POT min {min} range {range}"),
+            SetPause(on) => write!(f, "PAUSE {}", on_off(*on)),
+            GetSpeedPot => f.write_str("GET SPEED POT"),
+            Backspace => f.write_str("BACKSPACE"),
+            SetPinConfig(v) => write!(f, "SET PIN CONFIG 0x{v:02X}"),
+            ClearBuffer => f.write_str("CLEAR BUFFER"),
+            KeyImmediate(down) => write!(f, "KEY IMMEDIATE {}", if *down { "down" } else { "up" }),
+            SetHscwSpeed(v) => write!(f, "SET HSCW SPEED {v}"),
+            SetFarnsworth(wpm) => write!(f, "SET FARNSWORTH {wpm}"),
+            SetModeRegister(v) => write!(f, "SET MODE 0x{v:02X}"),
+            LoadDefaults(d) => write!(
+                f,
+                "LOAD DEFAULTS speed {} mode 0x{:02X} pot {}+{}",
+                d.speed_wpm, d.mode_register, d.min_wpm, d.wpm_range
+            ),
+            SetFirstExtension(v) => write!(f, "SET FIRST EXTENSION {v}"),
+            SetKeyCompensation(v) => write!(f, "SET KEY COMPENSATION {v}"),
+            SetPaddleSwitchpoint(v) => write!(f, "SET PADDLE SWITCHPOINT {v}"),
+            Null => f.write_str("NULL"),
+            SoftwarePaddle { dit, dah } => match (dit, dah) {
+                (false, false) => f.write_str("SOFTWARE PADDLE none"),
+                (true, false) => f.write_str("SOFTWARE PADDLE dit"),
+                (false, true) => f.write_str("SOFTWARE PADDLE dah"),
+                (true, true) => f.write_str("SOFTWARE PADDLE dit|dah"),
+            },
+            RequestStatus => f.write_str("REQUEST STATUS"),
+
+            Pointer { subcmd, arg: None } => write!(f, "POINTER {subcmd}"),
+            Pointer { subcmd, arg: Some(a) } => write!(f, "POINTER {subcmd} 0x{a:02X}"),
+            SetRatio(r) => write!(f, "SET RATIO {r}"),
+            BufferedPtt(on) => write!(f, "BUFFERED PTT {}", on_off(*on)),
+            KeyBuffered(s) => write!(f, "KEY DOWN {s}s"),
+            BufferedWait(s) => write!(f, "WAIT {s}s"),
+            Merge(c1, c2) => write!(f, "MERGE '{}{}'", *c1 as char, *c2 as char),
+            BufferedSpeed(wpm) => write!(f, "BUFFERED SPEED {wpm}"),
+            BufferedHscwSpeed(v) => write!(f, "BUFFERED HSCW SPEED {v}"),
+            CancelBufferedSpeed => f.write_str("CANCEL BUFFERED SPEED"),
+            BufferedNop => f.write_str("NOP"),
+
+            Text(text) => write!(f, "TEXT {text:?}"),
+            Unknown(byte) => write!(f, "UNKNOWN 0x{byte:02X}"),
+        }
+    }
+}
+
+/// Incremental decoder for a host → WinKeyer byte stream.
+///
+/// Feed bytes as they arrive with [`push`](Self::push) and pull complete
+/// commands with [`next_command`](Self::next_command). A command split
+/// across reads is held until the rest arrives.
+///
+/// ```
+/// use winkey::protocol::decoder::{Command, CommandDecoder};
+///
+/// let mut decoder = CommandDecoder::new();
+/// decoder.push(&[0x02, 25, b'C']);
+/// decoder.push(&[b'Q', 0x1B, b'A']);
+/// assert_eq!(decoder.next_command(), Some(Command::SetSpeed(25)));
+/// assert_eq!(decoder.next_command(), Some(Command::Text("CQ".into())));
+/// assert_eq!(decoder.next_command(), None); // merge needs one more byte
+/// decoder.push(b"R");
+/// assert_eq!(decoder.next_command(), Some(Command::Merge(b'A', b'R')));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CommandDecoder {
+    buf: Vec<u8>,
+}
+
+impl CommandDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append received bytes.
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Next complete command, or `None` until more bytes arrive.
+    pub fn next_command(&mut self) -> Option<Command> {
+        let (cmd, len) = Command::decode(&self.buf)?;
+        self.buf.drain(..len);
+        Some(cmd)
+    }
+
+    /// Push `bytes` and return every command now complete.
+    pub fn decode(&mut self, bytes: &[u8]) -> Vec<Command> {
+        self.push(bytes);
+        std::iter::from_fn(|| self.next_command()).collect()
+    }
+
+    /// Bytes held back waiting for the rest of a command.
+    pub fn pending(&self) -> &[u8] {
+        &self.buf
+    }
+}
+
+/// Decode a complete byte sequence. Trailing bytes of an unfinished
+/// command are ignored.
+pub fn decode_commands(bytes: &[u8]) -> Vec<Command> {
+    CommandDecoder::new().decode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(bytes: &[u8]) -> Command {
+        let (cmd, len) = Command::decode(bytes).expect("incomplete");
+        assert_eq!(len, bytes.len(), "{cmd:?}");
+        assert_eq!(cmd.encode(), bytes, "{cmd:?}");
+        cmd
+    }
+
+    #[test]
+    fn admin_round_trip() {
+        assert_eq!(round_trip(&command::admin_host_open()), Command::HostOpen);
+        assert_eq!(round_trip(&command::admin_echo_test(0x42)), Command::EchoTest(0x42));
+        assert_eq!(round_trip(&command::admin_send_msg(3)), Command::SendStoredMessage(3));
+        assert_eq!(
+            round_trip(&command::admin_set_rtty_registers(1, 2)),
+            Command::SetRttyRegisters(1, 2)
+        );
+        assert_eq!(
+            round_trip(&command::admin_set_sidetone_volume(4)),
+            Command::SetSidetoneVolume(4)
+        );
+        for sub in (0x00..=0x19u8).filter(|&sub| sub != 0x0D) {
+            let bytes = [0x00, sub, 7, 8];
+            let (_, len) = Command::decode(&bytes).unwrap();
+            round_trip(&bytes[..len]);
+        }
+        assert_eq!(round_trip(&[0x00, 0x7E]), Command::UnknownAdmin(0x7E));
+    }
+
+    #[test]
+    fn display_mnemonics() {
+        let shown: Vec<String> = decode_commands(&[0x02, 25, 0x00, 0x02, b'C', b'Q', 0x1B, b'A', b'R'])
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(shown, ["SET SPEED 25", "HOST OPEN", "TEXT \"CQ\"", "MERGE 'AR'"]);
+        assert_eq!(Command::HostOpen.reply_len(), Some(1));
+        assert_eq!(Command::SetSpeed(25).reply_len(), None);
+    }
+
+    #[test]
+    fn load_eeprom_waits_for_image() {
+        let mut bytes = command::admin_load_eeprom().to_vec();
+        bytes.extend((0..=255u8).collect::<Vec<_>>());
+        assert!(Command::decode(&bytes[..200]).is_none());
+        match round_trip(&bytes) {
+            Command::LoadEeprom(image) => assert_eq!(image.len(), EEPROM_SIZE),
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn immediate_round_trip() {
+        assert_eq!(round_trip(&command::set_speed(28)), Command::SetSpeed(28));
+        assert_eq!(
+            round_trip(&command::set_ptt_timing(4, 3)),
+            Command::SetPttTiming { lead_in: 4, tail: 3 }
+        );
+        assert_eq!(
+            round_trip(&command::set_speed_pot(10, 25)),
+            Command::SetSpeedPot { min: 10, range: 25 }
+        );
+        assert_eq!(round_trip(&command::set_pause(true)), Command::SetPause(true));
+        assert_eq!(round_trip(&command::key_immediate(false)), Command::KeyImmediate(false));
+        assert_eq!(
+            round_trip(&command::software_paddle(false, true)),
+            Command::SoftwarePaddle { dit: false, dah: true }
+        );
+        assert_eq!(round_trip(&command::clear_buffer()), Command::ClearBuffer);
+        assert_eq!(round_trip(&command::request_status()), Command::RequestStatus);
+    }
+
+    #[test]
+    fn load_defaults_round_trip() {
+        let defaults = LoadDefaults {
+            speed_wpm: 32,
+            min_wpm: 12,
+            ..LoadDefaults::default()
+        };
+        match round_trip(&command::load_defaults(&defaults)) {
+            Command::LoadDefaults(d) => assert_eq!(d, defaults),
+            other => panic!("unexpected {other:?}"),
+        }
+        assert!(Command::decode(&command::load_defaults(&defaults)[..15]).is_none());
+    }
+
+    #[test]
+    fn buffered_round_trip() {
+        assert_eq!(round_trip(&command::pointer_cmd(0x00)), Command::Pointer { subcmd: 0, arg: None });
+        assert_eq!(
+            round_trip(&command::pointer_cmd_with_data(0x03, &[5])),
+            Command::Pointer { subcmd: 3, arg: Some(5) }
+        );
+        assert_eq!(round_trip(&command::buffered_merge(b'S', b'K')), Command::Merge(b'S', b'K'));
+        assert_eq!(round_trip(&command::buffered_speed_change(15)), Command::BufferedSpeed(15));
+        assert_eq!(round_trip(&command::cancel_buffered_speed()), Command::CancelBufferedSpeed);
+        assert_eq!(round_trip(&command::buffered_ptt(true)), Command::BufferedPtt(true));
+        assert_eq!(round_trip(&command::set_ratio(45)), Command::SetRatio(45));
+        assert!(Command::Merge(b'A', b'R').is_buffered());
+        assert!(!Command::ClearBuffer.is_buffered());
+    }
+
+    #[test]
+    fn text_runs() {
+        assert_eq!(
+            decode_commands(b"CQ TEST\x1C\x0fDE K1EL"),
+            vec![
+                Command::Text("CQ TEST".into()),
+                Command::BufferedSpeed(15),
+                Command::Text("DE K1EL".into()),
+            ]
+        );
+        assert_eq!(decode_commands(&[0x90]), vec![Command::Unknown(0x90)]);
+    }
+
+    #[test]
+    fn streaming_split_anywhere() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&command::admin_host_open());
+        bytes.extend_from_slice(&command::load_defaults(&LoadDefaults::default()));
+        bytes.extend_from_slice(&command::set_speed_pot(5, 30));
+        bytes.extend_from_slice(&command::buffered_merge(b'A', b'R'));
+        bytes.extend_from_slice(&command::clear_buffer());
+        let whole = decode_commands(&bytes);
+        assert_eq!(whole.len(), 5);
+
+        for split in 1..bytes.len() {
+            let mut decoder = CommandDecoder::new();
+            let mut got = decoder.decode(&bytes[..split]);
+            got.extend(decoder.decode(&bytes[split..]));
+            assert_eq!(got, whole, "split at {split}");
+            assert!(decoder.pending().is_empty());
+        }
+    }
+}
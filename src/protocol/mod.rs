@@ -1,4 +1,5 @@
 pub mod command;
+pub mod decoder;
 pub mod response;
 pub mod types;
 pub mod version;
@@ -41,7 +41,9 @@ pub fn decode_status(byte: u8) -> KeyerStatus {
 
 /// Decode a speed pot byte into WPM.
 ///
-/// The pot value (0-31) is added to `min_wpm` to get the actual speed.
+/// The keyer scales the pot position to `0..=wpm_range` and reports it in
+/// the low 6 bits; that value is added to `min_wpm` to get the speed. The
+/// result is only right if `min_wpm` matches the keyer's current setting.
 pub fn decode_speed_pot(byte: u8, min_wpm: u8) -> u8 {
     let pot_value = byte & 0x3F;
     min_wpm.saturating_add(pot_value)
@@ -41,6 +41,8 @@ impl WinKeyerVersion {
 
 /// Paddle keying mode.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum PaddleMode {
     /// Iambic A (self-completing, no dot/dash memory)
     IambicA,
@@ -63,6 +65,16 @@ impl PaddleMode {
             Self::Bug => 0x30,
         }
     }
+
+    /// Decode from a mode-register byte (bits 5-4).
+    pub fn from_mode_bits(byte: u8) -> Self {
+        match byte & 0x30 {
+            0x00 => Self::IambicB,
+            0x10 => Self::IambicA,
+            0x20 => Self::Ultimatic,
+            _ => Self::Bug,
+        }
+    }
 }
 
 bitflags! {
@@ -77,6 +89,7 @@ bitflags! {
     /// - Bit 1: Auto-space
     /// - Bit 0: Contest spacing
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct ModeRegister: u8 {
         const PADDLE_WATCHDOG_DISABLE = 0x80;
         const PADDLE_ECHO    = 0x40;
@@ -116,6 +129,7 @@ bitflags! {
     /// On WKUSB, Pin 3 is the primary CW key output. We name bit 2 as
     /// KEY_OUTPUT for API clarity since it's the port most rigs connect to.
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct PinConfig: u8 {
         const PTT_ENABLE       = 0x01;
         const SIDETONE_ENABLE  = 0x02;
@@ -147,10 +161,33 @@ pub fn sidetone_byte(freq_hz: u16, version: WinKeyerVersion) -> u8 {
     }
 }
 
+/// Frequency in Hz for a sidetone control byte; the inverse of
+/// [`sidetone_byte`], up to its rounding.
+pub fn sidetone_hz(byte: u8, version: WinKeyerVersion) -> u16 {
+    if version.supports_wk3() {
+        (62500u32 / byte.max(1) as u32).clamp(500, 4000) as u16
+    } else {
+        (4000u32 / (byte & 0x0F).clamp(1, 10) as u32) as u16
+    }
+}
+
+/// HSCW speed byte for `lpm` letters per minute, or `None` if the keyer
+/// can't encode it.
+///
+/// The byte is LPM / 100, so only whole hundreds from 1000 to 8000 LPM are
+/// representable.
+pub fn hscw_speed_byte(lpm: u16) -> Option<u8> {
+    if lpm.is_multiple_of(100) && (1000..=8000).contains(&lpm) {
+        Some((lpm / 100) as u8)
+    } else {
+        None
+    }
+}
+
 /// Parameters for the Load Defaults command (0x0F, 15 bytes).
 ///
 /// Field order per K1EL WK3 Datasheet v1.3, Table 13.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct LoadDefaults {
     pub mode_register: u8,
     pub speed_wpm: u8,
@@ -213,12 +250,42 @@ impl LoadDefaults {
             self.x1_mode,
         ]
     }
+    /// Decode the 15-byte parameter block (without the 0x0F prefix).
+    pub fn from_bytes(bytes: &[u8; 15]) -> Self {
+        Self {
+            mode_register: bytes[0],
+            speed_wpm: bytes[1],
+            sidetone: bytes[2],
+            weight: bytes[3],
+            lead_in_time: bytes[4],
+            tail_time: bytes[5],
+            min_wpm: bytes[6],
+            wpm_range: bytes[7],
+            x2_mode: bytes[8],
+            key_compensation: bytes[9],
+            farnsworth_wpm: bytes[10],
+            paddle_setpoint: bytes[11],
+            dit_dah_ratio: bytes[12],
+            pin_config: bytes[13],
+            x1_mode: bytes[14],
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn hscw_speed_encoding() {
+        assert_eq!(hscw_speed_byte(1000), Some(10));
+        assert_eq!(hscw_speed_byte(4500), Some(45));
+        assert_eq!(hscw_speed_byte(8000), Some(80));
+        assert_eq!(hscw_speed_byte(900), None);
+        assert_eq!(hscw_speed_byte(8100), None);
+        assert_eq!(hscw_speed_byte(2050), None);
+    }
+
     #[test]
     fn version_detection() {
         assert_eq!(WinKeyerVersion::from_version_byte(23), Some(WinKeyerVersion::Wk2));
@@ -243,6 +310,21 @@ mod tests {
         assert_eq!(PaddleMode::IambicA.to_mode_bits(), 0x10);
         assert_eq!(PaddleMode::Ultimatic.to_mode_bits(), 0x20);
         assert_eq!(PaddleMode::Bug.to_mode_bits(), 0x30);
+        for mode in [PaddleMode::IambicA, PaddleMode::IambicB, PaddleMode::Ultimatic, PaddleMode::Bug] {
+            assert_eq!(PaddleMode::from_mode_bits(0xC5 | mode.to_mode_bits()), mode);
+        }
+    }
+
+    #[test]
+    fn sidetone_hz_inverts_byte() {
+        for version in [WinKeyerVersion::Wk2, WinKeyerVersion::Wk3] {
+            for hz in [500, 650, 800, 1000, 2200, 4000] {
+                let byte = sidetone_byte(hz, version);
+                assert_eq!(sidetone_byte(sidetone_hz(byte, version), version), byte, "{version:?}");
+            }
+        }
+        assert_eq!(sidetone_hz(104, WinKeyerVersion::Wk3), 600);
+        assert_eq!(sidetone_hz(5, WinKeyerVersion::Wk2), 800);
     }
 
     #[test]
@@ -0,0 +1,313 @@
+//! `Keyer` implementation that drives a keyer served by [`KeyerServer`](super::KeyerServer).
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use super::wire::{self, FrameKind, Op, Reply};
+use crate::error::{Error, Result};
+use crate::event::{EventReceiver, EventSender, KeyerEvent};
+use crate::keyer::{Keyer, KeyerCapabilities, KeyerInfo};
+
+/// How long to wait for the server's `Welcome`.
+pub(super) const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long to wait for a reply before giving up on the server. Longer than
+/// a WinKeyer's own XOFF wait, so a full buffer still reports `BufferFull`.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Requests awaiting a response; `None` once the connection is gone.
+type Pending = Arc<Mutex<Option<HashMap<u32, oneshot::Sender<Reply>>>>>;
+
+/// A keyer at another station, reached through a [`KeyerServer`](super::KeyerServer).
+///
+/// Events from the remote keyer are re-emitted locally. Abort, tune and
+/// other immediate commands go out ahead of queued text, and the server runs
+/// them on a separate worker so they are never stuck behind a slow send.
+///
+/// # Example
+///
+/// ```no_run
+/// # use winkey::{Keyer, remote::RemoteKeyer};
+/// # async fn example() -> winkey::Result<()> {
+/// let keyer = RemoteKeyer::connect("radio.example.net:6789", "secret").await?;
+/// keyer.send_message("CQ TEST").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RemoteKeyer {
+    info: KeyerInfo,
+    capabilities: KeyerCapabilities,
+    rt_tx: mpsc::Sender<Vec<u8>>,
+    bg_tx: mpsc::Sender<Vec<u8>>,
+    pending: Pending,
+    next_id: AtomicU32,
+    event_tx: EventSender,
+    cancel: CancellationToken,
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl std::fmt::Debug for RemoteKeyer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteKeyer")
+            .field("info", &self.info)
+            .finish()
+    }
+}
+
+impl RemoteKeyer {
+    /// Connect to a server at `addr` (`host:port`) and authenticate.
+    pub async fn connect(addr: &str, token: &str) -> Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        let mut keyer = Self::connect_stream(stream, token).await?;
+        keyer.info.port = Some(addr.to_string());
+        Ok(keyer)
+    }
+
+    /// Authenticate over an already-open stream.
+    pub async fn connect_stream<S>(stream: S, token: &str) -> Result<Self>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (mut rd, mut wr) = tokio::io::split(stream);
+
+        wire::write_frame(&mut wr, FrameKind::Hello, &wire::encode_hello(token)).await?;
+        let reply = tokio::time::timeout(HANDSHAKE_TIMEOUT, wire::read_frame(&mut rd))
+            .await
+            .map_err(|_| Error::Timeout)??;
+        let (info, capabilities) = match reply {
+            Some((FrameKind::Welcome, payload)) => wire::decode_welcome(&payload)?,
+            Some((FrameKind::Denied, reason)) => {
+                return Err(Error::Protocol(format!(
+                    "server refused connection: {}",
+                    String::from_utf8_lossy(&reason)
+                )));
+            }
+            Some((kind, _)) => {
+                return Err(Error::Protocol(format!("expected welcome, got {kind:?}")));
+            }
+            None => return Err(Error::ConnectionLost),
+        };
+
+        let (rt_tx, mut rt_rx) = mpsc::channel::<Vec<u8>>(32);
+        let (bg_tx, mut bg_rx) = mpsc::channel::<Vec<u8>>(64);
+        let (event_tx, _) = crate::event::channel(256);
+        let pending: Pending = Arc::new(Mutex::new(Some(HashMap::new())));
+        let cancel = CancellationToken::new();
+
+        let writer = tokio::spawn({
+            let cancel = cancel.clone();
+            async move {
+                loop {
+                    let payload = tokio::select! {
+                        biased;
+                        _ = cancel.cancelled() => break,
+                        Some(p) = rt_rx.recv() => p,
+                        Some(p) = bg_rx.recv() => p,
+                        else => break,
+                    };
+                    if let Err(e) = wire::write_frame(&mut wr, FrameKind::Request, &payload).await {
+                        warn!("remote write failed: {e}");
+                        break;
+                    }
+                }
+                let _ = wr.shutdown().await;
+            }
+        });
+
+        let reader = tokio::spawn({
+            let cancel = cancel.clone();
+            let pending = pending.clone();
+            let event_tx = event_tx.clone();
+            async move {
+                loop {
+                    let frame = tokio::select! {
+                        _ = cancel.cancelled() => break,
+                        frame = wire::read_frame(&mut rd) => frame,
+                    };
+                    match frame {
+                        Ok(Some((FrameKind::Response, payload))) => match wire::decode_reply(&payload) {
+                            Ok((id, reply)) => {
+                                let waiter = pending.lock().unwrap().as_mut().and_then(|m| m.remove(&id));
+                                if let Some(waiter) = waiter {
+                                    let _ = waiter.send(reply);
+                                }
+                            }
+                            Err(e) => warn!("bad response from server: {e}"),
+                        },
+                        Ok(Some((FrameKind::Event, payload))) => {
+                            if let Some(event) = wire::decode_event(&payload) {
+                                let _ = event_tx.send(event);
+                            }
+                        }
+                        Ok(Some((kind, _))) => warn!("unexpected {kind:?} frame from server"),
+                        Ok(None) => {
+                            debug!("remote server closed connection");
+                            break;
+                        }
+                        Err(e) => {
+                            warn!("remote read failed: {e}");
+                            break;
+                        }
+                    }
+                }
+                // Dropping the waiters fails their requests with ConnectionLost.
+                pending.lock().unwrap().take();
+                let _ = event_tx.send(KeyerEvent::Disconnected);
+            }
+        });
+
+        let _ = event_tx.send(KeyerEvent::Connected);
+
+        Ok(Self {
+            info,
+            capabilities,
+            rt_tx,
+            bg_tx,
+            pending,
+            next_id: AtomicU32::new(1),
+            event_tx,
+            cancel,
+            tasks: Mutex::new(vec![writer, reader]),
+        })
+    }
+
+    /// Send `op` and wait for the server's reply, failing with
+    /// [`Error::Timeout`] if none comes. A request too long for one frame
+    /// is refused here; the writer task would otherwise fail on it and end
+    /// the session.
+    async fn request(&self, op: Op) -> Reply {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let payload = op.encode(id);
+        if payload.len() > wire::MAX_PAYLOAD {
+            return Err(Error::InvalidParameter(format!(
+                "request too long for the remote link: {} bytes",
+                payload.len()
+            )));
+        }
+        let (reply_tx, reply_rx) = oneshot::channel();
+        match self.pending.lock().unwrap().as_mut() {
+            Some(map) => {
+                map.insert(id, reply_tx);
+            }
+            None => return Err(Error::ConnectionLost),
+        }
+
+        let queue = if op.is_priority() { &self.rt_tx } else { &self.bg_tx };
+        if queue.send(payload).await.is_err() {
+            if let Some(map) = self.pending.lock().unwrap().as_mut() {
+                map.remove(&id);
+            }
+            return Err(Error::NotConnected);
+        }
+        match tokio::time::timeout(REQUEST_TIMEOUT, reply_rx).await {
+            Ok(reply) => reply.map_err(|_| Error::ConnectionLost)?,
+            Err(_) => {
+                if let Some(map) = self.pending.lock().unwrap().as_mut() {
+                    map.remove(&id);
+                }
+                warn!(id, "remote request timed out");
+                Err(Error::Timeout)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Keyer for RemoteKeyer {
+    fn info(&self) -> &KeyerInfo {
+        &self.info
+    }
+
+    /// Capabilities of the keyer at the server.
+    fn capabilities(&self) -> &KeyerCapabilities {
+        &self.capabilities
+    }
+
+    async fn send_message(&self, text: &str) -> Result<()> {
+        self.request(Op::SendMessage(text.to_string())).await.map(|_| ())
+    }
+
+    async fn abort(&self) -> Result<()> {
+        self.request(Op::Abort).await.map(|_| ())
+    }
+
+    async fn set_speed(&self, wpm: u8) -> Result<()> {
+        self.request(Op::SetSpeed(wpm)).await.map(|_| ())
+    }
+
+    async fn get_speed(&self) -> Result<u8> {
+        self.request(Op::GetSpeed)
+            .await?
+            .ok_or_else(|| Error::Protocol("speed missing from reply".into()))
+    }
+
+    async fn set_tune(&self, on: bool) -> Result<()> {
+        self.request(Op::SetTune(on)).await.map(|_| ())
+    }
+
+    async fn set_ptt(&self, on: bool) -> Result<()> {
+        self.request(Op::SetPtt(on)).await.map(|_| ())
+    }
+
+    fn subscribe(&self) -> EventReceiver {
+        self.event_tx.subscribe()
+    }
+
+    /// Disconnect from the server. The remote keyer keeps running.
+    async fn close(&self) -> Result<()> {
+        self.cancel.cancel();
+        let tasks = std::mem::take(&mut *self.tasks.lock().unwrap());
+        for task in tasks {
+            let _ = task.await;
+        }
+        Ok(())
+    }
+
+    async fn send_prosign(&self, c1: u8, c2: u8) -> Result<()> {
+        self.request(Op::SendProsign(c1, c2)).await.map(|_| ())
+    }
+
+    async fn set_buffered_speed(&self, wpm: u8) -> Result<()> {
+        self.request(Op::SetBufferedSpeed(wpm)).await.map(|_| ())
+    }
+
+    async fn cancel_buffered_speed(&self) -> Result<()> {
+        self.request(Op::CancelBufferedSpeed).await.map(|_| ())
+    }
+
+    async fn set_pause(&self, paused: bool) -> Result<()> {
+        self.request(Op::SetPause(paused)).await.map(|_| ())
+    }
+
+    async fn set_weight(&self, weight: u8) -> Result<()> {
+        self.request(Op::SetWeight(weight)).await.map(|_| ())
+    }
+
+    async fn set_farnsworth(&self, wpm: u8) -> Result<()> {
+        self.request(Op::SetFarnsworth(wpm)).await.map(|_| ())
+    }
+
+    async fn set_sidetone(&self, freq_hz: u16) -> Result<()> {
+        self.request(Op::SetSidetone(freq_hz)).await.map(|_| ())
+    }
+}
+
+impl Drop for RemoteKeyer {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+        for task in self.tasks.get_mut().unwrap().drain(..) {
+            task.abort();
+        }
+    }
+}
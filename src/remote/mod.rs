@@ -0,0 +1,283 @@
+//! Remote keying over TCP.
+//!
+//! [`KeyerServer`] runs at the radio site and owns the local keyer;
+//! [`RemoteKeyer`] runs at the operator's station and implements [`Keyer`]
+//! by forwarding every call to the server, rebuilding [`KeyerEvent`]s from
+//! the event frames it sends back.
+//!
+//! A server can instead pass a serial port through unchanged
+//! ([`KeyerServer::raw_port`]), for a [`RemoteSerialPort`] that stands in
+//! for the local port so a keyer backend (or other host software) runs at
+//! the operator's end.
+//!
+//! Frames are length-prefixed (`[len: u16 BE][kind][payload]`). A session
+//! opens with a shared-token handshake, then carries id-tagged requests and
+//! responses interleaved with event frames.
+//!
+//! [`Keyer`]: crate::Keyer
+//! [`KeyerEvent`]: crate::KeyerEvent
+
+mod client;
+mod serial;
+mod server;
+pub(crate) mod wire;
+
+pub use client::RemoteKeyer;
+pub use serial::RemoteSerialPort;
+pub use server::KeyerServer;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use tokio::sync::Notify;
+
+    use super::*;
+    use crate::error::{Error, Result};
+    use crate::event::{EventReceiver, EventSender, KeyerEvent};
+    use crate::keyer::{Keyer, KeyerCapabilities, KeyerInfo};
+    use crate::protocol::types::WinKeyerVersion;
+    use crate::timing::MorseTiming;
+    use crate::transport::MockPort;
+    use crate::virtual_keyer::VirtualKeyer;
+    use crate::WinKeyerBuilder;
+
+    async fn pair(keyer: Arc<dyn Keyer>, token: &str) -> Result<RemoteKeyer> {
+        let (server_end, client_end) = tokio::io::duplex(4096);
+        let server = KeyerServer::new(keyer, "secret");
+        tokio::spawn(async move { server.serve_connection(server_end).await });
+        RemoteKeyer::connect_stream(client_end, token).await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn commands_and_events_round_trip() {
+        let local = Arc::new(VirtualKeyer::new(MorseTiming::default()));
+        let remote = pair(local.clone(), "secret").await.unwrap();
+        assert_eq!(remote.info().name, "Virtual keyer");
+        assert!(remote.capabilities().prosigns);
+        assert!(!remote.capabilities().sidetone);
+
+        let mut rx = remote.subscribe();
+        remote.send_message("EE").await.unwrap();
+        let mut echoed = String::new();
+        while echoed.len() < 2 {
+            if let KeyerEvent::CharacterSent { ch, .. } = rx.recv().await.unwrap() {
+                echoed.push(ch);
+            }
+        }
+        assert_eq!(echoed, "EE");
+
+        remote.set_speed(30).await.unwrap();
+        assert_eq!(remote.get_speed().await.unwrap(), 30);
+        assert_eq!(local.get_speed().await.unwrap(), 30);
+
+        match remote.set_speed(3).await {
+            Err(Error::InvalidParameter(m)) => assert!(m.contains("got 3")),
+            other => panic!("unexpected {other:?}"),
+        }
+        assert!(matches!(
+            remote.set_sidetone(700).await,
+            Err(Error::Unsupported(_))
+        ));
+
+        remote.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn oversized_request_keeps_session() {
+        let local = Arc::new(VirtualKeyer::new(MorseTiming::default()));
+        let remote = pair(local, "secret").await.unwrap();
+        let text = "E".repeat(70_000);
+        assert!(matches!(
+            remote.send_message(&text).await,
+            Err(Error::InvalidParameter(_))
+        ));
+        assert_eq!(remote.get_speed().await.unwrap(), 20);
+    }
+
+    #[tokio::test]
+    async fn wrong_token_is_refused() {
+        let local = Arc::new(VirtualKeyer::new(MorseTiming::default()));
+        match pair(local, "guess").await {
+            Err(Error::Protocol(m)) => assert!(m.contains("authentication failed")),
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn disconnect_fails_requests() {
+        let local = Arc::new(VirtualKeyer::new(MorseTiming::default()));
+        let (server_end, client_end) = tokio::io::duplex(4096);
+        let server = KeyerServer::new(local, "secret");
+        let task = tokio::spawn(async move { server.serve_connection(server_end).await });
+        let remote = RemoteKeyer::connect_stream(client_end, "secret").await.unwrap();
+        let mut rx = remote.subscribe();
+
+        task.abort();
+        loop {
+            if let KeyerEvent::Disconnected = rx.recv().await.unwrap() {
+                break;
+            }
+        }
+        assert!(matches!(
+            remote.get_speed().await,
+            Err(Error::ConnectionLost)
+        ));
+    }
+
+    /// Keyer whose text path never completes, like a WinKeyer stuck in XOFF.
+    struct StuckKeyer {
+        info: KeyerInfo,
+        capabilities: KeyerCapabilities,
+        event_tx: EventSender,
+        aborted: Notify,
+    }
+
+    #[async_trait]
+    impl Keyer for StuckKeyer {
+        fn info(&self) -> &KeyerInfo {
+            &self.info
+        }
+
+        fn capabilities(&self) -> &KeyerCapabilities {
+            &self.capabilities
+        }
+
+        async fn send_message(&self, _text: &str) -> Result<()> {
+            std::future::pending().await
+        }
+
+        async fn abort(&self) -> Result<()> {
+            self.aborted.notify_one();
+            Ok(())
+        }
+
+        async fn set_speed(&self, _wpm: u8) -> Result<()> {
+            Ok(())
+        }
+
+        async fn get_speed(&self) -> Result<u8> {
+            Ok(20)
+        }
+
+        async fn set_tune(&self, _on: bool) -> Result<()> {
+            Ok(())
+        }
+
+        async fn set_ptt(&self, _on: bool) -> Result<()> {
+            Ok(())
+        }
+
+        fn subscribe(&self) -> EventReceiver {
+            self.event_tx.subscribe()
+        }
+
+        async fn close(&self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn abort_bypasses_stuck_text() {
+        let local = Arc::new(StuckKeyer {
+            info: KeyerInfo {
+                name: "stuck".into(),
+                version: "0".into(),
+                port: None,
+            },
+            capabilities: KeyerCapabilities::default(),
+            event_tx: crate::event::channel(4).0,
+            aborted: Notify::new(),
+        });
+        let remote = Arc::new(pair(local.clone(), "secret").await.unwrap());
+
+        let sender = remote.clone();
+        let send = tokio::spawn(async move { sender.send_message("CQ").await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        tokio::time::timeout(Duration::from_secs(1), remote.abort())
+            .await
+            .expect("abort stuck behind text")
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(1), local.aborted.notified())
+            .await
+            .unwrap();
+        assert!(!send.is_finished());
+        send.abort();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn silent_server_times_out() {
+        let (server_end, client_end) = tokio::io::duplex(4096);
+        tokio::spawn(async move {
+            let (mut rd, mut wr) = tokio::io::split(server_end);
+            wire::read_frame(&mut rd).await.unwrap();
+            let welcome = wire::encode_welcome(
+                &KeyerInfo {
+                    name: "silent".into(),
+                    version: "0".into(),
+                    port: None,
+                },
+                &KeyerCapabilities::default(),
+            );
+            wire::write_frame(&mut wr, wire::FrameKind::Welcome, &welcome).await.unwrap();
+            // Read requests but never answer
+            while let Ok(Some(_)) = wire::read_frame(&mut rd).await {}
+        });
+        let remote = RemoteKeyer::connect_stream(client_end, "secret").await.unwrap();
+        assert!(matches!(remote.get_speed().await, Err(Error::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn raw_port_carries_winkeyer_protocol() {
+        let mock = MockPort::new();
+        let server = KeyerServer::raw_port(mock.clone(), "secret");
+
+        let (server_end, client_end) = tokio::io::duplex(4096);
+        let first = server.clone();
+        tokio::spawn(async move { first.serve_connection(server_end).await });
+        let port = RemoteSerialPort::connect_stream(client_end, "secret").await.unwrap();
+
+        let wk = mock.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            wk.queue_read(&[23]);
+        });
+        let keyer = WinKeyerBuilder::new("remote").build_with_port(port).await.unwrap();
+        assert_eq!(keyer.version(), WinKeyerVersion::Wk2);
+        keyer.send_message("CQ").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(mock.written_data().ends_with(b"CQ"));
+
+        // The port is taken, and keyer clients are turned away
+        let (server_end, client_end) = tokio::io::duplex(4096);
+        let second = server.clone();
+        tokio::spawn(async move { second.serve_connection(server_end).await });
+        match RemoteSerialPort::connect_stream(client_end, "secret").await {
+            Err(Error::Protocol(m)) => assert!(m.contains("in use")),
+            other => panic!("unexpected {other:?}"),
+        }
+        let (server_end, client_end) = tokio::io::duplex(4096);
+        tokio::spawn(async move { server.serve_connection(server_end).await });
+        match RemoteKeyer::connect_stream(client_end, "secret").await {
+            Err(Error::Protocol(m)) => assert!(m.contains("raw serial port")),
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn tcp_connect() {
+        let local = Arc::new(VirtualKeyer::new(MorseTiming::default()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = KeyerServer::new(local, "secret");
+        let task = tokio::spawn(async move { server.serve(listener).await });
+
+        let remote = RemoteKeyer::connect(&addr, "secret").await.unwrap();
+        assert_eq!(remote.info().port.as_deref(), Some(addr.as_str()));
+        assert_eq!(remote.get_speed().await.unwrap(), 20);
+        remote.close().await.unwrap();
+        task.abort();
+    }
+}
@@ -0,0 +1,171 @@
+//! Raw serial tunnel to a port served by [`KeyerServer::raw_port`](super::KeyerServer::raw_port).
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream, ReadBuf};
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use super::client::HANDSHAKE_TIMEOUT;
+use super::wire::{self, FrameKind};
+use crate::error::{Error, Result};
+
+/// A serial port at another station, reached through a
+/// [`KeyerServer`](super::KeyerServer) serving it raw.
+///
+/// Reads and writes carry the port's bytes unchanged, so it can stand in
+/// for the local port in
+/// [`WinKeyerBuilder::build_with_port`](crate::WinKeyerBuilder::build_with_port)
+/// and the keyer runs with its own IO task at this end. Reads end once the
+/// connection drops.
+///
+/// # Example
+///
+/// ```no_run
+/// # use winkey::{WinKeyerBuilder, remote::RemoteSerialPort};
+/// # async fn example() -> winkey::Result<()> {
+/// let port = RemoteSerialPort::connect("radio.example.net:6789", "secret").await?;
+/// let keyer = WinKeyerBuilder::new("radio.example.net:6789")
+///     .build_with_port(port)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RemoteSerialPort {
+    inner: DuplexStream,
+    task: JoinHandle<()>,
+}
+
+impl std::fmt::Debug for RemoteSerialPort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteSerialPort")
+            .field("connected", &!self.task.is_finished())
+            .finish()
+    }
+}
+
+impl RemoteSerialPort {
+    /// Connect to a server at `addr` (`host:port`) and authenticate.
+    pub async fn connect(addr: &str, token: &str) -> Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        Self::connect_stream(stream, token).await
+    }
+
+    /// Authenticate over an already-open stream.
+    pub async fn connect_stream<S>(stream: S, token: &str) -> Result<Self>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (mut rd, mut wr) = tokio::io::split(stream);
+
+        wire::write_frame(&mut wr, FrameKind::RawHello, &wire::encode_hello(token)).await?;
+        let reply = tokio::time::timeout(HANDSHAKE_TIMEOUT, wire::read_frame(&mut rd))
+            .await
+            .map_err(|_| Error::Timeout)??;
+        match reply {
+            Some((FrameKind::Welcome, _)) => {}
+            Some((FrameKind::Denied, reason)) => {
+                return Err(Error::Protocol(format!(
+                    "server refused connection: {}",
+                    String::from_utf8_lossy(&reason)
+                )));
+            }
+            Some((kind, _)) => {
+                return Err(Error::Protocol(format!("expected welcome, got {kind:?}")));
+            }
+            None => return Err(Error::ConnectionLost),
+        }
+
+        let (inner, tunnel) = tokio::io::duplex(1024);
+        let task = tokio::spawn(pump(rd, wr, tunnel));
+        Ok(Self { inner, task })
+    }
+}
+
+/// Move bytes between the local end of the tunnel and `Data` frames until
+/// either side closes.
+async fn pump<R, W>(mut rd: R, mut wr: W, tunnel: DuplexStream)
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let (mut tunnel_rd, mut tunnel_wr) = tokio::io::split(tunnel);
+
+    let upstream = async {
+        let mut buf = [0u8; 256];
+        loop {
+            match tunnel_rd.read(&mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if let Err(e) = wire::write_frame(&mut wr, FrameKind::Data, &buf[..n]).await {
+                        warn!("remote port write failed: {e}");
+                        break;
+                    }
+                }
+            }
+        }
+        let _ = wr.shutdown().await;
+    };
+    let downstream = async {
+        loop {
+            match wire::read_frame(&mut rd).await {
+                Ok(Some((FrameKind::Data, bytes))) => {
+                    if tunnel_wr.write_all(&bytes).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(Some((kind, _))) => warn!("unexpected {kind:?} frame from server"),
+                Ok(None) => {
+                    debug!("remote port server closed connection");
+                    break;
+                }
+                Err(e) => {
+                    warn!("remote port read failed: {e}");
+                    break;
+                }
+            }
+        }
+    };
+    tokio::select! {
+        _ = upstream => {}
+        _ = downstream => {}
+    }
+}
+
+impl AsyncRead for RemoteSerialPort {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for RemoteSerialPort {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+impl Drop for RemoteSerialPort {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -0,0 +1,338 @@
+//! TCP server exposing a local keyer to remote clients.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::wire::{self, FrameKind, Op, Reply};
+use crate::error::{Error, Result};
+use crate::event::{EventReceiver, KeyerEvent};
+use crate::keyer::Keyer;
+use crate::transport;
+
+/// How long a client has to send its `Hello` after connecting.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Pause after a failed `accept`, e.g. out of file descriptors, before
+/// trying again.
+const ACCEPT_RETRY: Duration = Duration::from_millis(100);
+
+type Outgoing = mpsc::Sender<(FrameKind, Vec<u8>)>;
+
+/// A port that can be bridged byte for byte.
+trait RawPort: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> RawPort for T {}
+
+/// What a server exposes.
+#[derive(Clone)]
+enum Served {
+    Keyer(Arc<dyn Keyer>),
+    /// Held by the one client using it.
+    Port(Arc<Mutex<Box<dyn RawPort>>>),
+}
+
+/// Serves one keyer (typically a [`WinKeyer`](crate::WinKeyer)) to remote
+/// [`RemoteKeyer`](super::RemoteKeyer) clients, or a raw serial port to a
+/// [`RemoteSerialPort`](super::RemoteSerialPort).
+///
+/// Clients authenticate with a shared token. The token is sent in clear
+/// text, so run the link over a VPN or SSH tunnel when it crosses the
+/// internet. Several clients may share a keyer; each receives every keyer
+/// event. A raw port serves one client at a time.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use winkey::{WinKeyerBuilder, remote::KeyerServer};
+/// # async fn example() -> winkey::Result<()> {
+/// let keyer = WinKeyerBuilder::new("/dev/ttyUSB0").build().await?;
+/// let server = KeyerServer::new(Arc::new(keyer), "secret");
+/// let listener = tokio::net::TcpListener::bind("0.0.0.0:6789").await?;
+/// server.serve(listener).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct KeyerServer {
+    served: Served,
+    token: Arc<str>,
+}
+
+impl std::fmt::Debug for KeyerServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let served = match &self.served {
+            Served::Keyer(keyer) => keyer.info().name.as_str(),
+            Served::Port(_) => "raw port",
+        };
+        f.debug_struct("KeyerServer").field("served", &served).finish()
+    }
+}
+
+impl KeyerServer {
+    /// Serve `keyer` to clients presenting `token`.
+    pub fn new(keyer: Arc<dyn Keyer>, token: &str) -> Self {
+        Self {
+            served: Served::Keyer(keyer),
+            token: token.into(),
+        }
+    }
+
+    /// Pass `port`'s bytes through unchanged to a client presenting
+    /// `token`, so the host software at the other end talks to the keyer
+    /// as if it were plugged in locally.
+    pub fn raw_port<P>(port: P, token: &str) -> Self
+    where
+        P: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        Self {
+            served: Served::Port(Arc::new(Mutex::new(Box::new(port)))),
+            token: token.into(),
+        }
+    }
+
+    /// Open the WinKeyer serial port at `path` and serve it raw (see
+    /// [`raw_port`](Self::raw_port)).
+    pub fn serial(path: &str, token: &str) -> Result<Self> {
+        Ok(Self::raw_port(transport::open_serial(path, 1200)?, token))
+    }
+
+    /// Accept clients forever, serving each on its own task. A failed
+    /// `accept` is logged and retried rather than ending the server.
+    pub async fn serve(&self, listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("accept failed: {e}");
+                    tokio::time::sleep(ACCEPT_RETRY).await;
+                    continue;
+                }
+            };
+            if let Err(e) = stream.set_nodelay(true) {
+                warn!(%peer, "set_nodelay failed: {e}");
+            }
+            info!(%peer, "remote client connected");
+            let server = self.clone();
+            tokio::spawn(async move {
+                match server.serve_connection(stream).await {
+                    Ok(()) => info!(%peer, "remote client disconnected"),
+                    Err(e) => warn!(%peer, "remote client dropped: {e}"),
+                }
+            });
+        }
+    }
+
+    /// Serve a single already-connected client until it disconnects.
+    pub async fn serve_connection<S>(&self, stream: S) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (mut rd, mut wr) = tokio::io::split(stream);
+
+        let hello = tokio::time::timeout(HANDSHAKE_TIMEOUT, wire::read_frame(&mut rd))
+            .await
+            .map_err(|_| Error::Timeout)??;
+        let (kind, payload) = match hello {
+            Some((kind @ (FrameKind::Hello | FrameKind::RawHello), payload)) => (kind, payload),
+            Some((kind, _)) => {
+                return Err(Error::Protocol(format!("expected hello, got {kind:?}")));
+            }
+            None => return Ok(()),
+        };
+        if payload.first() != Some(&wire::PROTOCOL_VERSION) {
+            wire::write_frame(&mut wr, FrameKind::Denied, b"unsupported protocol version").await?;
+            return Err(Error::Protocol("client protocol version mismatch".into()));
+        }
+        if !tokens_match(&payload[1..], self.token.as_bytes()) {
+            wire::write_frame(&mut wr, FrameKind::Denied, b"authentication failed").await?;
+            return Err(Error::Protocol("client authentication failed".into()));
+        }
+
+        match (&self.served, kind) {
+            (Served::Keyer(keyer), FrameKind::Hello) => serve_keyer(keyer.clone(), rd, wr).await,
+            (Served::Port(port), FrameKind::RawHello) => serve_port(port, rd, wr).await,
+            (Served::Keyer(_), _) => {
+                wire::write_frame(&mut wr, FrameKind::Denied, b"server does not serve a raw port").await?;
+                Err(Error::Protocol("raw client connected to keyer server".into()))
+            }
+            (Served::Port(_), _) => {
+                wire::write_frame(&mut wr, FrameKind::Denied, b"server serves a raw serial port").await?;
+                Err(Error::Protocol("keyer client connected to raw port server".into()))
+            }
+        }
+    }
+}
+
+/// Run one `RemoteKeyer` session.
+async fn serve_keyer<R, W>(keyer: Arc<dyn Keyer>, mut rd: R, mut wr: W) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Send + Unpin + 'static,
+{
+    let welcome = wire::encode_welcome(keyer.info(), keyer.capabilities());
+    wire::write_frame(&mut wr, FrameKind::Welcome, &welcome).await?;
+
+    let (out_tx, mut out_rx) = mpsc::channel::<(FrameKind, Vec<u8>)>(64);
+    let writer = tokio::spawn(async move {
+        while let Some((kind, payload)) = out_rx.recv().await {
+            if wire::write_frame(&mut wr, kind, &payload).await.is_err() {
+                break;
+            }
+        }
+    });
+    let forwarder = tokio::spawn(forward_events(keyer.subscribe(), out_tx.clone()));
+    let (rt_tx, rt_worker) = spawn_worker(keyer.clone(), out_tx.clone());
+    let (bg_tx, bg_worker) = spawn_worker(keyer, out_tx);
+
+    let result = loop {
+        match wire::read_frame(&mut rd).await {
+            Ok(Some((FrameKind::Request, payload))) => {
+                let (id, op) = match Op::decode(&payload) {
+                    Ok(request) => request,
+                    Err(e) => break Err(e),
+                };
+                debug!(id, ?op, "remote request");
+                let queue = if op.is_priority() { &rt_tx } else { &bg_tx };
+                if queue.send((id, op)).await.is_err() {
+                    break Ok(());
+                }
+            }
+            Ok(Some((kind, _))) => {
+                break Err(Error::Protocol(format!("unexpected {kind:?} frame from client")));
+            }
+            Ok(None) => break Ok(()),
+            Err(e) => break Err(e),
+        }
+    };
+
+    forwarder.abort();
+    rt_worker.abort();
+    bg_worker.abort();
+    writer.abort();
+    result
+}
+
+/// Bridge `port` to one `RemoteSerialPort` session.
+async fn serve_port<R, W>(port: &Mutex<Box<dyn RawPort>>, mut rd: R, mut wr: W) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let Ok(mut port) = port.try_lock() else {
+        wire::write_frame(&mut wr, FrameKind::Denied, b"port in use").await?;
+        return Err(Error::Protocol("serial port already in use".into()));
+    };
+    wire::write_frame(&mut wr, FrameKind::Welcome, &[]).await?;
+    let (mut port_rd, mut port_wr) = tokio::io::split(&mut *port);
+
+    let upstream = async {
+        loop {
+            match wire::read_frame(&mut rd).await? {
+                Some((FrameKind::Data, bytes)) => {
+                    port_wr.write_all(&bytes).await?;
+                    port_wr.flush().await?;
+                }
+                Some((kind, _)) => {
+                    return Err(Error::Protocol(format!("unexpected {kind:?} frame from client")));
+                }
+                None => return Ok(()),
+            }
+        }
+    };
+    let downstream = async {
+        let mut buf = [0u8; 256];
+        loop {
+            let n = port_rd.read(&mut buf).await?;
+            if n == 0 {
+                return Err(Error::ConnectionLost);
+            }
+            wire::write_frame(&mut wr, FrameKind::Data, &buf[..n]).await?;
+        }
+    };
+    tokio::select! {
+        result = upstream => result,
+        result = downstream => result,
+    }
+}
+
+/// Compare tokens without an early exit on the first mismatch.
+fn tokens_match(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Run requests from one queue in order and send each reply back.
+fn spawn_worker(keyer: Arc<dyn Keyer>, out_tx: Outgoing) -> (mpsc::Sender<(u32, Op)>, JoinHandle<()>) {
+    let (tx, mut rx) = mpsc::channel::<(u32, Op)>(64);
+    let task = tokio::spawn(async move {
+        while let Some((id, op)) = rx.recv().await {
+            let reply = execute(keyer.as_ref(), op).await;
+            if out_tx
+                .send((FrameKind::Response, wire::encode_reply(id, &reply)))
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+    (tx, task)
+}
+
+async fn execute(keyer: &dyn Keyer, op: Op) -> Reply {
+    match op {
+        Op::SendMessage(text) => keyer.send_message(&text).await.map(|_| None),
+        Op::Abort => keyer.abort().await.map(|_| None),
+        Op::SetSpeed(wpm) => keyer.set_speed(wpm).await.map(|_| None),
+        Op::GetSpeed => keyer.get_speed().await.map(Some),
+        Op::SetTune(on) => keyer.set_tune(on).await.map(|_| None),
+        Op::SetPtt(on) => keyer.set_ptt(on).await.map(|_| None),
+        Op::SendProsign(c1, c2) => keyer.send_prosign(c1, c2).await.map(|_| None),
+        Op::SetBufferedSpeed(wpm) => keyer.set_buffered_speed(wpm).await.map(|_| None),
+        Op::CancelBufferedSpeed => keyer.cancel_buffered_speed().await.map(|_| None),
+        Op::SetPause(on) => keyer.set_pause(on).await.map(|_| None),
+        Op::SetWeight(weight) => keyer.set_weight(weight).await.map(|_| None),
+        Op::SetFarnsworth(wpm) => keyer.set_farnsworth(wpm).await.map(|_| None),
+        Op::SetSidetone(hz) => keyer.set_sidetone(hz).await.map(|_| None),
+    }
+}
+
+async fn forward_events(mut events: EventReceiver, out_tx: Outgoing) {
+    loop {
+        match events.recv().await {
+            Ok(event) => {
+                // The client sees the gap too
+                if let KeyerEvent::EventsDropped { count } = event {
+                    warn!("remote event forwarder lagged by {count}");
+                }
+                if out_tx
+                    .send((FrameKind::Event, wire::encode_event(&event)))
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+            Err(_) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_comparison() {
+        assert!(tokens_match(b"secret", b"secret"));
+        assert!(!tokens_match(b"secreT", b"secret"));
+        assert!(!tokens_match(b"secret1", b"secret"));
+        assert!(!tokens_match(b"", b"secret"));
+    }
+}
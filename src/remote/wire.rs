@@ -0,0 +1,511 @@
+//! Framing and encoding for the remote keyer protocol.
+//!
+//! Every frame is `[len: u16 BE][kind: u8][payload]`, where `len` counts the
+//! kind byte and the payload. A session starts with the client's `Hello`
+//! (protocol version + shared token) and the server's `Welcome` (capabilities
+//! and keyer info) or `Denied` (reason). After that the client sends
+//! `Request` frames and the server answers each with a `Response` carrying
+//! the same id, interleaved with unsolicited `Event` frames.
+//!
+//! A server exposing a raw serial port answers `RawHello` instead, with an
+//! empty `Welcome`; after that both sides send the port's bytes in `Data`
+//! frames.
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::error::{Error, Result};
+use crate::event::{EchoSource, KeyerEvent, KeyerStatus};
+use crate::keyer::{KeyerCapabilities, KeyerInfo};
+
+/// Bumped on any incompatible change to the frame layout.
+pub(crate) const PROTOCOL_VERSION: u8 = 1;
+
+/// Frame type byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FrameKind {
+    Hello,
+    RawHello,
+    Welcome,
+    Denied,
+    Request,
+    Response,
+    Event,
+    Data,
+}
+
+impl FrameKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            FrameKind::Hello => 0x01,
+            FrameKind::RawHello => 0x04,
+            FrameKind::Welcome => 0x02,
+            FrameKind::Denied => 0x03,
+            FrameKind::Request => 0x10,
+            FrameKind::Response => 0x11,
+            FrameKind::Event => 0x20,
+            FrameKind::Data => 0x30,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0x01 => Some(FrameKind::Hello),
+            0x04 => Some(FrameKind::RawHello),
+            0x02 => Some(FrameKind::Welcome),
+            0x03 => Some(FrameKind::Denied),
+            0x10 => Some(FrameKind::Request),
+            0x11 => Some(FrameKind::Response),
+            0x20 => Some(FrameKind::Event),
+            0x30 => Some(FrameKind::Data),
+            _ => None,
+        }
+    }
+}
+
+/// Longest payload a frame can carry: the length field also counts the
+/// kind byte.
+pub(crate) const MAX_PAYLOAD: usize = u16::MAX as usize - 1;
+
+/// Write one frame.
+pub(crate) async fn write_frame<W>(writer: &mut W, kind: FrameKind, payload: &[u8]) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    if payload.len() > MAX_PAYLOAD {
+        return Err(Error::InvalidParameter(format!("frame too long: {} bytes", payload.len())));
+    }
+    let len = (payload.len() + 1) as u16;
+    let mut frame = Vec::with_capacity(payload.len() + 3);
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.push(kind.to_byte());
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Read one frame. Returns `None` on a clean end of stream.
+pub(crate) async fn read_frame<R>(reader: &mut R) -> Result<Option<(FrameKind, Vec<u8>)>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut len = [0u8; 2];
+    match reader.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u16::from_be_bytes(len) as usize;
+    if len == 0 {
+        return Err(Error::Protocol("empty frame".into()));
+    }
+    let mut body = vec![0u8; len];
+    reader.read_exact(&mut body).await?;
+    let kind = FrameKind::from_byte(body[0])
+        .ok_or_else(|| Error::Protocol(format!("unknown frame kind 0x{:02X}", body[0])))?;
+    body.remove(0);
+    Ok(Some((kind, body)))
+}
+
+/// An operation the client asks the server's keyer to perform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Op {
+    SendMessage(String),
+    Abort,
+    SetSpeed(u8),
+    GetSpeed,
+    SetTune(bool),
+    SetPtt(bool),
+    SendProsign(u8, u8),
+    SetBufferedSpeed(u8),
+    CancelBufferedSpeed,
+    SetPause(bool),
+    SetWeight(u8),
+    SetFarnsworth(u8),
+    SetSidetone(u16),
+}
+
+impl Op {
+    /// Immediate operations travel on the priority path so they are never
+    /// stuck behind queued text (same split as the IO task's RT channel).
+    /// PTT is queued with the text, in order.
+    pub fn is_priority(&self) -> bool {
+        !matches!(
+            self,
+            Op::SendMessage(_)
+                | Op::SetPtt(_)
+                | Op::SendProsign(..)
+                | Op::SetBufferedSpeed(_)
+                | Op::CancelBufferedSpeed
+        )
+    }
+
+    /// Encode as a `Request` payload.
+    pub fn encode(&self, id: u32) -> Vec<u8> {
+        let mut out = id.to_be_bytes().to_vec();
+        match self {
+            Op::SendMessage(text) => {
+                out.push(0x01);
+                out.extend_from_slice(text.as_bytes());
+            }
+            Op::Abort => out.push(0x02),
+            Op::SetSpeed(wpm) => out.extend_from_slice(&[0x03, *wpm]),
+            Op::GetSpeed => out.push(0x04),
+            Op::SetTune(on) => out.extend_from_slice(&[0x05, *on as u8]),
+            Op::SetPtt(on) => out.extend_from_slice(&[0x06, *on as u8]),
+            Op::SendProsign(c1, c2) => out.extend_from_slice(&[0x07, *c1, *c2]),
+            Op::SetBufferedSpeed(wpm) => out.extend_from_slice(&[0x08, *wpm]),
+            Op::CancelBufferedSpeed => out.push(0x09),
+            Op::SetPause(on) => out.extend_from_slice(&[0x0A, *on as u8]),
+            Op::SetWeight(weight) => out.extend_from_slice(&[0x0B, *weight]),
+            Op::SetFarnsworth(wpm) => out.extend_from_slice(&[0x0C, *wpm]),
+            Op::SetSidetone(hz) => {
+                out.push(0x0D);
+                out.extend_from_slice(&hz.to_be_bytes());
+            }
+        }
+        out
+    }
+
+    /// Decode a `Request` payload into its id and operation.
+    pub fn decode(payload: &[u8]) -> Result<(u32, Op)> {
+        let bad = || Error::Protocol(format!("malformed request: {payload:02X?}"));
+        if payload.len() < 5 {
+            return Err(bad());
+        }
+        let id = u32::from_be_bytes(payload[..4].try_into().unwrap());
+        let args = &payload[5..];
+        let byte = |i: usize| args.get(i).copied().ok_or_else(bad);
+        let op = match payload[4] {
+            0x01 => Op::SendMessage(String::from_utf8(args.to_vec()).map_err(|_| bad())?),
+            0x02 => Op::Abort,
+            0x03 => Op::SetSpeed(byte(0)?),
+            0x04 => Op::GetSpeed,
+            0x05 => Op::SetTune(byte(0)? != 0),
+            0x06 => Op::SetPtt(byte(0)? != 0),
+            0x07 => Op::SendProsign(byte(0)?, byte(1)?),
+            0x08 => Op::SetBufferedSpeed(byte(0)?),
+            0x09 => Op::CancelBufferedSpeed,
+            0x0A => Op::SetPause(byte(0)? != 0),
+            0x0B => Op::SetWeight(byte(0)?),
+            0x0C => Op::SetFarnsworth(byte(0)?),
+            0x0D => Op::SetSidetone(u16::from_be_bytes([byte(0)?, byte(1)?])),
+            _ => return Err(bad()),
+        };
+        Ok((id, op))
+    }
+}
+
+/// Result of an operation: `Some(value)` for queries such as `GetSpeed`.
+pub(crate) type Reply = Result<Option<u8>>;
+
+/// Encode a `Response` payload.
+pub(crate) fn encode_reply(id: u32, reply: &Reply) -> Vec<u8> {
+    let mut out = id.to_be_bytes().to_vec();
+    match reply {
+        Ok(None) => out.push(0x00),
+        Ok(Some(value)) => out.extend_from_slice(&[0x00, *value]),
+        Err(e) => {
+            let (code, msg) = match e {
+                Error::Transport(m) => (0x01, m.clone()),
+                Error::Protocol(m) => (0x02, m.clone()),
+                Error::Timeout => (0x03, String::new()),
+                Error::Unsupported(m) => (0x04, m.clone()),
+                Error::InvalidParameter(m) => (0x05, m.clone()),
+                Error::NotConnected => (0x06, String::new()),
+                Error::ConnectionLost => (0x07, String::new()),
+                Error::BufferFull => (0x08, String::new()),
+                Error::Io(e) => (0x01, e.to_string()),
+            };
+            out.push(code);
+            out.extend_from_slice(msg.as_bytes());
+        }
+    }
+    out
+}
+
+/// Decode a `Response` payload into its id and result.
+pub(crate) fn decode_reply(payload: &[u8]) -> Result<(u32, Reply)> {
+    if payload.len() < 5 {
+        return Err(Error::Protocol(format!("malformed response: {payload:02X?}")));
+    }
+    let id = u32::from_be_bytes(payload[..4].try_into().unwrap());
+    let rest = &payload[5..];
+    let msg = || String::from_utf8_lossy(rest).into_owned();
+    let reply = match payload[4] {
+        0x00 => Ok(rest.first().copied()),
+        0x01 => Err(Error::Transport(msg())),
+        0x02 => Err(Error::Protocol(msg())),
+        0x03 => Err(Error::Timeout),
+        0x04 => Err(Error::Unsupported(msg())),
+        0x05 => Err(Error::InvalidParameter(msg())),
+        0x06 => Err(Error::NotConnected),
+        0x07 => Err(Error::ConnectionLost),
+        0x08 => Err(Error::BufferFull),
+        code => Err(Error::Protocol(format!("remote error code 0x{code:02X}: {}", msg()))),
+    };
+    Ok((id, reply))
+}
+
+/// Encode an `Event` payload.
+pub(crate) fn encode_event(event: &KeyerEvent) -> Vec<u8> {
+    match event {
+        KeyerEvent::StatusChanged(s) => {
+            let bits = s.xoff as u8
+                | (s.breakin as u8) << 1
+                | (s.busy as u8) << 2
+                | (s.keydown as u8) << 3
+                | (s.waiting as u8) << 4;
+            vec![0x01, 0xC0 | bits]
+        }
+        KeyerEvent::SpeedPotChanged { wpm } => vec![0x02, *wpm],
+        KeyerEvent::CharacterSent { ch, source } => {
+            // Host echo keeps the original kind so older clients still see it
+            let mut out = vec![match source {
+                EchoSource::Host => 0x03,
+                EchoSource::Paddle => 0x07,
+            }];
+            out.extend_from_slice(&(*ch as u32).to_be_bytes());
+            out
+        }
+        KeyerEvent::PaddleWord(word) => {
+            let mut out = vec![0x08];
+            out.extend_from_slice(word.as_bytes());
+            out
+        }
+        KeyerEvent::PaddleBreakIn => vec![0x04],
+        KeyerEvent::Connected => vec![0x05],
+        KeyerEvent::Disconnected => vec![0x06],
+        KeyerEvent::EventsDropped { count } => {
+            let mut out = vec![0x09];
+            out.extend_from_slice(&count.to_be_bytes());
+            out
+        }
+    }
+}
+
+/// Decode an `Event` payload. Unknown kinds yield `None` so newer servers
+/// can add events without breaking older clients.
+pub(crate) fn decode_event(payload: &[u8]) -> Option<KeyerEvent> {
+    match *payload {
+        [0x01, byte] => Some(KeyerEvent::StatusChanged(KeyerStatus::from_status_byte(byte))),
+        [0x02, wpm] => Some(KeyerEvent::SpeedPotChanged { wpm }),
+        [kind @ (0x03 | 0x07), a, b, c, d] => {
+            let source = if kind == 0x03 { EchoSource::Host } else { EchoSource::Paddle };
+            char::from_u32(u32::from_be_bytes([a, b, c, d]))
+                .map(|ch| KeyerEvent::CharacterSent { ch, source })
+        }
+        [0x04] => Some(KeyerEvent::PaddleBreakIn),
+        [0x05] => Some(KeyerEvent::Connected),
+        [0x06] => Some(KeyerEvent::Disconnected),
+        [0x08, ref word @ ..] => String::from_utf8(word.to_vec()).ok().map(KeyerEvent::PaddleWord),
+        [0x09, ref count @ ..] => <[u8; 8]>::try_from(count)
+            .ok()
+            .map(|count| KeyerEvent::EventsDropped { count: u64::from_be_bytes(count) }),
+        _ => None,
+    }
+}
+
+/// Encode a `Hello` payload.
+pub(crate) fn encode_hello(token: &str) -> Vec<u8> {
+    let mut out = vec![PROTOCOL_VERSION];
+    out.extend_from_slice(token.as_bytes());
+    out
+}
+
+fn capability_bits(caps: &KeyerCapabilities) -> u16 {
+    [
+        caps.speed_pot,
+        caps.sidetone,
+        caps.ptt_control,
+        caps.paddle_echo,
+        caps.prosigns,
+        caps.buffered_speed,
+        caps.farnsworth,
+        caps.contest_spacing,
+        caps.pause,
+        caps.weight,
+        caps.key_state,
+    ]
+    .iter()
+    .enumerate()
+    .fold(0, |bits, (i, &set)| bits | (set as u16) << i)
+}
+
+fn capabilities_from_bits(bits: u16) -> KeyerCapabilities {
+    let bit = |i: u16| bits & (1 << i) != 0;
+    KeyerCapabilities {
+        speed_pot: bit(0),
+        sidetone: bit(1),
+        ptt_control: bit(2),
+        paddle_echo: bit(3),
+        prosigns: bit(4),
+        buffered_speed: bit(5),
+        farnsworth: bit(6),
+        contest_spacing: bit(7),
+        pause: bit(8),
+        weight: bit(9),
+        key_state: bit(10),
+    }
+}
+
+fn push_str(out: &mut Vec<u8>, s: &str) {
+    let bytes = &s.as_bytes()[..s.len().min(255)];
+    out.push(bytes.len() as u8);
+    out.extend_from_slice(bytes);
+}
+
+/// Encode a `Welcome` payload describing the served keyer.
+pub(crate) fn encode_welcome(info: &KeyerInfo, caps: &KeyerCapabilities) -> Vec<u8> {
+    let mut out = capability_bits(caps).to_be_bytes().to_vec();
+    push_str(&mut out, &info.name);
+    push_str(&mut out, &info.version);
+    out
+}
+
+/// Decode a `Welcome` payload. The returned info has no port set.
+pub(crate) fn decode_welcome(payload: &[u8]) -> Result<(KeyerInfo, KeyerCapabilities)> {
+    let bad = || Error::Protocol("malformed welcome".into());
+    let mut rest = payload.get(2..).ok_or_else(bad)?;
+    let mut take_str = || -> Result<String> {
+        let (&len, tail) = rest.split_first().ok_or_else(bad)?;
+        let s = tail.get(..len as usize).ok_or_else(bad)?;
+        rest = &tail[len as usize..];
+        Ok(String::from_utf8_lossy(s).into_owned())
+    };
+    let name = take_str()?;
+    let version = take_str()?;
+    let caps = capabilities_from_bits(u16::from_be_bytes([payload[0], payload[1]]));
+    Ok((
+        KeyerInfo {
+            name,
+            version,
+            port: None,
+        },
+        caps,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn op_round_trip() {
+        let ops = [
+            Op::SendMessage("CQ TEST".into()),
+            Op::Abort,
+            Op::SetSpeed(28),
+            Op::GetSpeed,
+            Op::SetTune(true),
+            Op::SetPtt(false),
+            Op::SendProsign(b'A', b'R'),
+            Op::SetBufferedSpeed(15),
+            Op::CancelBufferedSpeed,
+            Op::SetPause(true),
+            Op::SetWeight(55),
+            Op::SetFarnsworth(12),
+            Op::SetSidetone(1200),
+        ];
+        for (id, op) in ops.into_iter().enumerate() {
+            let encoded = op.encode(id as u32);
+            assert_eq!(Op::decode(&encoded).unwrap(), (id as u32, op));
+        }
+    }
+
+    #[test]
+    fn priority_split() {
+        assert!(Op::Abort.is_priority());
+        assert!(Op::SetTune(true).is_priority());
+        assert!(Op::SetSpeed(20).is_priority());
+        assert!(!Op::SendMessage("E".into()).is_priority());
+        assert!(!Op::SendProsign(b'S', b'K').is_priority());
+        assert!(!Op::SetPtt(false).is_priority());
+    }
+
+    #[test]
+    fn reply_round_trip() {
+        let (id, reply) = decode_reply(&encode_reply(7, &Ok(Some(25)))).unwrap();
+        assert_eq!(id, 7);
+        assert_eq!(reply.unwrap(), Some(25));
+
+        let err = Err(Error::InvalidParameter("speed must be 5-99 WPM, got 3".into()));
+        let (_, reply) = decode_reply(&encode_reply(8, &err)).unwrap();
+        match reply {
+            Err(Error::InvalidParameter(m)) => assert!(m.contains("got 3")),
+            other => panic!("unexpected {other:?}"),
+        }
+
+        let (_, reply) = decode_reply(&encode_reply(9, &Err(Error::BufferFull))).unwrap();
+        assert!(matches!(reply, Err(Error::BufferFull)));
+    }
+
+    #[test]
+    fn event_round_trip() {
+        let status = KeyerStatus {
+            xoff: false,
+            breakin: true,
+            busy: true,
+            keydown: false,
+            waiting: true,
+        };
+        let events = [
+            KeyerEvent::StatusChanged(status),
+            KeyerEvent::SpeedPotChanged { wpm: 31 },
+            KeyerEvent::CharacterSent {
+                ch: 'Q',
+                source: EchoSource::Host,
+            },
+            KeyerEvent::CharacterSent {
+                ch: 'K',
+                source: EchoSource::Paddle,
+            },
+            KeyerEvent::PaddleWord("K1EL".into()),
+            KeyerEvent::EventsDropped { count: 300 },
+            KeyerEvent::PaddleBreakIn,
+            KeyerEvent::Connected,
+            KeyerEvent::Disconnected,
+        ];
+        for event in events {
+            let decoded = decode_event(&encode_event(&event)).unwrap();
+            assert_eq!(format!("{decoded:?}"), format!("{event:?}"));
+        }
+        assert!(decode_event(&[0x7F]).is_none());
+    }
+
+    #[test]
+    fn welcome_round_trip() {
+        let info = KeyerInfo {
+            name: "WinKeyer 3.1".into(),
+            version: "31".into(),
+            port: Some("/dev/ttyUSB0".into()),
+        };
+        let caps = KeyerCapabilities {
+            prosigns: true,
+            pause: true,
+            ..Default::default()
+        };
+        let (decoded, decoded_caps) = decode_welcome(&encode_welcome(&info, &caps)).unwrap();
+        assert_eq!(decoded.name, "WinKeyer 3.1");
+        assert_eq!(decoded.version, "31");
+        assert_eq!(decoded.port, None);
+        assert!(decoded_caps.prosigns && decoded_caps.pause);
+        assert!(!decoded_caps.sidetone && !decoded_caps.weight);
+    }
+
+    #[tokio::test]
+    async fn frame_round_trip() {
+        let (mut a, mut b) = tokio::io::duplex(64);
+        write_frame(&mut a, FrameKind::Event, &[0x04]).await.unwrap();
+        write_frame(&mut a, FrameKind::Request, &Op::Abort.encode(1)).await.unwrap();
+        drop(a);
+        assert_eq!(
+            read_frame(&mut b).await.unwrap(),
+            Some((FrameKind::Event, vec![0x04]))
+        );
+        let (kind, payload) = read_frame(&mut b).await.unwrap().unwrap();
+        assert_eq!(kind, FrameKind::Request);
+        assert_eq!(Op::decode(&payload).unwrap(), (1, Op::Abort));
+        assert_eq!(read_frame(&mut b).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,337 @@
+//! WK3.1 RTTY (FSK) mode.
+//!
+//! WinKeyer 3.1 firmware can key a transmitter's FSK input instead of
+//! sending CW. The host still writes ASCII to the buffer and the keyer does
+//! the Baudot conversion, inserting LTRS/FIGS shifts as needed, and echoes
+//! each character back the same way it does in CW mode.
+//!
+//! This module holds the mode register layout ([`RttyConfig`]) and an ITA2
+//! encoder ([`BaudotEncoder`]) that mirrors the keyer's LTRS/FIGS state, so
+//! text can be checked before it is queued and its on-air time estimated.
+//! Enter the mode with [`WinKeyer::enter_rtty`](crate::WinKeyer::enter_rtty).
+
+use std::time::Duration;
+
+/// RTTY mode enable (P1 bit 7).
+const P1_ENABLE: u8 = 0x80;
+/// Unshift on space (P1 bit 4).
+const P1_USOS: u8 = 0x10;
+/// Reverse mark and space on the FSK output (P1 bit 3).
+const P1_REVERSE: u8 = 0x08;
+/// Two stop bits instead of 1.5 (P1 bit 2).
+const P1_TWO_STOP_BITS: u8 = 0x04;
+/// Baud rate select (P1 bits 1-0).
+const P1_BAUD_MASK: u8 = 0x03;
+/// Send LTRS diddles while the buffer is empty (P2 bit 0).
+const P2_DIDDLE: u8 = 0x01;
+
+/// ITA2 LTRS shift code.
+pub const LTRS: u8 = 0x1F;
+/// ITA2 FIGS shift code.
+pub const FIGS: u8 = 0x1B;
+
+/// RTTY data rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RttyBaud {
+    /// 45.45 baud, the amateur standard.
+    #[default]
+    Baud45,
+    Baud50,
+    Baud75,
+}
+
+impl RttyBaud {
+    /// Length of one bit.
+    pub fn bit_time(self) -> Duration {
+        match self {
+            Self::Baud45 => Duration::from_micros(22_000),
+            Self::Baud50 => Duration::from_micros(20_000),
+            Self::Baud75 => Duration::from_micros(13_333),
+        }
+    }
+
+    fn bits(self) -> u8 {
+        match self {
+            Self::Baud45 => 0,
+            Self::Baud50 => 1,
+            Self::Baud75 => 2,
+        }
+    }
+
+    fn from_bits(bits: u8) -> Self {
+        match bits & P1_BAUD_MASK {
+            1 => Self::Baud50,
+            2 => Self::Baud75,
+            _ => Self::Baud45,
+        }
+    }
+}
+
+/// RTTY settings, sent as the two Set RTTY Mode Registers bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RttyConfig {
+    pub baud: RttyBaud,
+    /// Swap mark and space on the FSK output.
+    pub reverse: bool,
+    /// Unshift on space: both ends fall back to LTRS after a space.
+    pub usos: bool,
+    /// Two stop bits instead of 1.5.
+    pub two_stop_bits: bool,
+    /// Send LTRS while idle to keep the receiving decoder in sync.
+    pub diddle: bool,
+}
+
+impl Default for RttyConfig {
+    /// 45.45 baud, 1.5 stop bits, USOS and diddle on.
+    fn default() -> Self {
+        Self {
+            baud: RttyBaud::Baud45,
+            reverse: false,
+            usos: true,
+            two_stop_bits: false,
+            diddle: true,
+        }
+    }
+}
+
+impl RttyConfig {
+    /// The P1 and P2 register bytes, with RTTY mode enabled.
+    pub fn registers(&self) -> (u8, u8) {
+        let mut p1 = P1_ENABLE | self.baud.bits();
+        if self.usos {
+            p1 |= P1_USOS;
+        }
+        if self.reverse {
+            p1 |= P1_REVERSE;
+        }
+        if self.two_stop_bits {
+            p1 |= P1_TWO_STOP_BITS;
+        }
+        let p2 = if self.diddle { P2_DIDDLE } else { 0 };
+        (p1, p2)
+    }
+
+    /// Decode register bytes, or `None` if they leave RTTY mode off.
+    pub fn from_registers(p1: u8, p2: u8) -> Option<Self> {
+        if p1 & P1_ENABLE == 0 {
+            return None;
+        }
+        Some(Self {
+            baud: RttyBaud::from_bits(p1),
+            reverse: p1 & P1_REVERSE != 0,
+            usos: p1 & P1_USOS != 0,
+            two_stop_bits: p1 & P1_TWO_STOP_BITS != 0,
+            diddle: p2 & P2_DIDDLE != 0,
+        })
+    }
+
+    /// Time for one Baudot character: start bit, five data bits, stop bits.
+    pub fn char_time(&self) -> Duration {
+        // Counted in half bits so 1.5 stop bits is exact.
+        let stop = if self.two_stop_bits { 4 } else { 3 };
+        self.baud.bit_time() * (12 + stop) / 2
+    }
+
+    /// Estimated on-air time for `text`, including the shift codes the
+    /// keyer will add. Characters RTTY can't send are not counted.
+    pub fn send_time(&self, text: &str) -> Duration {
+        let mut encoder = BaudotEncoder::new(self.usos);
+        let codes: usize = text.chars().map(|ch| encoder.encode(ch).len()).sum();
+        self.char_time() * codes as u32
+    }
+}
+
+/// Which half of the ITA2 table is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shift {
+    Letters,
+    Figures,
+}
+
+/// ITA2 code and shift for `ch` (US teleprinter figures). Space, CR and LF
+/// work in either shift and return `None` for the shift.
+pub fn baudot(ch: char) -> Option<(u8, Option<Shift>)> {
+    use Shift::{Figures, Letters};
+    let (code, shift) = match ch.to_ascii_uppercase() {
+        ' ' => (0x04, None),
+        '\r' => (0x08, None),
+        '\n' => (0x02, None),
+        'E' => (0x01, Some(Letters)),
+        'A' => (0x03, Some(Letters)),
+        'S' => (0x05, Some(Letters)),
+        'I' => (0x06, Some(Letters)),
+        'U' => (0x07, Some(Letters)),
+        'D' => (0x09, Some(Letters)),
+        'R' => (0x0A, Some(Letters)),
+        'J' => (0x0B, Some(Letters)),
+        'N' => (0x0C, Some(Letters)),
+        'F' => (0x0D, Some(Letters)),
+        'C' => (0x0E, Some(Letters)),
+        'K' => (0x0F, Some(Letters)),
+        'T' => (0x10, Some(Letters)),
+        'Z' => (0x11, Some(Letters)),
+        'L' => (0x12, Some(Letters)),
+        'W' => (0x13, Some(Letters)),
+        'H' => (0x14, Some(Letters)),
+        'Y' => (0x15, Some(Letters)),
+        'P' => (0x16, Some(Letters)),
+        'Q' => (0x17, Some(Letters)),
+        'O' => (0x18, Some(Letters)),
+        'B' => (0x19, Some(Letters)),
+        'G' => (0x1A, Some(Letters)),
+        'M' => (0x1C, Some(Letters)),
+        'X' => (0x1D, Some(Letters)),
+        'V' => (0x1E, Some(Letters)),
+        '3' => (0x01, Some(Figures)),
+        '-' => (0x03, Some(Figures)),
+        '8' => (0x06, Some(Figures)),
+        '7' => (0x07, Some(Figures)),
+        '$' => (0x09, Some(Figures)),
+        '4' => (0x0A, Some(Figures)),
+        '\'' => (0x0B, Some(Figures)),
+        ',' => (0x0C, Some(Figures)),
+        '!' => (0x0D, Some(Figures)),
+        ':' => (0x0E, Some(Figures)),
+        '(' => (0x0F, Some(Figures)),
+        '5' => (0x10, Some(Figures)),
+        '"' => (0x11, Some(Figures)),
+        ')' => (0x12, Some(Figures)),
+        '2' => (0x13, Some(Figures)),
+        '#' => (0x14, Some(Figures)),
+        '6' => (0x15, Some(Figures)),
+        '0' => (0x16, Some(Figures)),
+        '1' => (0x17, Some(Figures)),
+        '9' => (0x18, Some(Figures)),
+        '?' => (0x19, Some(Figures)),
+        '&' => (0x1A, Some(Figures)),
+        '.' => (0x1C, Some(Figures)),
+        '/' => (0x1D, Some(Figures)),
+        ';' => (0x1E, Some(Figures)),
+        _ => return None,
+    };
+    Some((code, shift))
+}
+
+/// Check that `text` can be queued in RTTY mode.
+///
+/// CR and LF have Baudot codes but are WinKeyer commands (Clear Buffer and
+/// Set Farnsworth) when written to the buffer, so they are rejected here.
+pub fn validate_rtty_text(text: &str) -> std::result::Result<(), String> {
+    for (i, ch) in text.chars().enumerate() {
+        if matches!(ch, '\r' | '\n') || baudot(ch).is_none() {
+            return Err(format!("invalid RTTY character {ch:?} at position {i}"));
+        }
+    }
+    Ok(())
+}
+
+/// ITA2 encoder tracking LTRS/FIGS state the way the keyer does.
+#[derive(Debug, Clone)]
+pub struct BaudotEncoder {
+    shift: Option<Shift>,
+    usos: bool,
+}
+
+impl BaudotEncoder {
+    /// A new encoder. The first character always gets a shift code, since
+    /// the receiver's state is unknown.
+    pub fn new(usos: bool) -> Self {
+        Self { shift: None, usos }
+    }
+
+    /// Current shift, once one has been sent.
+    pub fn shift(&self) -> Option<Shift> {
+        self.shift
+    }
+
+    /// Codes for `ch`, preceded by LTRS or FIGS if the shift changes.
+    /// Characters with no Baudot code give an empty result.
+    pub fn encode(&mut self, ch: char) -> Vec<u8> {
+        let Some((code, shift)) = baudot(ch) else {
+            return Vec::new();
+        };
+        let mut codes = Vec::with_capacity(2);
+        if let Some(shift) = shift
+            && self.shift != Some(shift)
+        {
+            codes.push(match shift {
+                Shift::Letters => LTRS,
+                Shift::Figures => FIGS,
+            });
+            self.shift = Some(shift);
+        }
+        codes.push(code);
+        if code == 0x04 && self.usos {
+            self.shift = Some(Shift::Letters);
+        }
+        codes
+    }
+
+    /// Codes for a whole string.
+    pub fn encode_str(&mut self, text: &str) -> Vec<u8> {
+        text.chars().flat_map(|ch| self.encode(ch)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registers_round_trip() {
+        let config = RttyConfig {
+            baud: RttyBaud::Baud75,
+            reverse: true,
+            usos: false,
+            two_stop_bits: true,
+            diddle: false,
+        };
+        assert_eq!(config.registers(), (0x80 | 0x08 | 0x04 | 0x02, 0));
+        let (p1, p2) = config.registers();
+        assert_eq!(RttyConfig::from_registers(p1, p2), Some(config));
+        assert_eq!(RttyConfig::default().registers(), (0x90, 0x01));
+        assert_eq!(RttyConfig::from_registers(0x00, 0x00), None);
+    }
+
+    #[test]
+    fn shifts_inserted_on_change() {
+        let mut enc = BaudotEncoder::new(false);
+        assert_eq!(enc.encode_str("RY"), [LTRS, 0x0A, 0x15]);
+        assert_eq!(enc.encode_str("599"), [FIGS, 0x10, 0x18, 0x18]);
+        // Space works in either shift
+        assert_eq!(enc.encode_str(" 1"), [0x04, 0x17]);
+        assert_eq!(enc.encode_str("K"), [LTRS, 0x0F]);
+        assert_eq!(enc.shift(), Some(Shift::Letters));
+    }
+
+    #[test]
+    fn usos_reshifts_after_space() {
+        let mut enc = BaudotEncoder::new(true);
+        assert_eq!(enc.encode_str("5 5"), [FIGS, 0x10, 0x04, FIGS, 0x10]);
+        let mut enc = BaudotEncoder::new(false);
+        assert_eq!(enc.encode_str("5 5"), [FIGS, 0x10, 0x04, 0x10]);
+    }
+
+    #[test]
+    fn text_validation() {
+        assert!(validate_rtty_text("CQ TEST DE K1EL 599 #12").is_ok());
+        assert!(validate_rtty_text("cq $5").is_ok());
+        assert!(validate_rtty_text("CQ\r\n").is_err());
+        assert!(validate_rtty_text("A+B").is_err());
+        assert!(validate_rtty_text("@").is_err());
+    }
+
+    #[test]
+    fn timing() {
+        let config = RttyConfig::default();
+        // 7.5 bits at 22ms
+        assert_eq!(config.char_time(), Duration::from_millis(165));
+        // LTRS R Y
+        assert_eq!(config.send_time("RY"), Duration::from_millis(495));
+        let two = RttyConfig {
+            two_stop_bits: true,
+            ..config
+        };
+        assert_eq!(two.char_time(), Duration::from_millis(176));
+    }
+}
@@ -0,0 +1,418 @@
+//! Bit-banged keyer on a serial port's DTR/RTS modem control lines.
+//!
+//! Many portable stations key the rig with a transistor hung off a
+//! USB-serial adapter. [`SerialLineKeyer`] generates Morse timing on the
+//! host with the crate's [`timing`](crate::timing) model and toggles the
+//! control lines for key and PTT.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio_serial::SerialPort;
+
+use crate::error::{Error, Result};
+use crate::event::EventReceiver;
+use crate::keyer::{Keyer, KeyerCapabilities, KeyerInfo};
+use crate::soft::{Control, LineDriver, PttTiming, SoftKeyer};
+use crate::timing::MorseTiming;
+use crate::transport;
+
+/// A serial port modem control line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlLine {
+    /// Data Terminal Ready.
+    Dtr,
+    /// Request To Send.
+    Rts,
+}
+
+/// [`LineDriver`] that drives DTR/RTS on an open serial port.
+pub struct SerialLines {
+    port: tokio_serial::SerialStream,
+    key_line: ControlLine,
+    ptt_line: Option<ControlLine>,
+}
+
+impl SerialLines {
+    /// Wrap an open port, keying on `key_line` and (optionally) PTT on `ptt_line`.
+    pub fn new(
+        port: tokio_serial::SerialStream,
+        key_line: ControlLine,
+        ptt_line: Option<ControlLine>,
+    ) -> Self {
+        Self {
+            port,
+            key_line,
+            ptt_line,
+        }
+    }
+
+    fn write_line(&mut self, line: ControlLine, level: bool) -> std::io::Result<()> {
+        match line {
+            ControlLine::Dtr => self.port.write_data_terminal_ready(level),
+            ControlLine::Rts => self.port.write_request_to_send(level),
+        }
+        .map_err(std::io::Error::from)
+    }
+}
+
+impl LineDriver for SerialLines {
+    fn set_key(&mut self, down: bool) -> std::io::Result<()> {
+        self.write_line(self.key_line, down)
+    }
+
+    fn set_ptt(&mut self, on: bool) -> std::io::Result<()> {
+        match self.ptt_line {
+            Some(line) => self.write_line(line, on),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Builder for a [`SerialLineKeyer`].
+///
+/// # Example
+///
+/// ```no_run
+/// # use winkey::serial_line::{ControlLine, SerialLineKeyerBuilder};
+/// # async fn example() -> winkey::Result<()> {
+/// let keyer = SerialLineKeyerBuilder::new("/dev/ttyUSB0")
+///     .speed(22)
+///     .key_line(ControlLine::Dtr)
+///     .ptt_line(Some(ControlLine::Rts))
+///     .ptt_lead_in_ms(50)
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct SerialLineKeyerBuilder {
+    port_path: String,
+    timing: MorseTiming,
+    key_line: ControlLine,
+    ptt_line: Option<ControlLine>,
+    ptt_lead_in_ms: u16,
+    ptt_tail_ms: u16,
+}
+
+impl SerialLineKeyerBuilder {
+    /// Create a new builder for the given serial port path.
+    pub fn new(port_path: &str) -> Self {
+        Self {
+            port_path: port_path.to_string(),
+            timing: MorseTiming::default(),
+            key_line: ControlLine::Dtr,
+            ptt_line: None,
+            ptt_lead_in_ms: 0,
+            ptt_tail_ms: 0,
+        }
+    }
+
+    /// Set the initial CW speed in WPM (5-99).
+    pub fn speed(mut self, wpm: u8) -> Self {
+        self.timing.wpm = wpm;
+        self
+    }
+
+    /// Set keying weight (10-90, default 50).
+    pub fn weight(mut self, value: u8) -> Self {
+        self.timing.weight = value;
+        self
+    }
+
+    /// Set dit/dah ratio (33-66, default 50 = 3:1).
+    pub fn dit_dah_ratio(mut self, ratio: u8) -> Self {
+        self.timing.dit_dah_ratio = ratio;
+        self
+    }
+
+    /// Set Farnsworth character speed (0 = disable).
+    pub fn farnsworth(mut self, wpm: u8) -> Self {
+        self.timing.farnsworth_wpm = wpm;
+        self
+    }
+
+    /// Enable or disable contest spacing (6-unit word gap).
+    pub fn contest_spacing(mut self, enabled: bool) -> Self {
+        self.timing.contest_spacing = enabled;
+        self
+    }
+
+    /// Control line used for keying (default DTR).
+    pub fn key_line(mut self, line: ControlLine) -> Self {
+        self.key_line = line;
+        self
+    }
+
+    /// Control line used for PTT (default none).
+    pub fn ptt_line(mut self, line: Option<ControlLine>) -> Self {
+        self.ptt_line = line;
+        self
+    }
+
+    /// Set PTT lead-in time in milliseconds.
+    pub fn ptt_lead_in_ms(mut self, ms: u16) -> Self {
+        self.ptt_lead_in_ms = ms;
+        self
+    }
+
+    /// Set PTT tail time in milliseconds.
+    pub fn ptt_tail_ms(mut self, ms: u16) -> Self {
+        self.ptt_tail_ms = ms;
+        self
+    }
+
+    /// Validate builder parameters against the keyer's limits.
+    fn validate(&self) -> Result<()> {
+        if !(5..=99).contains(&self.timing.wpm) {
+            return Err(Error::InvalidParameter(format!(
+                "speed_wpm must be 5-99, got {}",
+                self.timing.wpm
+            )));
+        }
+        if !(10..=90).contains(&self.timing.weight) {
+            return Err(Error::InvalidParameter(format!(
+                "weight must be 10-90, got {}",
+                self.timing.weight
+            )));
+        }
+        if !(33..=66).contains(&self.timing.dit_dah_ratio) {
+            return Err(Error::InvalidParameter(format!(
+                "dit_dah_ratio must be 33-66, got {}",
+                self.timing.dit_dah_ratio
+            )));
+        }
+        if self.ptt_line == Some(self.key_line) {
+            return Err(Error::InvalidParameter(
+                "key and PTT must use different control lines".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Open the serial port and start keying on its control lines.
+    pub async fn build(self) -> Result<SerialLineKeyer> {
+        self.validate()?;
+        let port = transport::open_serial(&self.port_path, 9600)?;
+        let lines = SerialLines::new(port, self.key_line, self.ptt_line);
+        self.build_with_driver(lines).await
+    }
+
+    /// Build using any [`LineDriver`] (for testing or custom hardware).
+    pub async fn build_with_driver<D: LineDriver>(self, mut driver: D) -> Result<SerialLineKeyer> {
+        self.validate()?;
+
+        // Opening a port commonly asserts DTR/RTS; make sure we start unkeyed.
+        driver.set_key(false)?;
+        driver.set_ptt(false)?;
+
+        let ptt = PttTiming {
+            enabled: self.ptt_line.is_some(),
+            lead_in: Duration::from_millis(self.ptt_lead_in_ms as u64),
+            tail: Duration::from_millis(self.ptt_tail_ms as u64),
+        };
+
+        let soft = SoftKeyer::spawn(driver, self.timing, ptt);
+
+        Ok(SerialLineKeyer {
+            soft,
+            info: KeyerInfo {
+                name: "Serial line keyer".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                port: Some(self.port_path),
+            },
+            capabilities: KeyerCapabilities {
+                speed_pot: false,
+                sidetone: false,
+                ptt_control: self.ptt_line.is_some(),
+                paddle_echo: false,
+                prosigns: true,
+                buffered_speed: true,
+                farnsworth: true,
+                contest_spacing: true,
+                pause: true,
+                weight: true,
+                key_state: true,
+            },
+        })
+    }
+}
+
+/// Keyer that bit-bangs CW on serial DTR/RTS lines.
+///
+/// Emits `CharacterSent` as each character starts and `StatusChanged`
+/// (busy/keydown) in the same shape as [`WinKeyer`](crate::WinKeyer).
+pub struct SerialLineKeyer {
+    soft: SoftKeyer,
+    info: KeyerInfo,
+    capabilities: KeyerCapabilities,
+}
+
+impl std::fmt::Debug for SerialLineKeyer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SerialLineKeyer")
+            .field("info", &self.info)
+            .field("timing", &self.soft.timing())
+            .finish()
+    }
+}
+
+impl SerialLineKeyer {
+    /// Set dit/dah ratio (33-66, default 50 = 3:1).
+    pub async fn set_ratio(&self, ratio: u8) -> Result<()> {
+        self.soft.set_ratio(ratio).await
+    }
+
+    /// Queue raw WinKeyer buffered bytes (e.g. from
+    /// [`build_contest_message`](crate::message::build_contest_message)).
+    pub async fn raw_write(&self, data: &[u8]) -> Result<()> {
+        self.soft.engine.buffered(data.to_vec()).await
+    }
+}
+
+#[async_trait]
+impl Keyer for SerialLineKeyer {
+    fn info(&self) -> &KeyerInfo {
+        &self.info
+    }
+
+    fn capabilities(&self) -> &KeyerCapabilities {
+        &self.capabilities
+    }
+
+    async fn send_message(&self, text: &str) -> Result<()> {
+        self.soft.send_message(text).await
+    }
+
+    async fn abort(&self) -> Result<()> {
+        self.soft.engine.control(Control::Abort).await
+    }
+
+    async fn set_speed(&self, wpm: u8) -> Result<()> {
+        self.soft.set_speed(wpm).await
+    }
+
+    async fn get_speed(&self) -> Result<u8> {
+        Ok(self.soft.timing().wpm)
+    }
+
+    async fn set_tune(&self, on: bool) -> Result<()> {
+        self.soft.engine.control(Control::Tune(on)).await
+    }
+
+    async fn set_ptt(&self, on: bool) -> Result<()> {
+        self.soft.set_ptt(on).await
+    }
+
+    fn subscribe(&self) -> EventReceiver {
+        self.soft.event_tx.subscribe()
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.soft.close().await
+    }
+
+    async fn send_prosign(&self, c1: u8, c2: u8) -> Result<()> {
+        self.soft.send_prosign(c1, c2).await
+    }
+
+    async fn set_buffered_speed(&self, wpm: u8) -> Result<()> {
+        self.soft.set_buffered_speed(wpm).await
+    }
+
+    async fn cancel_buffered_speed(&self) -> Result<()> {
+        self.soft.cancel_buffered_speed().await
+    }
+
+    async fn set_pause(&self, paused: bool) -> Result<()> {
+        self.soft.set_pause(paused).await
+    }
+
+    async fn set_weight(&self, weight: u8) -> Result<()> {
+        self.soft.set_weight(weight).await
+    }
+
+    async fn set_farnsworth(&self, wpm: u8) -> Result<()> {
+        self.soft.set_farnsworth(wpm).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::KeyerEvent;
+    use crate::soft::tests::{FakeLines, Line};
+    use tokio::time::Instant;
+
+    #[tokio::test]
+    async fn build_invalid_speed() {
+        let result = SerialLineKeyerBuilder::new("/dev/ttyUSB0")
+            .speed(100)
+            .build_with_driver(FakeLines::default())
+            .await;
+        assert!(matches!(result, Err(Error::InvalidParameter(_))));
+    }
+
+    #[tokio::test]
+    async fn build_rejects_shared_key_and_ptt_line() {
+        let result = SerialLineKeyerBuilder::new("/dev/ttyUSB0")
+            .key_line(ControlLine::Rts)
+            .ptt_line(Some(ControlLine::Rts))
+            .build_with_driver(FakeLines::default())
+            .await;
+        assert!(matches!(result, Err(Error::InvalidParameter(_))));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn send_message_keys_lines() {
+        let lines = FakeLines::default();
+        let keyer = SerialLineKeyerBuilder::new("/dev/ttyUSB0")
+            .speed(20)
+            .ptt_line(Some(ControlLine::Rts))
+            .ptt_lead_in_ms(20)
+            .build_with_driver(lines.clone())
+            .await
+            .unwrap();
+        let mut rx = keyer.subscribe();
+        let start = Instant::now();
+
+        keyer.send_message("t").await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let trace = lines.trace(start);
+        // Initial unkey from build, then PTT, key down 20 ms later for a dah
+        assert_eq!(
+            &trace[2..],
+            &[
+                (0, Line::Ptt, true),
+                (20, Line::Key, true),
+                (200, Line::Key, false),
+                (380, Line::Ptt, false),
+            ]
+        );
+
+        let mut echoed = String::new();
+        while let Ok(ev) = rx.try_recv() {
+            if let KeyerEvent::CharacterSent { ch, .. } = ev {
+                echoed.push(ch);
+            }
+        }
+        assert_eq!(echoed, "T");
+
+        keyer.close().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn set_speed_updates_timing() {
+        let keyer = SerialLineKeyerBuilder::new("/dev/ttyUSB0")
+            .build_with_driver(FakeLines::default())
+            .await
+            .unwrap();
+
+        keyer.set_speed(30).await.unwrap();
+        assert_eq!(keyer.get_speed().await.unwrap(), 30);
+        assert!(keyer.set_speed(4).await.is_err());
+
+        keyer.close().await.unwrap();
+    }
+}
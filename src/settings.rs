@@ -0,0 +1,514 @@
+//! Snapshot of a WinKeyer's current settings.
+//!
+//! WinKeyer has no command to read most settings back, so [`WinKeyer`]
+//! keeps this cache up to date from every setter it sends (and from speed
+//! pot events, see [`speed_pot`](crate::speed_pot)). Read it with [`WinKeyer::settings`] or follow changes with
+//! [`WinKeyer::watch_settings`].
+//!
+//! [`WinKeyer`]: crate::WinKeyer
+//! [`WinKeyer::settings`]: crate::WinKeyer::settings
+//! [`WinKeyer::watch_settings`]: crate::WinKeyer::watch_settings
+//!
+//! [`SettingsChange`] describes several settings to change at once for
+//! [`WinKeyer::configure`](crate::WinKeyer::configure).
+
+use crate::builder::{validate_paddle_timing, validate_settings};
+use crate::error::{Error, Result};
+use crate::protocol::decoder::Command;
+use crate::protocol::types::{
+    sidetone_byte, sidetone_hz, LoadDefaults, ModeRegister, PaddleMode, PinConfig,
+    WinKeyerVersion,
+};
+use crate::rtty::RttyConfig;
+
+/// Settings last sent to the keyer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyerSettings {
+    /// Host-set CW speed in WPM.
+    pub speed_wpm: u8,
+    /// Last speed pot reading, once the pot has reported.
+    pub speed_pot_wpm: Option<u8>,
+    /// HSCW speed in letters per minute while in HSCW mode.
+    pub hscw_lpm: Option<u16>,
+    /// RTTY settings while in RTTY mode (WK3.1).
+    pub rtty: Option<RttyConfig>,
+    pub weight: u8,
+    pub dit_dah_ratio: u8,
+    /// Sidetone frequency in Hz.
+    pub sidetone_hz: u16,
+    /// WK3 sidetone volume, once set.
+    pub sidetone_volume: Option<u8>,
+    /// Farnsworth speed (0 = disabled).
+    pub farnsworth_wpm: u8,
+    /// Full mode register byte, including paddle mode bits.
+    pub mode_register: u8,
+    pub pin_config: PinConfig,
+    /// PTT lead-in in 10ms units.
+    pub ptt_lead_in: u8,
+    /// PTT tail in 10ms units.
+    pub ptt_tail: u8,
+    /// Speed pot minimum in WPM.
+    pub min_wpm: u8,
+    /// Speed pot range in WPM.
+    pub wpm_range: u8,
+    /// Key compensation in milliseconds.
+    pub key_compensation: u8,
+    /// First extension in milliseconds. Not part of Load Defaults.
+    pub first_extension: u8,
+    pub paddle_switchpoint: u8,
+    /// X1MODE extension register.
+    pub x1_mode: u8,
+    /// X2MODE extension register (WK3).
+    pub x2_mode: u8,
+    pub paused: bool,
+}
+
+impl KeyerSettings {
+    /// Settings after a Load Defaults block.
+    pub fn from_load_defaults(defaults: &LoadDefaults, version: WinKeyerVersion) -> Self {
+        Self {
+            speed_wpm: defaults.speed_wpm,
+            speed_pot_wpm: None,
+            hscw_lpm: None,
+            rtty: None,
+            weight: defaults.weight,
+            dit_dah_ratio: defaults.dit_dah_ratio,
+            sidetone_hz: sidetone_hz(defaults.sidetone, version),
+            sidetone_volume: None,
+            farnsworth_wpm: defaults.farnsworth_wpm,
+            mode_register: defaults.mode_register,
+            pin_config: PinConfig::from_bits_retain(defaults.pin_config),
+            ptt_lead_in: defaults.lead_in_time,
+            ptt_tail: defaults.tail_time,
+            min_wpm: defaults.min_wpm,
+            wpm_range: defaults.wpm_range,
+            key_compensation: defaults.key_compensation,
+            first_extension: 0,
+            paddle_switchpoint: defaults.paddle_setpoint,
+            x1_mode: defaults.x1_mode,
+            x2_mode: defaults.x2_mode,
+            paused: false,
+        }
+    }
+
+    /// The Load Defaults block that recreates these settings.
+    pub fn to_load_defaults(&self, version: WinKeyerVersion) -> LoadDefaults {
+        LoadDefaults {
+            mode_register: self.mode_register,
+            speed_wpm: self.speed_wpm,
+            sidetone: sidetone_byte(self.sidetone_hz, version),
+            weight: self.weight,
+            lead_in_time: self.ptt_lead_in,
+            tail_time: self.ptt_tail,
+            min_wpm: self.min_wpm,
+            wpm_range: self.wpm_range,
+            x2_mode: self.x2_mode,
+            key_compensation: self.key_compensation,
+            farnsworth_wpm: self.farnsworth_wpm,
+            paddle_setpoint: self.paddle_switchpoint,
+            dit_dah_ratio: self.dit_dah_ratio,
+            pin_config: self.pin_config.bits(),
+            x1_mode: self.x1_mode,
+        }
+    }
+
+    /// Paddle mode from the mode register.
+    pub fn paddle_mode(&self) -> PaddleMode {
+        PaddleMode::from_mode_bits(self.mode_register)
+    }
+
+    /// Mode register flags, without the paddle mode bits.
+    pub fn mode_flags(&self) -> ModeRegister {
+        ModeRegister::from_bits_truncate(self.mode_register)
+    }
+
+    /// Change the speed pot range. A pot reading taken on the old scale is
+    /// dropped until the keyer reports the pot again.
+    fn set_speed_pot_range(&mut self, min_wpm: u8, wpm_range: u8) {
+        if (min_wpm, wpm_range) != (self.min_wpm, self.wpm_range) {
+            self.speed_pot_wpm = None;
+        }
+        self.min_wpm = min_wpm;
+        self.wpm_range = wpm_range;
+    }
+
+    /// Update from a host command sent to the keyer. Commands that don't
+    /// change a cached setting are ignored.
+    pub(crate) fn apply_command(&mut self, cmd: &Command, version: WinKeyerVersion) {
+        match *cmd {
+            Command::SetSpeed(wpm) if wpm != 0 => {
+                self.speed_wpm = wpm;
+                self.hscw_lpm = None;
+            }
+            Command::SetHscwSpeed(v) => self.hscw_lpm = (v != 0).then(|| v as u16 * 100),
+            Command::SetWeight(w) => self.weight = w,
+            Command::SetRatio(r) => self.dit_dah_ratio = r,
+            Command::SidetoneControl(v) => self.sidetone_hz = sidetone_hz(v, version),
+            Command::SetSidetoneVolume(v) => self.sidetone_volume = Some(v),
+            Command::SetFarnsworth(wpm) => self.farnsworth_wpm = wpm,
+            Command::SetModeRegister(v) => self.mode_register = v,
+            Command::SetPinConfig(v) => self.pin_config = PinConfig::from_bits_retain(v),
+            Command::SetPttTiming { lead_in, tail } => {
+                self.ptt_lead_in = lead_in;
+                self.ptt_tail = tail;
+            }
+            Command::SetSpeedPot { min, range } => self.set_speed_pot_range(min, range),
+            Command::SetKeyCompensation(v) => self.key_compensation = v,
+            Command::SetFirstExtension(v) => self.first_extension = v,
+            Command::SetPaddleSwitchpoint(v) => self.paddle_switchpoint = v,
+            Command::LoadX1Mode(v) => self.x1_mode = v,
+            Command::LoadX2Mode(v) => self.x2_mode = v,
+            Command::SetPause(on) => self.paused = on,
+            Command::SetRttyRegisters(p1, p2) => self.rtty = RttyConfig::from_registers(p1, p2),
+            Command::LoadDefaults(ref d) => {
+                self.set_speed_pot_range(d.min_wpm, d.wpm_range);
+                *self = Self {
+                    speed_pot_wpm: self.speed_pot_wpm,
+                    sidetone_volume: self.sidetone_volume,
+                    first_extension: self.first_extension,
+                    rtty: self.rtty,
+                    ..Self::from_load_defaults(d, version)
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// Check against the same limits as the builder and the setters.
+    pub(crate) fn validate(&self) -> Result<()> {
+        validate_settings(
+            self.speed_wpm,
+            self.weight,
+            self.dit_dah_ratio,
+            self.min_wpm,
+            self.wpm_range,
+        )?;
+        validate_paddle_timing(
+            self.key_compensation,
+            self.first_extension,
+            self.paddle_switchpoint,
+        )?;
+        if !(500..=4000).contains(&self.sidetone_hz) {
+            return Err(Error::InvalidParameter(format!(
+                "sidetone must be 500-4000 Hz, got {}",
+                self.sidetone_hz
+            )));
+        }
+        Ok(())
+    }
+
+    /// Commands that take the keyer from `self` to `target`, one per
+    /// changed setting.
+    fn diff(&self, target: &Self, version: WinKeyerVersion) -> Vec<Command> {
+        let mut cmds = Vec::new();
+        if target.mode_register != self.mode_register {
+            cmds.push(Command::SetModeRegister(target.mode_register));
+        }
+        if target.speed_wpm != self.speed_wpm {
+            cmds.push(Command::SetSpeed(target.speed_wpm));
+        }
+        let tone = sidetone_byte(target.sidetone_hz, version);
+        if tone != sidetone_byte(self.sidetone_hz, version) {
+            cmds.push(Command::SidetoneControl(tone));
+        }
+        if target.weight != self.weight {
+            cmds.push(Command::SetWeight(target.weight));
+        }
+        if (target.ptt_lead_in, target.ptt_tail) != (self.ptt_lead_in, self.ptt_tail) {
+            cmds.push(Command::SetPttTiming {
+                lead_in: target.ptt_lead_in,
+                tail: target.ptt_tail,
+            });
+        }
+        if (target.min_wpm, target.wpm_range) != (self.min_wpm, self.wpm_range) {
+            cmds.push(Command::SetSpeedPot {
+                min: target.min_wpm,
+                range: target.wpm_range,
+            });
+        }
+        if target.farnsworth_wpm != self.farnsworth_wpm {
+            cmds.push(Command::SetFarnsworth(target.farnsworth_wpm));
+        }
+        if target.dit_dah_ratio != self.dit_dah_ratio {
+            cmds.push(Command::SetRatio(target.dit_dah_ratio));
+        }
+        if target.pin_config != self.pin_config {
+            cmds.push(Command::SetPinConfig(target.pin_config.bits()));
+        }
+        cmds
+    }
+
+    /// Bytes that take the keyer from `self` to `target`: the individual
+    /// commands, or a Load Defaults block when that is shorter. The block is
+    /// always followed by [`load_defaults_followup`], which isn't counted:
+    /// it would make the block lose every time.
+    pub(crate) fn update_bytes(&self, target: &Self, version: WinKeyerVersion) -> Vec<u8> {
+        let separate: Vec<u8> = self
+            .diff(target, version)
+            .iter()
+            .flat_map(Command::encode)
+            .collect();
+        let defaults = target.to_load_defaults(version);
+        let mut block = Command::LoadDefaults(defaults.clone()).encode();
+        if separate.len() <= block.len() {
+            separate
+        } else {
+            block.extend(load_defaults_followup(&defaults));
+            block
+        }
+    }
+}
+
+/// Standalone commands to send after a Load Defaults block, re-asserting the
+/// mode register, pin config and sidetone that some WK3.1 firmware doesn't
+/// take from the block.
+pub(crate) fn load_defaults_followup(defaults: &LoadDefaults) -> Vec<u8> {
+    [
+        Command::SetModeRegister(defaults.mode_register),
+        Command::SetPinConfig(defaults.pin_config),
+        Command::SidetoneControl(defaults.sidetone),
+    ]
+    .iter()
+    .flat_map(Command::encode)
+    .collect()
+}
+
+/// Several settings to change together, built up for
+/// [`WinKeyer::configure`](crate::WinKeyer::configure).
+///
+/// Settings left out keep their current value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[must_use]
+pub struct SettingsChange {
+    speed_wpm: Option<u8>,
+    weight: Option<u8>,
+    dit_dah_ratio: Option<u8>,
+    sidetone_hz: Option<u16>,
+    farnsworth_wpm: Option<u8>,
+    paddle_mode: Option<PaddleMode>,
+    contest_spacing: Option<bool>,
+    auto_space: Option<bool>,
+    swap_paddles: Option<bool>,
+    pin_config: Option<PinConfig>,
+    ptt_timing: Option<(u8, u8)>,
+    speed_pot: Option<(u8, u8)>,
+}
+
+impl SettingsChange {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// CW speed in WPM (5-99).
+    pub fn speed(mut self, wpm: u8) -> Self {
+        self.speed_wpm = Some(wpm);
+        self
+    }
+
+    /// Keying weight (10-90).
+    pub fn weight(mut self, weight: u8) -> Self {
+        self.weight = Some(weight);
+        self
+    }
+
+    /// Dit/dah ratio (33-66).
+    pub fn dit_dah_ratio(mut self, ratio: u8) -> Self {
+        self.dit_dah_ratio = Some(ratio);
+        self
+    }
+
+    /// Sidetone frequency in Hz (500-4000).
+    pub fn sidetone(mut self, freq_hz: u16) -> Self {
+        self.sidetone_hz = Some(freq_hz);
+        self
+    }
+
+    /// Farnsworth speed (0 = disable).
+    pub fn farnsworth(mut self, wpm: u8) -> Self {
+        self.farnsworth_wpm = Some(wpm);
+        self
+    }
+
+    pub fn paddle_mode(mut self, mode: PaddleMode) -> Self {
+        self.paddle_mode = Some(mode);
+        self
+    }
+
+    pub fn contest_spacing(mut self, enabled: bool) -> Self {
+        self.contest_spacing = Some(enabled);
+        self
+    }
+
+    pub fn auto_space(mut self, enabled: bool) -> Self {
+        self.auto_space = Some(enabled);
+        self
+    }
+
+    pub fn swap_paddles(mut self, enabled: bool) -> Self {
+        self.swap_paddles = Some(enabled);
+        self
+    }
+
+    pub fn pin_config(mut self, config: PinConfig) -> Self {
+        self.pin_config = Some(config);
+        self
+    }
+
+    /// PTT lead-in and tail in 10ms units.
+    pub fn ptt_timing(mut self, lead_in: u8, tail: u8) -> Self {
+        self.ptt_timing = Some((lead_in, tail));
+        self
+    }
+
+    /// Speed pot minimum and range in WPM.
+    pub fn speed_pot(mut self, min_wpm: u8, wpm_range: u8) -> Self {
+        self.speed_pot = Some((min_wpm, wpm_range));
+        self
+    }
+
+    /// True if nothing was set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Apply to a settings snapshot.
+    pub(crate) fn apply(&self, s: &mut KeyerSettings) {
+        if let Some(wpm) = self.speed_wpm {
+            s.speed_wpm = wpm;
+        }
+        if let Some(w) = self.weight {
+            s.weight = w;
+        }
+        if let Some(r) = self.dit_dah_ratio {
+            s.dit_dah_ratio = r;
+        }
+        if let Some(hz) = self.sidetone_hz {
+            s.sidetone_hz = hz;
+        }
+        if let Some(wpm) = self.farnsworth_wpm {
+            s.farnsworth_wpm = wpm;
+        }
+        if let Some(mode) = self.paddle_mode {
+            s.mode_register = (s.mode_register & !0x30) | mode.to_mode_bits();
+        }
+        let mut flags = ModeRegister::from_bits_retain(s.mode_register);
+        for (flag, value) in [
+            (ModeRegister::CONTEST_SPACING, self.contest_spacing),
+            (ModeRegister::AUTO_SPACE, self.auto_space),
+            (ModeRegister::SWAP_PADDLES, self.swap_paddles),
+        ] {
+            if let Some(on) = value {
+                flags.set(flag, on);
+            }
+        }
+        s.mode_register = flags.bits();
+        if let Some(pins) = self.pin_config {
+            s.pin_config = pins;
+        }
+        if let Some((lead_in, tail)) = self.ptt_timing {
+            s.ptt_lead_in = lead_in;
+            s.ptt_tail = tail;
+        }
+        if let Some((min, range)) = self.speed_pot {
+            s.set_speed_pot_range(min, range);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_update_snapshot() {
+        let version = WinKeyerVersion::Wk3;
+        let mut settings = KeyerSettings::from_load_defaults(&LoadDefaults::default(), version);
+        settings.apply_command(&Command::SetWeight(60), version);
+        settings.apply_command(&Command::SetPttTiming { lead_in: 5, tail: 3 }, version);
+        settings.apply_command(&Command::SidetoneControl(104), version);
+        settings.apply_command(&Command::SetModeRegister(0x54), version);
+        settings.apply_command(&Command::Text("CQ".into()), version);
+        assert_eq!(settings.weight, 60);
+        assert_eq!((settings.ptt_lead_in, settings.ptt_tail), (5, 3));
+        assert_eq!(settings.sidetone_hz, 600);
+        assert_eq!(settings.paddle_mode(), PaddleMode::IambicA);
+        assert!(settings.mode_flags().contains(ModeRegister::SERIAL_ECHO));
+    }
+
+    #[test]
+    fn load_defaults_keeps_pot_reading() {
+        let version = WinKeyerVersion::Wk2;
+        let mut settings = KeyerSettings::from_load_defaults(&LoadDefaults::default(), version);
+        settings.speed_pot_wpm = Some(22);
+        let defaults = LoadDefaults {
+            speed_wpm: 30,
+            ..Default::default()
+        };
+        settings.apply_command(&Command::LoadDefaults(defaults), version);
+        assert_eq!(settings.speed_wpm, 30);
+        assert_eq!(settings.speed_pot_wpm, Some(22));
+
+        // A new pot range makes the reading stale
+        let defaults = LoadDefaults {
+            min_wpm: 15,
+            ..Default::default()
+        };
+        settings.apply_command(&Command::LoadDefaults(defaults), version);
+        assert_eq!(settings.speed_pot_wpm, None);
+    }
+
+    fn wk3_defaults() -> KeyerSettings {
+        KeyerSettings::from_load_defaults(&LoadDefaults::default(), WinKeyerVersion::Wk3)
+    }
+
+    #[test]
+    fn small_change_sends_separate_commands() {
+        let current = wk3_defaults();
+        let mut target = current.clone();
+        SettingsChange::new()
+            .speed(30)
+            .weight(50) // unchanged
+            .contest_spacing(true)
+            .apply(&mut target);
+        assert_eq!(
+            current.update_bytes(&target, WinKeyerVersion::Wk3),
+            [0x0E, current.mode_register | 0x01, 0x02, 30]
+        );
+        assert!(current.update_bytes(&current, WinKeyerVersion::Wk3).is_empty());
+    }
+
+    #[test]
+    fn large_change_uses_load_defaults() {
+        let current = wk3_defaults();
+        let mut target = current.clone();
+        SettingsChange::new()
+            .speed(18)
+            .weight(55)
+            .dit_dah_ratio(45)
+            .farnsworth(12)
+            .ptt_timing(3, 2)
+            .speed_pot(10, 20)
+            .sidetone(600)
+            .apply(&mut target);
+        let bytes = current.update_bytes(&target, WinKeyerVersion::Wk3);
+        assert_eq!(bytes.len(), 22);
+        match Command::decode(&bytes) {
+            Some((Command::LoadDefaults(d), 16)) => {
+                assert_eq!(d, target.to_load_defaults(WinKeyerVersion::Wk3));
+                assert_eq!(bytes[16..], load_defaults_followup(&d));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn paddle_mode_and_flags_share_mode_register() {
+        let mut s = wk3_defaults();
+        SettingsChange::new()
+            .paddle_mode(PaddleMode::Bug)
+            .swap_paddles(true)
+            .apply(&mut s);
+        assert_eq!(s.paddle_mode(), PaddleMode::Bug);
+        assert!(s.mode_flags().contains(ModeRegister::SWAP_PADDLES | ModeRegister::SERIAL_ECHO));
+    }
+}
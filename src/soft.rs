@@ -0,0 +1,999 @@
+//! Software keying engine for backends that generate Morse on the host.
+//!
+//! Mirrors the WinKeyer IO task: a single tokio task owns the output lines
+//! and runs a biased select loop over an RT channel (abort, tune, PTT,
+//! timing) and a BG channel. The BG channel carries the same buffered byte
+//! stream the WinKeyer accepts (text, merged prosigns, buffered speed
+//! changes, buffered PTT/key/wait), so messages built with
+//! [`crate::message`] play identically on software backends.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, trace};
+
+use crate::error::{Error, Result};
+use crate::event::{EchoSource, EventSender, KeyerEvent, KeyerStatus};
+use crate::protocol::command;
+use crate::protocol::decoder::Command;
+use crate::timing::{KeyStep, MorseTiming};
+
+/// Output lines driven by a software keyer.
+///
+/// Implementations should apply the new state immediately; all timing is
+/// done by the engine.
+pub trait LineDriver: Send + 'static {
+    /// Key (true) or unkey (false) the transmitter.
+    fn set_key(&mut self, down: bool) -> std::io::Result<()>;
+
+    /// Assert (true) or release (false) PTT.
+    fn set_ptt(&mut self, on: bool) -> std::io::Result<()>;
+
+    /// Called as each character starts keying. Default: no-op.
+    fn character(&mut self, _ch: char) {}
+}
+
+/// PTT sequencing around automatically keyed transmissions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct PttTiming {
+    pub enabled: bool,
+    pub lead_in: Duration,
+    pub tail: Duration,
+}
+
+/// Immediate (RT) engine controls.
+#[derive(Debug)]
+pub(crate) enum Control {
+    /// Clear the buffer, unkey, and drop automatic PTT.
+    Abort,
+    /// Key-down tune mode.
+    Tune(bool),
+    /// Stop taking new characters from the buffer.
+    Pause(bool),
+    /// Replace the base timing (speed, weight, ratio, Farnsworth).
+    Timing(MorseTiming),
+}
+
+/// A request sent to the engine task.
+#[derive(Debug)]
+pub(crate) enum Request {
+    /// Apply an immediate control.
+    Control {
+        control: Control,
+        reply: oneshot::Sender<Result<()>>,
+    },
+    /// Append WinKeyer buffered bytes to the send buffer.
+    Buffered {
+        data: Vec<u8>,
+        reply: oneshot::Sender<Result<()>>,
+    },
+    /// Unkey, release PTT, and stop the task.
+    Shutdown {
+        reply: oneshot::Sender<Result<()>>,
+    },
+}
+
+/// Handle for communicating with the engine task.
+pub(crate) struct SoftHandle {
+    pub rt_tx: mpsc::Sender<Request>,
+    pub bg_tx: mpsc::Sender<Request>,
+    pub cancel: CancellationToken,
+    pub task: JoinHandle<()>,
+}
+
+impl SoftHandle {
+    /// Send an immediate control via the RT channel.
+    pub async fn control(&self, control: Control) -> Result<()> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.rt_tx
+            .send(Request::Control {
+                control,
+                reply: reply_tx,
+            })
+            .await
+            .map_err(|_| Error::NotConnected)?;
+        reply_rx.await.map_err(|_| Error::NotConnected)?
+    }
+
+    /// Queue buffered bytes via the BG channel.
+    pub async fn buffered(&self, data: Vec<u8>) -> Result<()> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.bg_tx
+            .send(Request::Buffered {
+                data,
+                reply: reply_tx,
+            })
+            .await
+            .map_err(|_| Error::NotConnected)?;
+        reply_rx.await.map_err(|_| Error::NotConnected)?
+    }
+
+    /// Request graceful shutdown of the engine task.
+    pub async fn shutdown(&self) -> Result<()> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        if self
+            .rt_tx
+            .send(Request::Shutdown { reply: reply_tx })
+            .await
+            .is_err()
+        {
+            self.cancel.cancel();
+            return Ok(());
+        }
+        match tokio::time::timeout(Duration::from_secs(2), reply_rx).await {
+            Ok(Ok(result)) => result,
+            _ => {
+                self.cancel.cancel();
+                Ok(())
+            }
+        }
+    }
+}
+
+/// One item decoded from the WinKeyer buffered byte stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BufferedItem {
+    /// Plain character (space = word gap).
+    Char(char),
+    /// Two letters merged into a prosign (0x1B).
+    Merge(char, char),
+    /// Buffered speed change (0x1C).
+    Speed(u8),
+    /// Cancel buffered speed change (0x1E).
+    CancelSpeed,
+    /// Buffered PTT on/off (0x18).
+    Ptt(bool),
+    /// Key down for N seconds (0x19).
+    KeyDown(u8),
+    /// Wait N seconds (0x1A).
+    Wait(u8),
+    /// Dit/dah ratio change (0x17).
+    Ratio(u8),
+    /// Anything else that occupies buffer space but produces no output.
+    Nop,
+}
+
+/// Pop the next complete item from a buffered byte stream.
+///
+/// Command lengths come from [`Command::decode`], so the engine skips
+/// exactly what a WinKeyer would. Returns `None` when the buffer is empty
+/// or ends in an incomplete multi-byte command (which stays in the buffer).
+pub(crate) fn next_buffered_item(buf: &mut VecDeque<u8>) -> Option<BufferedItem> {
+    let bytes = buf.make_contiguous();
+    let first = *bytes.first()?;
+    // One character at a time, so a text run isn't taken as a whole
+    if (0x20..=0x7E).contains(&first) {
+        buf.pop_front();
+        return Some(BufferedItem::Char(first as char));
+    }
+    let (cmd, len) = Command::decode(bytes)?;
+    let item = match cmd {
+        Command::Merge(c1, c2) => BufferedItem::Merge(c1 as char, c2 as char),
+        Command::BufferedSpeed(0) | Command::CancelBufferedSpeed => BufferedItem::CancelSpeed,
+        Command::BufferedSpeed(wpm) => BufferedItem::Speed(wpm),
+        Command::SetRatio(ratio) => BufferedItem::Ratio(ratio),
+        Command::BufferedPtt(on) => BufferedItem::Ptt(on),
+        Command::KeyBuffered(secs) => BufferedItem::KeyDown(secs),
+        Command::BufferedWait(secs) => BufferedItem::Wait(secs),
+        _ => BufferedItem::Nop,
+    };
+    buf.drain(..len);
+    Some(item)
+}
+
+/// What to do at the start of a step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Action {
+    Key(bool),
+    Ptt(bool),
+    Echo(char),
+    Hold,
+}
+
+/// Perform `action`, then hold for `hold` before the next step.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Step {
+    pub action: Action,
+    pub hold: Duration,
+}
+
+impl From<KeyStep> for Step {
+    fn from(s: KeyStep) -> Self {
+        Self {
+            action: Action::Key(s.key_down),
+            hold: s.duration,
+        }
+    }
+}
+
+/// Outcome of [`Planner::next`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Plan {
+    /// Nothing left to send.
+    Idle,
+    /// Steps for the next buffered item were queued.
+    Item,
+    /// The buffer ran dry; steps for the PTT tail were queued.
+    Tail,
+}
+
+/// Turns buffered items into timed steps.
+///
+/// Holds the state that the WinKeyer keeps alongside its buffer (buffered
+/// speed, PTT sequencing) but no clock, so it serves both the real-time
+/// engine and offline rendering.
+pub(crate) struct Planner {
+    pub timing: MorseTiming,
+    pub buffered_wpm: Option<u8>,
+    pub ptt_timing: PttTiming,
+    pub auto_ptt: bool,
+    pub manual_ptt: bool,
+}
+
+impl Planner {
+    pub fn new(timing: MorseTiming, ptt_timing: PttTiming) -> Self {
+        Self {
+            timing,
+            buffered_wpm: None,
+            ptt_timing,
+            auto_ptt: false,
+            manual_ptt: false,
+        }
+    }
+
+    /// Expand the next item in `buffer` onto `steps`.
+    ///
+    /// `ptt_on` is the current PTT line state, used to decide whether a
+    /// lead-in is needed.
+    pub fn next(
+        &mut self,
+        buffer: &mut VecDeque<u8>,
+        ptt_on: bool,
+        steps: &mut VecDeque<Step>,
+    ) -> Plan {
+        let Some(item) = next_buffered_item(buffer) else {
+            if !self.auto_ptt {
+                return Plan::Idle;
+            }
+            self.auto_ptt = false;
+            steps.push_back(Step {
+                action: Action::Hold,
+                hold: self.ptt_timing.tail,
+            });
+            if !self.manual_ptt {
+                steps.push_back(Step {
+                    action: Action::Ptt(false),
+                    hold: Duration::ZERO,
+                });
+            }
+            return Plan::Tail;
+        };
+
+        let timing = self.effective_timing();
+        match item {
+            BufferedItem::Char(ch) => {
+                if let Some(keying) = timing.character(ch) {
+                    self.begin_transmission(ptt_on, steps);
+                    steps.push_back(Step {
+                        action: Action::Echo(ch),
+                        hold: Duration::ZERO,
+                    });
+                    steps.extend(keying.into_iter().map(Step::from));
+                }
+            }
+            BufferedItem::Merge(c1, c2) => {
+                if let Some(keying) = timing.merged(c1, c2) {
+                    self.begin_transmission(ptt_on, steps);
+                    steps.extend(keying.into_iter().map(Step::from));
+                }
+            }
+            BufferedItem::Speed(wpm) => self.buffered_wpm = Some(wpm),
+            BufferedItem::CancelSpeed => self.buffered_wpm = None,
+            BufferedItem::Ratio(ratio) => self.timing.dit_dah_ratio = ratio,
+            BufferedItem::Ptt(on) => {
+                self.manual_ptt = on;
+                steps.push_back(Step {
+                    action: Action::Ptt(on || self.auto_ptt),
+                    hold: Duration::ZERO,
+                });
+            }
+            BufferedItem::KeyDown(secs) => {
+                self.begin_transmission(ptt_on, steps);
+                steps.push_back(Step {
+                    action: Action::Key(true),
+                    hold: Duration::from_secs(secs as u64),
+                });
+                steps.push_back(Step {
+                    action: Action::Key(false),
+                    hold: Duration::ZERO,
+                });
+            }
+            BufferedItem::Wait(secs) => steps.push_back(Step {
+                action: Action::Hold,
+                hold: Duration::from_secs(secs as u64),
+            }),
+            BufferedItem::Nop => {}
+        }
+        Plan::Item
+    }
+
+    /// Timing with any buffered speed change applied.
+    fn effective_timing(&self) -> MorseTiming {
+        match self.buffered_wpm {
+            Some(wpm) => MorseTiming { wpm, ..self.timing },
+            None => self.timing,
+        }
+    }
+
+    /// Queue PTT lead-in ahead of keying if PTT is not already up.
+    fn begin_transmission(&mut self, ptt_on: bool, steps: &mut VecDeque<Step>) {
+        if !self.ptt_timing.enabled {
+            return;
+        }
+        self.auto_ptt = true;
+        if !ptt_on {
+            steps.push_back(Step {
+                action: Action::Ptt(true),
+                hold: self.ptt_timing.lead_in,
+            });
+        }
+    }
+}
+
+/// Spawn the engine task that owns the line driver.
+pub(crate) fn spawn_engine<D: LineDriver>(
+    driver: D,
+    event_tx: EventSender,
+    timing: MorseTiming,
+    ptt: PttTiming,
+) -> SoftHandle {
+    let (rt_tx, rt_rx) = mpsc::channel::<Request>(32);
+    let (bg_tx, bg_rx) = mpsc::channel::<Request>(64);
+    let cancel = CancellationToken::new();
+
+    let engine = Engine {
+        driver,
+        event_tx,
+        planner: Planner::new(timing, ptt),
+        buffer: VecDeque::new(),
+        steps: VecDeque::new(),
+        deadline: None,
+        in_tail: false,
+        key: false,
+        ptt: false,
+        tune: false,
+        paused: false,
+        last_status: None,
+    };
+
+    let task = tokio::spawn(engine.run(rt_rx, bg_rx, cancel.clone()));
+
+    SoftHandle {
+        rt_tx,
+        bg_tx,
+        cancel,
+        task,
+    }
+}
+
+/// Engine state, owned by the task.
+struct Engine<D> {
+    driver: D,
+    event_tx: EventSender,
+    planner: Planner,
+    buffer: VecDeque<u8>,
+    steps: VecDeque<Step>,
+    /// When the current step's hold ends (`None` = idle).
+    deadline: Option<Instant>,
+    /// Pending steps are only the PTT tail and may be discarded.
+    in_tail: bool,
+    key: bool,
+    ptt: bool,
+    tune: bool,
+    paused: bool,
+    last_status: Option<KeyerStatus>,
+}
+
+impl<D: LineDriver> Engine<D> {
+    async fn run(
+        mut self,
+        mut rt_rx: mpsc::Receiver<Request>,
+        mut bg_rx: mpsc::Receiver<Request>,
+        cancel: CancellationToken,
+    ) {
+        debug!("soft keyer task started");
+
+        loop {
+            self.publish_status();
+            let deadline = self.deadline;
+
+            let result = tokio::select! {
+                biased;
+
+                _ = cancel.cancelled() => {
+                    debug!("soft keyer task cancelled");
+                    break;
+                }
+
+                req = rt_rx.recv() => match req {
+                    Some(req) => match self.handle_request(req) {
+                        Some(r) => r,
+                        None => return,
+                    },
+                    None => break,
+                },
+
+                req = bg_rx.recv() => match req {
+                    Some(req) => match self.handle_request(req) {
+                        Some(r) => r,
+                        None => return,
+                    },
+                    None => break,
+                },
+
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)),
+                    if deadline.is_some() =>
+                {
+                    self.advance()
+                }
+            };
+
+            if let Err(e) = result {
+                error!("line driver error: {e}");
+                let _ = self.event_tx.send(KeyerEvent::Disconnected);
+                break;
+            }
+        }
+
+        let _ = self.set_key(false);
+        let _ = self.set_ptt(false);
+        debug!("soft keyer task exiting");
+    }
+
+    /// Handle one request. Returns `None` when the task should exit.
+    fn handle_request(&mut self, req: Request) -> Option<std::io::Result<()>> {
+        match req {
+            Request::Control { control, reply } => {
+                let result = self.apply_control(control);
+                let _ = reply.send(
+                    result
+                        .as_ref()
+                        .map(|_| ())
+                        .map_err(|e| Error::Transport(e.to_string())),
+                );
+                Some(result)
+            }
+            Request::Buffered { data, reply } => {
+                trace!("buffering {} bytes: {:02X?}", data.len(), data);
+                self.buffer.extend(data);
+                let result = if self.in_tail {
+                    // New text during the PTT tail keeps PTT asserted.
+                    self.steps.clear();
+                    self.in_tail = false;
+                    self.deadline = None;
+                    self.advance()
+                } else if self.deadline.is_none() {
+                    self.advance()
+                } else {
+                    Ok(())
+                };
+                let _ = reply.send(Ok(()));
+                Some(result)
+            }
+            Request::Shutdown { reply } => {
+                debug!("soft keyer shutdown requested");
+                let _ = self.set_key(false);
+                let _ = self.set_ptt(false);
+                let _ = reply.send(Ok(()));
+                None
+            }
+        }
+    }
+
+    fn apply_control(&mut self, control: Control) -> std::io::Result<()> {
+        match control {
+            Control::Abort => {
+                self.buffer.clear();
+                self.steps.clear();
+                self.deadline = None;
+                self.in_tail = false;
+                self.planner.buffered_wpm = None;
+                self.tune = false;
+                self.planner.auto_ptt = false;
+                self.set_key(false)?;
+                if !self.planner.manual_ptt {
+                    self.set_ptt(false)?;
+                }
+                Ok(())
+            }
+            Control::Tune(on) => {
+                self.tune = on;
+                if on {
+                    self.deadline = None;
+                    self.set_key(true)
+                } else {
+                    self.set_key(false)?;
+                    self.advance()
+                }
+            }
+            Control::Pause(paused) => {
+                self.paused = paused;
+                if !paused && self.deadline.is_none() {
+                    self.advance()?;
+                }
+                Ok(())
+            }
+            Control::Timing(timing) => {
+                self.planner.timing = timing;
+                Ok(())
+            }
+        }
+    }
+
+    /// Finish the current step and start the next one(s).
+    ///
+    /// Zero-length steps run back to back; the loop stops at the first step
+    /// with a hold time or when nothing is left to send.
+    fn advance(&mut self) -> std::io::Result<()> {
+        if self.tune {
+            return Ok(());
+        }
+        let mut at = self.deadline.unwrap_or_else(Instant::now);
+        loop {
+            if let Some(step) = self.steps.pop_front() {
+                self.perform(step.action)?;
+                if !step.hold.is_zero() {
+                    at += step.hold;
+                    self.deadline = Some(at);
+                    return Ok(());
+                }
+                continue;
+            }
+            self.in_tail = false;
+            if !self.refill() {
+                self.deadline = None;
+                return Ok(());
+            }
+        }
+    }
+
+    /// Expand the next buffered item into steps. Returns false when idle.
+    fn refill(&mut self) -> bool {
+        if self.paused {
+            return false;
+        }
+        match self.planner.next(&mut self.buffer, self.ptt, &mut self.steps) {
+            Plan::Idle => false,
+            Plan::Item => true,
+            Plan::Tail => {
+                self.in_tail = true;
+                true
+            }
+        }
+    }
+
+    fn perform(&mut self, action: Action) -> std::io::Result<()> {
+        match action {
+            Action::Key(down) => self.set_key(down),
+            Action::Ptt(on) => self.set_ptt(on),
+            Action::Echo(ch) => {
+                self.driver.character(ch);
+                let _ = self.event_tx.send(KeyerEvent::CharacterSent {
+                    ch,
+                    source: EchoSource::Host,
+                });
+                Ok(())
+            }
+            Action::Hold => Ok(()),
+        }
+    }
+
+    fn set_key(&mut self, down: bool) -> std::io::Result<()> {
+        if self.key != down {
+            trace!("key {}", if down { "down" } else { "up" });
+            self.driver.set_key(down)?;
+            self.key = down;
+        }
+        Ok(())
+    }
+
+    fn set_ptt(&mut self, on: bool) -> std::io::Result<()> {
+        if self.ptt != on {
+            trace!("ptt {}", if on { "on" } else { "off" });
+            self.driver.set_ptt(on)?;
+            self.ptt = on;
+        }
+        Ok(())
+    }
+
+    /// Emit `StatusChanged` when busy/keydown changed since the last emit.
+    fn publish_status(&mut self) {
+        let status = KeyerStatus {
+            xoff: false,
+            breakin: false,
+            busy: !self.in_tail && (self.deadline.is_some() || !self.buffer.is_empty()),
+            keydown: self.key,
+            waiting: false,
+        };
+        if self.last_status != Some(status) {
+            self.last_status = Some(status);
+            let _ = self.event_tx.send(KeyerEvent::StatusChanged(status));
+        }
+    }
+}
+
+/// State shared by every keyer built on the software engine.
+///
+/// Backends wrap this and delegate their `Keyer` methods to it.
+pub(crate) struct SoftKeyer {
+    pub engine: SoftHandle,
+    pub event_tx: EventSender,
+    pub timing: Mutex<MorseTiming>,
+}
+
+impl SoftKeyer {
+    /// Start the engine with the given driver and settings.
+    pub fn spawn<D: LineDriver>(driver: D, timing: MorseTiming, ptt: PttTiming) -> Self {
+        let (event_tx, _) = crate::event::channel(256);
+        let _ = event_tx.send(KeyerEvent::Connected);
+        let engine = spawn_engine(driver, event_tx.clone(), timing, ptt);
+        Self {
+            engine,
+            event_tx,
+            timing: Mutex::new(timing),
+        }
+    }
+
+    /// Current base timing.
+    pub fn timing(&self) -> MorseTiming {
+        *self.timing.lock().unwrap()
+    }
+
+    /// Apply `update` to the base timing and push it to the engine.
+    pub async fn update_timing(&self, update: impl FnOnce(&mut MorseTiming)) -> Result<()> {
+        let mut timing = self.timing();
+        update(&mut timing);
+        self.engine.control(Control::Timing(timing)).await?;
+        *self.timing.lock().unwrap() = timing;
+        Ok(())
+    }
+
+    pub async fn send_message(&self, text: &str) -> Result<()> {
+        command::validate_cw_text(text).map_err(Error::InvalidParameter)?;
+        self.engine.buffered(command::encode_text(text)).await
+    }
+
+    pub async fn send_prosign(&self, c1: u8, c2: u8) -> Result<()> {
+        self.engine
+            .buffered(command::buffered_merge(c1, c2).to_vec())
+            .await
+    }
+
+    pub async fn set_speed(&self, wpm: u8) -> Result<()> {
+        if !(5..=99).contains(&wpm) {
+            return Err(Error::InvalidParameter(format!(
+                "speed must be 5-99 WPM, got {wpm}"
+            )));
+        }
+        self.update_timing(|t| t.wpm = wpm).await
+    }
+
+    pub async fn set_weight(&self, weight: u8) -> Result<()> {
+        if !(10..=90).contains(&weight) {
+            return Err(Error::InvalidParameter(format!(
+                "weight must be 10-90, got {weight}"
+            )));
+        }
+        self.update_timing(|t| t.weight = weight).await
+    }
+
+    pub async fn set_ratio(&self, ratio: u8) -> Result<()> {
+        if !(33..=66).contains(&ratio) {
+            return Err(Error::InvalidParameter(format!(
+                "ratio must be 33-66, got {ratio}"
+            )));
+        }
+        self.update_timing(|t| t.dit_dah_ratio = ratio).await
+    }
+
+    pub async fn set_farnsworth(&self, wpm: u8) -> Result<()> {
+        self.update_timing(|t| t.farnsworth_wpm = wpm).await
+    }
+
+    /// Manual PTT, queued in the buffer like WinKeyer's buffered PTT.
+    pub async fn set_ptt(&self, on: bool) -> Result<()> {
+        self.engine
+            .buffered(command::buffered_ptt(on).to_vec())
+            .await
+    }
+
+    pub async fn set_buffered_speed(&self, wpm: u8) -> Result<()> {
+        if !(5..=99).contains(&wpm) {
+            return Err(Error::InvalidParameter(format!(
+                "speed must be 5-99 WPM, got {wpm}"
+            )));
+        }
+        self.engine
+            .buffered(command::buffered_speed_change(wpm).to_vec())
+            .await
+    }
+
+    pub async fn cancel_buffered_speed(&self) -> Result<()> {
+        self.engine
+            .buffered(command::cancel_buffered_speed().to_vec())
+            .await
+    }
+
+    pub async fn set_pause(&self, paused: bool) -> Result<()> {
+        self.engine.control(Control::Pause(paused)).await
+    }
+
+    pub async fn close(&self) -> Result<()> {
+        self.engine.shutdown().await
+    }
+}
+
+impl Drop for SoftKeyer {
+    fn drop(&mut self) {
+        self.engine.cancel.cancel();
+        self.engine.task.abort();
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Which line a recorded edge was on.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(crate) enum Line {
+        Key,
+        Ptt,
+    }
+
+    /// Line driver that records timestamped edges.
+    #[derive(Clone, Default)]
+    pub(crate) struct FakeLines {
+        pub edges: Arc<std::sync::Mutex<Vec<(Instant, Line, bool)>>>,
+    }
+
+    impl LineDriver for FakeLines {
+        fn set_key(&mut self, down: bool) -> std::io::Result<()> {
+            self.edges.lock().unwrap().push((Instant::now(), Line::Key, down));
+            Ok(())
+        }
+
+        fn set_ptt(&mut self, on: bool) -> std::io::Result<()> {
+            self.edges.lock().unwrap().push((Instant::now(), Line::Ptt, on));
+            Ok(())
+        }
+    }
+
+    impl FakeLines {
+        /// Edges as (ms since `start`, line, state).
+        pub(crate) fn trace(&self, start: Instant) -> Vec<(u64, Line, bool)> {
+            self.edges
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(t, l, s)| ((*t - start).as_millis() as u64, *l, *s))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn parse_buffered_stream() {
+        let mut buf: VecDeque<u8> = VecDeque::new();
+        buf.extend(b"A ");
+        buf.extend(command::buffered_merge(b'S', b'K'));
+        buf.extend(command::buffered_speed_change(30));
+        buf.extend(command::cancel_buffered_speed());
+        buf.extend(command::buffered_ptt(true));
+        buf.extend(command::key_buffered(3));
+        buf.extend(command::buffered_wait(2));
+
+        let mut items = Vec::new();
+        while let Some(item) = next_buffered_item(&mut buf) {
+            items.push(item);
+        }
+        assert_eq!(
+            items,
+            vec![
+                BufferedItem::Char('A'),
+                BufferedItem::Char(' '),
+                BufferedItem::Merge('S', 'K'),
+                BufferedItem::Speed(30),
+                BufferedItem::CancelSpeed,
+                BufferedItem::Ptt(true),
+                BufferedItem::KeyDown(3),
+                BufferedItem::Wait(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn argument_bytes_are_not_keyed() {
+        let mut buf: VecDeque<u8> = VecDeque::new();
+        buf.extend(command::set_ratio(50));
+        buf.extend(command::pointer_cmd_with_data(0x01, &[0x45]));
+        buf.extend(command::pointer_cmd(0x00));
+        buf.extend(b"T");
+
+        let mut items = Vec::new();
+        while let Some(item) = next_buffered_item(&mut buf) {
+            items.push(item);
+        }
+        assert_eq!(
+            items,
+            vec![
+                BufferedItem::Ratio(50),
+                BufferedItem::Nop,
+                BufferedItem::Nop,
+                BufferedItem::Char('T'),
+            ]
+        );
+    }
+
+    #[test]
+    fn incomplete_command_stays_buffered() {
+        let mut buf: VecDeque<u8> = VecDeque::from(vec![0x1B, b'A']);
+        assert_eq!(next_buffered_item(&mut buf), None);
+        assert_eq!(buf.len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn keys_character_with_paris_timing() {
+        let lines = FakeLines::default();
+        let (event_tx, mut rx) = crate::event::channel(64);
+        let engine = spawn_engine(lines.clone(), event_tx, MorseTiming::default(), PttTiming::default());
+        let start = Instant::now();
+
+        engine.buffered(b"A".to_vec()).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        assert_eq!(
+            lines.trace(start),
+            vec![
+                (0, Line::Key, true),
+                (60, Line::Key, false),
+                (120, Line::Key, true),
+                (300, Line::Key, false),
+            ]
+        );
+
+        let mut echoed = Vec::new();
+        while let Ok(ev) = rx.try_recv() {
+            if let KeyerEvent::CharacterSent { ch, .. } = ev {
+                echoed.push(ch);
+            }
+        }
+        assert_eq!(echoed, vec!['A']);
+
+        engine.shutdown().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn abort_unkeys_and_clears_buffer() {
+        let lines = FakeLines::default();
+        let (event_tx, _rx) = crate::event::channel(64);
+        let engine = spawn_engine(lines.clone(), event_tx, MorseTiming::default(), PttTiming::default());
+        let start = Instant::now();
+
+        engine.buffered(b"TTTT".to_vec()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        engine.control(Control::Abort).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        // T = 180 ms dah; aborted 100 ms in
+        assert_eq!(
+            lines.trace(start),
+            vec![(0, Line::Key, true), (100, Line::Key, false)]
+        );
+
+        engine.shutdown().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ptt_lead_in_and_tail() {
+        let lines = FakeLines::default();
+        let (event_tx, _rx) = crate::event::channel(64);
+        let ptt = PttTiming {
+            enabled: true,
+            lead_in: Duration::from_millis(50),
+            tail: Duration::from_millis(30),
+        };
+        let engine = spawn_engine(lines.clone(), event_tx, MorseTiming::default(), ptt);
+        let start = Instant::now();
+
+        engine.buffered(b"E".to_vec()).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        // PTT, lead-in, dit, letter gap, tail, release
+        assert_eq!(
+            lines.trace(start),
+            vec![
+                (0, Line::Ptt, true),
+                (50, Line::Key, true),
+                (110, Line::Key, false),
+                (320, Line::Ptt, false),
+            ]
+        );
+
+        engine.shutdown().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn buffered_speed_change_applies_midstream() {
+        let lines = FakeLines::default();
+        let (event_tx, _rx) = crate::event::channel(64);
+        let engine = spawn_engine(lines.clone(), event_tx, MorseTiming::default(), PttTiming::default());
+        let start = Instant::now();
+
+        let mut data = b"E".to_vec();
+        data.extend(command::buffered_speed_change(40));
+        data.extend(b"E");
+        engine.buffered(data).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        // 20 WPM dit + gap = 240 ms, then a 30 ms dit at 40 WPM
+        assert_eq!(
+            lines.trace(start),
+            vec![
+                (0, Line::Key, true),
+                (60, Line::Key, false),
+                (240, Line::Key, true),
+                (270, Line::Key, false),
+            ]
+        );
+
+        engine.shutdown().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn tune_holds_key_until_released() {
+        let lines = FakeLines::default();
+        let (event_tx, _rx) = crate::event::channel(64);
+        let engine = spawn_engine(lines.clone(), event_tx, MorseTiming::default(), PttTiming::default());
+        let start = Instant::now();
+
+        engine.control(Control::Tune(true)).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        engine.control(Control::Tune(false)).await.unwrap();
+
+        assert_eq!(
+            lines.trace(start),
+            vec![(0, Line::Key, true), (3000, Line::Key, false)]
+        );
+
+        engine.shutdown().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn status_reports_busy_and_keydown() {
+        let lines = FakeLines::default();
+        let (event_tx, mut rx) = crate::event::channel(64);
+        let engine = spawn_engine(lines, event_tx, MorseTiming::default(), PttTiming::default());
+
+        engine.buffered(b"E".to_vec()).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let mut statuses = Vec::new();
+        while let Ok(ev) = rx.try_recv() {
+            if let KeyerEvent::StatusChanged(s) = ev {
+                statuses.push((s.busy, s.keydown));
+            }
+        }
+        assert_eq!(
+            statuses,
+            vec![(false, false), (true, true), (true, false), (false, false)]
+        );
+
+        engine.shutdown().await.unwrap();
+    }
+}
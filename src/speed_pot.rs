@@ -0,0 +1,266 @@
+//! Speed pot follow policy and calibration.
+//!
+//! WinKeyer reports pot moves as [`KeyerEvent::SpeedPotChanged`] but keeps
+//! sending at the host-set speed. A [`SpeedPotPolicy`] decides whether a
+//! pot move should change that speed, so [`Keyer::get_speed`] and
+//! [`WinKeyer::settings`] stay truthful:
+//!
+//! - [`SpeedPotMode::Ignore`]: pot moves are only reported (the default).
+//! - [`SpeedPotMode::Direct`]: the pot reading becomes the speed.
+//! - [`SpeedPotMode::Offset`]: the pot nudges a base speed up or down from
+//!   the middle of its range.
+//!
+//! A hysteresis band drops readings within a few WPM of the last one acted
+//! on, so a pot sitting between two values doesn't make the speed flicker.
+//!
+//! [`Keyer::get_speed`]: crate::Keyer::get_speed
+//! [`WinKeyer::settings`]: crate::WinKeyer::settings
+
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::event::{EventReceiver, KeyerEvent};
+use crate::io::{write_via, Request};
+use crate::protocol::command;
+use crate::settings::KeyerSettings;
+
+/// What a speed pot move does to the sending speed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpeedPotMode {
+    /// Report pot moves only.
+    #[default]
+    Ignore,
+    /// The pot reading is the speed.
+    Direct,
+    /// Speed is `base_wpm` plus the pot's distance from the middle of its
+    /// range.
+    Offset { base_wpm: u8 },
+}
+
+/// Speed pot mode plus jitter suppression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SpeedPotPolicy {
+    pub mode: SpeedPotMode,
+    /// Readings within this many WPM of the last one acted on are dropped.
+    /// 1 is usually enough for a pot resting between two steps.
+    pub hysteresis_wpm: u8,
+}
+
+impl SpeedPotPolicy {
+    /// Report pot moves without changing the speed.
+    pub fn ignore() -> Self {
+        Self::default()
+    }
+
+    /// Let the pot set the speed.
+    pub fn direct() -> Self {
+        Self {
+            mode: SpeedPotMode::Direct,
+            ..Self::default()
+        }
+    }
+
+    /// Let the pot move the speed around `base_wpm`.
+    pub fn offset(base_wpm: u8) -> Self {
+        Self {
+            mode: SpeedPotMode::Offset { base_wpm },
+            ..Self::default()
+        }
+    }
+
+    /// Set the hysteresis band in WPM.
+    pub fn hysteresis(mut self, wpm: u8) -> Self {
+        self.hysteresis_wpm = wpm;
+        self
+    }
+
+    /// Speed for a pot reading of `pot_wpm` on a pot spanning
+    /// `min_wpm..=min_wpm + wpm_range`, or `None` in [`SpeedPotMode::Ignore`].
+    pub fn target_speed(&self, pot_wpm: u8, min_wpm: u8, wpm_range: u8) -> Option<u8> {
+        match self.mode {
+            SpeedPotMode::Ignore => None,
+            SpeedPotMode::Direct => Some(pot_wpm.clamp(5, 99)),
+            SpeedPotMode::Offset { base_wpm } => {
+                let centre = min_wpm as i16 + wpm_range as i16 / 2;
+                let wpm = base_wpm as i16 + pot_wpm as i16 - centre;
+                Some(wpm.clamp(5, 99) as u8)
+            }
+        }
+    }
+}
+
+/// One speed pot calibration sample from
+/// [`WinKeyer::calibrate_speed_pot`](crate::WinKeyer::calibrate_speed_pot).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpeedPotCalibration {
+    /// Raw A/D reading of the pot (0-255). Firmware without the Speed A2D
+    /// admin command returns 0.
+    pub raw: u8,
+    /// Speed the keyer reports for the pot, in WPM.
+    pub wpm: u8,
+    /// Pot range the keyer was using.
+    pub min_wpm: u8,
+    pub wpm_range: u8,
+}
+
+impl SpeedPotCalibration {
+    /// Pot position from the raw reading, 0.0 (fully counter-clockwise) to 1.0.
+    pub fn position(&self) -> f32 {
+        self.raw as f32 / 255.0
+    }
+
+    /// Where the reported speed sits in the range, 0.0 to 1.0.
+    pub fn range_position(&self) -> f32 {
+        let offset = self.wpm.saturating_sub(self.min_wpm).min(self.wpm_range);
+        offset as f32 / self.wpm_range.max(1) as f32
+    }
+
+    /// The reported speed is at one end of the range, so the pot can't
+    /// reach some of it (or the range is wider than the pot's travel).
+    pub fn at_limit(&self) -> bool {
+        self.wpm <= self.min_wpm || self.wpm >= self.min_wpm.saturating_add(self.wpm_range)
+    }
+}
+
+/// Policy plus the reading it last acted on.
+#[derive(Debug, Default)]
+pub(crate) struct PotFollower {
+    pub policy: SpeedPotPolicy,
+    last: Option<u8>,
+}
+
+impl PotFollower {
+    pub fn new(policy: SpeedPotPolicy) -> Self {
+        Self { policy, last: None }
+    }
+
+    /// Switch policy; the next reading is always acted on.
+    pub fn set_policy(&mut self, policy: SpeedPotPolicy) {
+        self.policy = policy;
+        self.last = None;
+    }
+
+    /// Speed to set for a new reading, after hysteresis.
+    pub fn on_reading(&mut self, pot_wpm: u8, settings: &KeyerSettings) -> Option<u8> {
+        if self
+            .last
+            .is_some_and(|last| last.abs_diff(pot_wpm) <= self.policy.hysteresis_wpm)
+        {
+            return None;
+        }
+        let target = self
+            .policy
+            .target_speed(pot_wpm, settings.min_wpm, settings.wpm_range)?;
+        self.last = Some(pot_wpm);
+        Some(target)
+    }
+}
+
+/// Follow speed pot events into the settings cache, changing the speed as
+/// the policy says.
+pub(crate) fn spawn_follower(
+    mut events: EventReceiver,
+    settings: watch::Sender<KeyerSettings>,
+    follower: Arc<Mutex<PotFollower>>,
+    rt_tx: mpsc::Sender<Request>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let wpm = match events.recv().await {
+                Ok(KeyerEvent::SpeedPotChanged { wpm }) => wpm,
+                Ok(_) => continue,
+                Err(_) => break,
+            };
+            settings.send_if_modified(|s| {
+                let changed = s.speed_pot_wpm != Some(wpm);
+                s.speed_pot_wpm = Some(wpm);
+                changed
+            });
+
+            let target = follower.lock().unwrap().on_reading(wpm, &settings.borrow());
+            let Some(target) = target else { continue };
+            if target == settings.borrow().speed_wpm {
+                continue;
+            }
+            debug!("speed pot {wpm} WPM -> speed {target} WPM");
+            match write_via(&rt_tx, command::set_speed(target).to_vec()).await {
+                Ok(()) => {
+                    settings.send_modify(|s| s.speed_wpm = target);
+                }
+                Err(e) => warn!("speed pot follow failed: {e}"),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::types::{LoadDefaults, WinKeyerVersion};
+
+    fn settings(min_wpm: u8, wpm_range: u8) -> KeyerSettings {
+        KeyerSettings {
+            min_wpm,
+            wpm_range,
+            ..KeyerSettings::from_load_defaults(&LoadDefaults::default(), WinKeyerVersion::Wk3)
+        }
+    }
+
+    #[test]
+    fn target_speed_per_mode() {
+        assert_eq!(SpeedPotPolicy::ignore().target_speed(25, 10, 25), None);
+        assert_eq!(SpeedPotPolicy::direct().target_speed(25, 10, 25), Some(25));
+        // Centre of 10..=30 is 20: pot at 23 is +3
+        assert_eq!(SpeedPotPolicy::offset(28).target_speed(23, 10, 20), Some(31));
+        assert_eq!(SpeedPotPolicy::offset(28).target_speed(12, 10, 20), Some(20));
+        assert_eq!(SpeedPotPolicy::offset(6).target_speed(10, 10, 20), Some(5));
+    }
+
+    #[test]
+    fn hysteresis_drops_jitter() {
+        let s = settings(10, 25);
+        let mut follower = PotFollower::new(SpeedPotPolicy::direct().hysteresis(1));
+        assert_eq!(follower.on_reading(20, &s), Some(20));
+        assert_eq!(follower.on_reading(21, &s), None);
+        assert_eq!(follower.on_reading(19, &s), None);
+        assert_eq!(follower.on_reading(22, &s), Some(22));
+        // Measured from the last reading acted on, not the last seen
+        assert_eq!(follower.on_reading(21, &s), None);
+
+        follower.set_policy(SpeedPotPolicy::offset(25));
+        assert_eq!(follower.on_reading(21, &s), Some(24));
+    }
+
+    #[test]
+    fn calibration_positions() {
+        let cal = SpeedPotCalibration {
+            raw: 51,
+            wpm: 15,
+            min_wpm: 10,
+            wpm_range: 25,
+        };
+        assert!((cal.position() - 0.2).abs() < 1e-6);
+        assert!((cal.range_position() - 0.2).abs() < 1e-6);
+        assert!(!cal.at_limit());
+        let top = SpeedPotCalibration { wpm: 35, ..cal };
+        assert_eq!(top.range_position(), 1.0);
+        assert!(top.at_limit());
+    }
+
+    #[tokio::test]
+    async fn follower_updates_pot_reading() {
+        let (event_tx, _) = crate::event::channel(8);
+        let (settings, mut rx) = watch::channel(settings(10, 25));
+        let (rt_tx, _rt_rx) = mpsc::channel(8);
+        let follower = Arc::new(Mutex::new(PotFollower::default()));
+        let task = spawn_follower(event_tx.subscribe(), settings, follower, rt_tx);
+        event_tx.send(KeyerEvent::SpeedPotChanged { wpm: 27 }).unwrap();
+        rx.changed().await.unwrap();
+        assert_eq!(rx.borrow().speed_pot_wpm, Some(27));
+        assert_eq!(rx.borrow().speed_wpm, 20);
+        task.abort();
+    }
+}
@@ -0,0 +1,275 @@
+//! [`Stream`] adapters for keyer events.
+//!
+//! [`Keyer::events`](crate::Keyer::events) returns an [`EventStream`]: the
+//! keyer's events as a [`Stream`], ending when the keyer closes. Lag shows
+//! up in the stream as [`KeyerEvent::EventsDropped`], so there is no error
+//! case to handle. Narrower streams pick out one kind of event:
+//!
+//! ```no_run
+//! # async fn example(keyer: &dyn winkey::Keyer) -> winkey::Result<()> {
+//! let mut pot = keyer.events().speed_pot();
+//! while let Some(wpm) = pot.recv().await {
+//!     println!("pot at {wpm} WPM");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! `recv` is a shortcut for `StreamExt::next` that needs no extra imports;
+//! the streams work with any `futures` combinator as well.
+
+use std::future::poll_fn;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use std::time::Duration;
+
+use futures_core::Stream;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::ReusableBoxFuture;
+
+use crate::error::{Error, Result};
+use crate::event::{EchoSource, EventReceiver, KeyerEvent, KeyerStatus};
+
+/// How long to wait for the keyer to report busy before assuming a message
+/// already finished (backends without status reporting).
+const BUSY_START_TIMEOUT: Duration = Duration::from_secs(1);
+
+type Received = (std::result::Result<KeyerEvent, RecvError>, EventReceiver);
+
+async fn receive(mut rx: EventReceiver) -> Received {
+    let result = rx.recv().await;
+    (result, rx)
+}
+
+/// A keyer's events as a [`Stream`].
+pub struct EventStream {
+    next: ReusableBoxFuture<'static, Received>,
+    closed: bool,
+}
+
+impl std::fmt::Debug for EventStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventStream")
+            .field("closed", &self.closed)
+            .finish()
+    }
+}
+
+impl EventStream {
+    /// Stream the events from `rx`.
+    pub fn new(rx: EventReceiver) -> Self {
+        Self {
+            next: ReusableBoxFuture::new(receive(rx)),
+            closed: false,
+        }
+    }
+
+    /// Next event, or `None` once the keyer has closed.
+    pub async fn recv(&mut self) -> Option<KeyerEvent> {
+        poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+
+    /// Only the events `f` maps to `Some`.
+    pub fn filter_map<T>(self, f: fn(KeyerEvent) -> Option<T>) -> FilteredEvents<T> {
+        FilteredEvents { events: self, f }
+    }
+
+    /// Echoed characters, from the host buffer and the paddles.
+    pub fn echo(self) -> FilteredEvents<(char, EchoSource)> {
+        self.filter_map(|event| match event {
+            KeyerEvent::CharacterSent { ch, source } => Some((ch, source)),
+            _ => None,
+        })
+    }
+
+    /// Words sent on the paddles.
+    pub fn paddle_words(self) -> FilteredEvents<String> {
+        self.filter_map(|event| match event {
+            KeyerEvent::PaddleWord(word) => Some(word),
+            _ => None,
+        })
+    }
+
+    /// Status changes.
+    pub fn status(self) -> FilteredEvents<KeyerStatus> {
+        self.filter_map(|event| match event {
+            KeyerEvent::StatusChanged(status) => Some(status),
+            _ => None,
+        })
+    }
+
+    /// Speed pot readings in WPM.
+    pub fn speed_pot(self) -> FilteredEvents<u8> {
+        self.filter_map(|event| match event {
+            KeyerEvent::SpeedPotChanged { wpm } => Some(wpm),
+            _ => None,
+        })
+    }
+
+    /// Collect host text echoed until the keyer goes busy and then idle
+    /// again, i.e. until what was queued has been sent.
+    ///
+    /// Call it before queueing the text, or the start may be missed. A
+    /// keyer that doesn't report busy within a second is taken to be done
+    /// already. Fails if the keyer disconnects first.
+    pub async fn host_text_until_idle(&mut self) -> Result<String> {
+        let mut echoed = String::new();
+        let mut started = false;
+        loop {
+            let event = if started {
+                self.recv().await
+            } else {
+                match tokio::time::timeout(BUSY_START_TIMEOUT, self.recv()).await {
+                    Ok(event) => event,
+                    Err(_) => return Ok(echoed),
+                }
+            };
+            match event {
+                Some(KeyerEvent::CharacterSent {
+                    ch,
+                    source: EchoSource::Host,
+                }) => {
+                    started = true;
+                    echoed.push(ch);
+                }
+                Some(KeyerEvent::StatusChanged(status)) => {
+                    if status.busy {
+                        started = true;
+                    } else if started {
+                        return Ok(echoed);
+                    }
+                }
+                Some(KeyerEvent::Disconnected) => return Err(Error::ConnectionLost),
+                Some(_) => {}
+                None => return Err(Error::NotConnected),
+            }
+        }
+    }
+}
+
+impl Stream for EventStream {
+    type Item = KeyerEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<KeyerEvent>> {
+        if self.closed {
+            return Poll::Ready(None);
+        }
+        let (result, rx) = ready!(self.next.poll(cx));
+        match result {
+            Ok(event) => {
+                self.next.set(receive(rx));
+                Poll::Ready(Some(event))
+            }
+            Err(_) => {
+                self.closed = true;
+                Poll::Ready(None)
+            }
+        }
+    }
+}
+
+/// Events of one kind, from [`EventStream::filter_map`] and friends.
+pub struct FilteredEvents<T> {
+    events: EventStream,
+    f: fn(KeyerEvent) -> Option<T>,
+}
+
+impl<T> std::fmt::Debug for FilteredEvents<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FilteredEvents")
+            .field("events", &self.events)
+            .finish()
+    }
+}
+
+impl<T> FilteredEvents<T> {
+    /// Next matching event, or `None` once the keyer has closed.
+    pub async fn recv(&mut self) -> Option<T> {
+        poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+}
+
+impl<T> Stream for FilteredEvents<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let f = self.f;
+        loop {
+            match ready!(Pin::new(&mut self.events).poll_next(cx)) {
+                Some(event) => {
+                    if let Some(item) = f(event) {
+                        return Poll::Ready(Some(item));
+                    }
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::channel;
+
+    fn status(busy: bool) -> KeyerEvent {
+        KeyerEvent::StatusChanged(KeyerStatus::from_status_byte(if busy { 0xC4 } else { 0xC0 }))
+    }
+
+    #[tokio::test]
+    async fn stream_ends_when_sender_drops() {
+        let (tx, rx) = channel(8);
+        let mut events = EventStream::new(rx);
+        tx.send(KeyerEvent::Connected).unwrap();
+        drop(tx);
+        assert!(matches!(events.recv().await, Some(KeyerEvent::Connected)));
+        assert!(events.recv().await.is_none());
+        assert!(events.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn filtered_streams() {
+        let (tx, rx) = channel(16);
+        let mut pot = EventStream::new(rx.resubscribe()).speed_pot();
+        let mut echo = EventStream::new(rx).echo();
+        tx.send(KeyerEvent::SpeedPotChanged { wpm: 22 }).unwrap();
+        tx.send(status(true)).unwrap();
+        tx.send(KeyerEvent::CharacterSent {
+            ch: 'K',
+            source: EchoSource::Paddle,
+        })
+        .unwrap();
+        drop(tx);
+        assert_eq!(pot.recv().await, Some(22));
+        assert_eq!(pot.recv().await, None);
+        assert_eq!(echo.recv().await, Some(('K', EchoSource::Paddle)));
+        assert_eq!(echo.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn host_text_until_idle() {
+        let (tx, rx) = channel(16);
+        let mut events = EventStream::new(rx);
+        tx.send(status(true)).unwrap();
+        for (ch, source) in [('C', EchoSource::Host), ('X', EchoSource::Paddle), ('Q', EchoSource::Host)] {
+            tx.send(KeyerEvent::CharacterSent { ch, source }).unwrap();
+        }
+        tx.send(status(false)).unwrap();
+        tx.send(KeyerEvent::CharacterSent {
+            ch: 'Z',
+            source: EchoSource::Host,
+        })
+        .unwrap();
+        assert_eq!(events.host_text_until_idle().await.unwrap(), "CQ");
+
+        tx.send(KeyerEvent::Disconnected).unwrap();
+        assert!(matches!(events.host_text_until_idle().await, Err(Error::ConnectionLost)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_keyer_does_not_hang() {
+        let (_tx, rx) = channel(16);
+        let mut events = EventStream::new(rx);
+        assert_eq!(events.host_text_until_idle().await.unwrap(), "");
+    }
+}
@@ -0,0 +1,534 @@
+//! Keyer multiplexer for SO2R (single operator, two radios) operation.
+//!
+//! [`KeyerSwitch`] holds one keyer per radio and implements [`Keyer`] by
+//! routing to the focused radio, so existing logger code keeps working.
+//! On top of that it can send on a specific (or the other) radio, queue
+//! messages that play one after another across radios, abort whichever
+//! radio is actually transmitting, and merge event streams tagged with the
+//! radio index.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use tokio::sync::{broadcast, Notify};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::error::{Error, Result};
+use crate::event::{EventReceiver, EventSender, KeyerEvent};
+use crate::keyer::{Keyer, KeyerCapabilities, KeyerInfo};
+
+/// A keyer event tagged with the radio it came from.
+#[derive(Debug, Clone)]
+pub struct RadioEvent {
+    pub radio: usize,
+    pub event: KeyerEvent,
+}
+
+/// State shared with the forwarding and queue tasks.
+struct Shared {
+    keyers: Vec<Arc<dyn Keyer>>,
+    busy: Vec<AtomicBool>,
+    /// Radio most recently sent to, for abort when no status is reported.
+    last_tx: AtomicUsize,
+    queue: Mutex<VecDeque<(usize, String)>>,
+    queue_notify: Notify,
+    abort_notify: Notify,
+    /// Bumped by every abort, so the queue can tell one happened while it
+    /// was handing a message to a keyer.
+    abort_generation: AtomicU64,
+    radio_tx: broadcast::Sender<RadioEvent>,
+}
+
+impl Shared {
+    /// Abort every radio that is sending (or was last sent to) and drop
+    /// queued messages. `except` skips one radio, e.g. the one whose paddle
+    /// caused the break-in.
+    async fn abort_transmitting(&self, except: Option<usize>) -> Result<()> {
+        self.queue.lock().unwrap().clear();
+        self.abort_generation.fetch_add(1, Ordering::AcqRel);
+        self.abort_notify.notify_waiters();
+
+        let last = self.last_tx.load(Ordering::Acquire);
+        let mut result = Ok(());
+        for (radio, keyer) in self.keyers.iter().enumerate() {
+            if Some(radio) == except {
+                continue;
+            }
+            let sending = self.busy[radio].load(Ordering::Acquire);
+            if sending || (radio == last && except.is_none()) {
+                debug!(radio, "aborting");
+                if let Err(e) = keyer.abort().await {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Routes one logical keyer across several radios.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use winkey::{Keyer, WinKeyerBuilder, switch::KeyerSwitch};
+/// # async fn example() -> winkey::Result<()> {
+/// let left = WinKeyerBuilder::new("/dev/ttyUSB0").build().await?;
+/// let right = WinKeyerBuilder::new("/dev/ttyUSB1").build().await?;
+/// let so2r = KeyerSwitch::new(vec![Arc::new(left), Arc::new(right)])?;
+///
+/// so2r.send_message("CQ TEST K1EL").await?;   // focused radio (0)
+/// so2r.send_on_other("K1EL 5NN 05").await?;   // radio 1
+/// # Ok(())
+/// # }
+/// ```
+pub struct KeyerSwitch {
+    shared: Arc<Shared>,
+    focus: AtomicUsize,
+    event_tx: EventSender,
+    tasks: Vec<JoinHandle<()>>,
+    info: KeyerInfo,
+    capabilities: KeyerCapabilities,
+}
+
+impl std::fmt::Debug for KeyerSwitch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyerSwitch")
+            .field("radios", &self.shared.keyers.len())
+            .field("focus", &self.focus())
+            .finish()
+    }
+}
+
+impl KeyerSwitch {
+    /// Create a switch over `keyers` (radio index = position). Focus starts
+    /// on radio 0. Must be called inside a tokio runtime.
+    pub fn new(keyers: Vec<Arc<dyn Keyer>>) -> Result<Self> {
+        if keyers.is_empty() {
+            return Err(Error::InvalidParameter(
+                "KeyerSwitch needs at least one keyer".to_string(),
+            ));
+        }
+
+        let (radio_tx, _) = broadcast::channel::<RadioEvent>(256);
+        let (event_tx, _) = crate::event::channel(256);
+
+        let capabilities = keyers
+            .iter()
+            .map(|k| k.capabilities().clone())
+            .reduce(|a, b| KeyerCapabilities {
+                speed_pot: a.speed_pot && b.speed_pot,
+                sidetone: a.sidetone && b.sidetone,
+                ptt_control: a.ptt_control && b.ptt_control,
+                paddle_echo: a.paddle_echo && b.paddle_echo,
+                prosigns: a.prosigns && b.prosigns,
+                buffered_speed: a.buffered_speed && b.buffered_speed,
+                farnsworth: a.farnsworth && b.farnsworth,
+                contest_spacing: a.contest_spacing && b.contest_spacing,
+                pause: a.pause && b.pause,
+                weight: a.weight && b.weight,
+                key_state: a.key_state && b.key_state,
+            })
+            .unwrap_or_default();
+
+        let shared = Arc::new(Shared {
+            busy: keyers.iter().map(|_| AtomicBool::new(false)).collect(),
+            keyers,
+            last_tx: AtomicUsize::new(0),
+            queue: Mutex::new(VecDeque::new()),
+            queue_notify: Notify::new(),
+            abort_notify: Notify::new(),
+            abort_generation: AtomicU64::new(0),
+            radio_tx,
+        });
+
+        let mut tasks = Vec::new();
+        for (radio, keyer) in shared.keyers.iter().enumerate() {
+            let rx = keyer.subscribe();
+            tasks.push(tokio::spawn(forward_events(
+                radio,
+                rx,
+                shared.clone(),
+                event_tx.clone(),
+            )));
+        }
+        tasks.push(tokio::spawn(run_queue(shared.clone())));
+
+        let info = KeyerInfo {
+            name: format!("KeyerSwitch ({} radios)", shared.keyers.len()),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            port: None,
+        };
+
+        Ok(Self {
+            shared,
+            focus: AtomicUsize::new(0),
+            event_tx,
+            tasks,
+            info,
+            capabilities,
+        })
+    }
+
+    /// Number of radios.
+    pub fn radio_count(&self) -> usize {
+        self.shared.keyers.len()
+    }
+
+    /// The keyer for `radio`.
+    pub fn keyer(&self, radio: usize) -> Option<&Arc<dyn Keyer>> {
+        self.shared.keyers.get(radio)
+    }
+
+    /// Currently focused radio.
+    pub fn focus(&self) -> usize {
+        self.focus.load(Ordering::Acquire)
+    }
+
+    /// Move focus to `radio`.
+    pub fn set_focus(&self, radio: usize) -> Result<()> {
+        self.check_radio(radio)?;
+        self.focus.store(radio, Ordering::Release);
+        Ok(())
+    }
+
+    /// The radio after the focused one (the other radio in a pair).
+    pub fn other(&self) -> usize {
+        (self.focus() + 1) % self.radio_count()
+    }
+
+    /// Move focus to [`other`](Self::other) and return the new focus.
+    pub fn swap_focus(&self) -> usize {
+        let next = self.other();
+        self.focus.store(next, Ordering::Release);
+        next
+    }
+
+    /// Send a message on a specific radio, regardless of focus.
+    pub async fn send_on(&self, radio: usize, text: &str) -> Result<()> {
+        self.check_radio(radio)?;
+        self.shared.last_tx.store(radio, Ordering::Release);
+        self.shared.keyers[radio].send_message(text).await
+    }
+
+    /// Send a message on the non-focused radio without moving focus.
+    pub async fn send_on_other(&self, text: &str) -> Result<()> {
+        self.send_on(self.other(), text).await
+    }
+
+    /// Queue a message for `radio` behind any other queued messages.
+    ///
+    /// Queued messages play strictly one at a time: the next starts only
+    /// after the previous radio reports idle, so CQs and exchanges on two
+    /// radios interleave without overlapping. [`abort`](Keyer::abort)
+    /// drops the queue.
+    pub fn queue(&self, radio: usize, text: &str) -> Result<()> {
+        self.check_radio(radio)?;
+        crate::protocol::command::validate_cw_text(text).map_err(Error::InvalidParameter)?;
+        self.shared
+            .queue
+            .lock()
+            .unwrap()
+            .push_back((radio, text.to_string()));
+        self.shared.queue_notify.notify_one();
+        Ok(())
+    }
+
+    /// Number of messages waiting in the interleave queue.
+    pub fn queued(&self) -> usize {
+        self.shared.queue.lock().unwrap().len()
+    }
+
+    /// Subscribe to events from all radios, tagged with the radio index.
+    pub fn subscribe_radios(&self) -> broadcast::Receiver<RadioEvent> {
+        self.shared.radio_tx.subscribe()
+    }
+
+    fn check_radio(&self, radio: usize) -> Result<()> {
+        if radio >= self.radio_count() {
+            return Err(Error::InvalidParameter(format!(
+                "radio must be 0-{}, got {radio}",
+                self.radio_count() - 1
+            )));
+        }
+        Ok(())
+    }
+
+    fn focused(&self) -> &Arc<dyn Keyer> {
+        &self.shared.keyers[self.focus()]
+    }
+}
+
+/// Forward one keyer's events to the merged streams and track busy state.
+///
+/// A paddle break-in on one radio aborts whatever the other radios are
+/// sending, since the operator has taken over.
+async fn forward_events(
+    radio: usize,
+    mut rx: EventReceiver,
+    shared: Arc<Shared>,
+    event_tx: EventSender,
+) {
+    loop {
+        let Ok(timed) = rx.recv_timed().await else {
+            return;
+        };
+
+        match &timed.event {
+            KeyerEvent::StatusChanged(status) => {
+                shared.busy[radio].store(status.busy, Ordering::Release);
+            }
+            KeyerEvent::PaddleBreakIn => {
+                debug!(radio, "paddle break-in");
+                let _ = shared.abort_transmitting(Some(radio)).await;
+            }
+            KeyerEvent::EventsDropped { count } => {
+                warn!(radio, "event forwarder lagged by {count}");
+            }
+            _ => {}
+        }
+
+        let _ = shared.radio_tx.send(RadioEvent {
+            radio,
+            event: timed.event.clone(),
+        });
+        // Keep the radio's timestamp; the merged stream has its own numbering
+        let _ = event_tx.forward(timed);
+    }
+}
+
+/// Play queued messages one at a time.
+async fn run_queue(shared: Arc<Shared>) {
+    loop {
+        // Registered before the message is taken, so an abort at any point
+        // from here on ends the wait below
+        let aborted = shared.abort_notify.notified();
+        tokio::pin!(aborted);
+        aborted.as_mut().enable();
+        let generation = shared.abort_generation.load(Ordering::Acquire);
+
+        let next = shared.queue.lock().unwrap().pop_front();
+        let Some((radio, text)) = next else {
+            shared.queue_notify.notified().await;
+            continue;
+        };
+
+        let keyer = &shared.keyers[radio];
+        let mut events = keyer.events();
+        shared.last_tx.store(radio, Ordering::Release);
+        if let Err(e) = keyer.send_message(&text).await {
+            warn!(radio, "queued message failed: {e}");
+            continue;
+        }
+        if shared.abort_generation.load(Ordering::Acquire) != generation {
+            // The abort may have reached the keyer before this message did
+            let _ = keyer.abort().await;
+            continue;
+        }
+
+        tokio::select! {
+            _ = aborted => {}
+            _ = events.host_text_until_idle() => {}
+        }
+    }
+}
+
+#[async_trait]
+impl Keyer for KeyerSwitch {
+    fn info(&self) -> &KeyerInfo {
+        &self.info
+    }
+
+    /// Capabilities common to every radio's keyer.
+    fn capabilities(&self) -> &KeyerCapabilities {
+        &self.capabilities
+    }
+
+    async fn send_message(&self, text: &str) -> Result<()> {
+        self.send_on(self.focus(), text).await
+    }
+
+    /// Abort the radio(s) actually transmitting and clear the queue.
+    async fn abort(&self) -> Result<()> {
+        self.shared.abort_transmitting(None).await
+    }
+
+    async fn set_speed(&self, wpm: u8) -> Result<()> {
+        self.focused().set_speed(wpm).await
+    }
+
+    async fn get_speed(&self) -> Result<u8> {
+        self.focused().get_speed().await
+    }
+
+    async fn set_tune(&self, on: bool) -> Result<()> {
+        self.focused().set_tune(on).await
+    }
+
+    async fn set_ptt(&self, on: bool) -> Result<()> {
+        self.focused().set_ptt(on).await
+    }
+
+    /// Events from all radios, untagged. Use
+    /// [`subscribe_radios`](KeyerSwitch::subscribe_radios) for the radio index.
+    fn subscribe(&self) -> EventReceiver {
+        self.event_tx.subscribe()
+    }
+
+    async fn close(&self) -> Result<()> {
+        for task in &self.tasks {
+            task.abort();
+        }
+        let mut result = Ok(());
+        for keyer in &self.shared.keyers {
+            if let Err(e) = keyer.close().await {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    async fn send_prosign(&self, c1: u8, c2: u8) -> Result<()> {
+        self.focused().send_prosign(c1, c2).await
+    }
+
+    async fn set_buffered_speed(&self, wpm: u8) -> Result<()> {
+        self.focused().set_buffered_speed(wpm).await
+    }
+
+    async fn cancel_buffered_speed(&self) -> Result<()> {
+        self.focused().cancel_buffered_speed().await
+    }
+
+    async fn set_pause(&self, paused: bool) -> Result<()> {
+        self.focused().set_pause(paused).await
+    }
+
+    async fn set_weight(&self, weight: u8) -> Result<()> {
+        self.focused().set_weight(weight).await
+    }
+
+    async fn set_farnsworth(&self, wpm: u8) -> Result<()> {
+        self.focused().set_farnsworth(wpm).await
+    }
+
+    async fn set_sidetone(&self, freq_hz: u16) -> Result<()> {
+        self.focused().set_sidetone(freq_hz).await
+    }
+}
+
+impl Drop for KeyerSwitch {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::timing::MorseTiming;
+    use crate::virtual_keyer::VirtualKeyer;
+
+    fn two_radios() -> (Arc<VirtualKeyer>, Arc<VirtualKeyer>, KeyerSwitch) {
+        let a = Arc::new(VirtualKeyer::new(MorseTiming::default()));
+        let b = Arc::new(VirtualKeyer::new(MorseTiming::default()));
+        let switch = KeyerSwitch::new(vec![a.clone(), b.clone()]).unwrap();
+        (a, b, switch)
+    }
+
+    #[tokio::test]
+    async fn empty_switch_rejected() {
+        assert!(matches!(
+            KeyerSwitch::new(Vec::new()),
+            Err(Error::InvalidParameter(_))
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn routes_to_focused_and_other() {
+        let (a, b, switch) = two_radios();
+
+        switch.send_message("E").await.unwrap();
+        switch.send_on_other("T").await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(a.timeline().text(), "E");
+        assert_eq!(b.timeline().text(), "T");
+
+        assert_eq!(switch.swap_focus(), 1);
+        switch.send_message("I").await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(b.timeline().text(), "TI");
+
+        assert!(switch.set_focus(2).is_err());
+        switch.close().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn merged_events_are_tagged() {
+        let (_a, _b, switch) = two_radios();
+        let mut rx = switch.subscribe_radios();
+
+        switch.send_on(1, "E").await.unwrap();
+        let mut tagged = None;
+        while tagged.is_none() {
+            if let Ok(RadioEvent {
+                radio,
+                event: KeyerEvent::CharacterSent { ch, .. },
+            }) = rx.recv().await
+            {
+                tagged = Some((radio, ch));
+            }
+        }
+        assert_eq!(tagged, Some((1, 'E')));
+
+        switch.close().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn queue_interleaves_without_overlap() {
+        let (a, b, switch) = two_radios();
+        switch.queue(0, "E").unwrap();
+        switch.queue(1, "E").unwrap();
+        switch.queue(0, "T").unwrap();
+        tokio::time::sleep(Duration::from_secs(3)).await;
+
+        let ia = a.timeline().key_intervals();
+        let ib = b.timeline().key_intervals();
+        assert_eq!(ia.len(), 2);
+        assert_eq!(ib.len(), 1);
+        // Radio 1 starts only after radio 0's E (dit + letter gap) is done,
+        // and radio 0's T waits for radio 1.
+        assert!(ib[0].start >= ia[0].end + Duration::from_millis(180));
+        assert!(ia[1].start >= ib[0].end + Duration::from_millis(180));
+        assert_eq!(switch.queued(), 0);
+
+        switch.close().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn abort_targets_transmitting_radio_and_clears_queue() {
+        let (a, b, switch) = two_radios();
+
+        switch.send_on_other("TTTT").await.unwrap();
+        switch.queue(0, "EEEE").unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        // Focus is radio 0, but radio 1 is the one sending
+        switch.abort().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(5)).await;
+
+        assert_eq!(b.timeline().text(), "T");
+        assert_eq!(switch.queued(), 0);
+        // The queued message on radio 0 either never started or was aborted
+        assert!(a.timeline().text().len() <= 1);
+
+        switch.close().await.unwrap();
+    }
+}
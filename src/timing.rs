@@ -0,0 +1,401 @@
+//! Morse timing model for software keyer backends.
+//!
+//! Element durations follow the PARIS standard (one dit = 1200 / WPM ms) and
+//! apply weight, dit/dah ratio, Farnsworth and contest spacing with the same
+//! semantics as the WinKeyer parameters of the same name.
+
+use std::time::Duration;
+
+/// Look up the dit/dah pattern for a character (`.` = dit, `-` = dah).
+///
+/// Covers the character set accepted by
+/// [`validate_cw_text`](crate::protocol::command::validate_cw_text).
+/// Lowercase letters map to their uppercase pattern. Returns `None` for
+/// space and unsupported characters.
+pub fn morse_pattern(ch: char) -> Option<&'static str> {
+    let pattern = match ch.to_ascii_uppercase() {
+        'A' => ".-",
+        'B' => "-...",
+        'C' => "-.-.",
+        'D' => "-..",
+        'E' => ".",
+        'F' => "..-.",
+        'G' => "--.",
+        'H' => "....",
+        'I' => "..",
+        'J' => ".---",
+        'K' => "-.-",
+        'L' => ".-..",
+        'M' => "--",
+        'N' => "-.",
+        'O' => "---",
+        'P' => ".--.",
+        'Q' => "--.-",
+        'R' => ".-.",
+        'S' => "...",
+        'T' => "-",
+        'U' => "..-",
+        'V' => "...-",
+        'W' => ".--",
+        'X' => "-..-",
+        'Y' => "-.--",
+        'Z' => "--..",
+        '0' => "-----",
+        '1' => ".----",
+        '2' => "..---",
+        '3' => "...--",
+        '4' => "....-",
+        '5' => ".....",
+        '6' => "-....",
+        '7' => "--...",
+        '8' => "---..",
+        '9' => "----.",
+        '.' => ".-.-.-",
+        ',' => "--..--",
+        '?' => "..--..",
+        '/' => "-..-.",
+        '!' => "-.-.--",
+        '=' => "-...-",
+        '+' => ".-.-.",
+        '-' => "-....-",
+        ':' => "---...",
+        ';' => "-.-.-.",
+        '\'' => ".----.",
+        '"' => ".-..-.",
+        '(' => "-.--.",
+        ')' => "-.--.-",
+        '@' => ".--.-.",
+        '&' => ".-...",
+        '_' => "..--.-",
+        _ => return None,
+    };
+    Some(pattern)
+}
+
+/// One segment of a keying timeline: hold the key in `key_down` state for
+/// `duration`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyStep {
+    pub key_down: bool,
+    pub duration: Duration,
+}
+
+/// Timing parameters for generating Morse on the host.
+///
+/// Field ranges match the WinKeyer: speed 5-99 WPM, weight 10-90
+/// (50 = standard), dit/dah ratio 33-66 (50 = 3:1). A `farnsworth_wpm`
+/// above `wpm` sends characters at the Farnsworth speed and stretches the
+/// spacing so the overall rate stays at `wpm`; 0 disables it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MorseTiming {
+    pub wpm: u8,
+    pub weight: u8,
+    pub dit_dah_ratio: u8,
+    pub farnsworth_wpm: u8,
+    pub contest_spacing: bool,
+}
+
+impl Default for MorseTiming {
+    fn default() -> Self {
+        Self {
+            wpm: 20,
+            weight: 50,
+            dit_dah_ratio: 50,
+            farnsworth_wpm: 0,
+            contest_spacing: false,
+        }
+    }
+}
+
+impl MorseTiming {
+    /// Speed at which individual characters are formed.
+    fn character_wpm(&self) -> u8 {
+        if self.farnsworth_wpm > self.wpm {
+            self.farnsworth_wpm
+        } else {
+            self.wpm
+        }
+    }
+
+    /// Length of one dit unit in microseconds at the character speed.
+    fn unit_us(&self) -> u64 {
+        1_200_000 / self.character_wpm().max(1) as u64
+    }
+
+    /// Extra key-down time added by weighting (negative = lighter).
+    fn weight_offset_us(&self) -> i64 {
+        self.unit_us() as i64 * (self.weight as i64 - 50) / 50
+    }
+
+    /// Farnsworth delay per unit of inter-character/word space, if active.
+    ///
+    /// ARRL formula: total added delay per PARIS word
+    /// `ta = (60 c - 37.2 s) / (c s)` seconds, spread over 19 space units.
+    fn farnsworth_unit_us(&self) -> Option<u64> {
+        if self.farnsworth_wpm <= self.wpm {
+            return None;
+        }
+        let c = self.farnsworth_wpm as f64;
+        let s = self.wpm.max(1) as f64;
+        let ta = (60.0 * c - 37.2 * s) / (c * s);
+        Some((ta * 1_000_000.0 / 19.0) as u64)
+    }
+
+    /// Duration of a single dit (unweighted).
+    pub fn dit(&self) -> Duration {
+        Duration::from_micros(self.unit_us())
+    }
+
+    /// Duration of a single dah (unweighted), scaled by the dit/dah ratio.
+    pub fn dah(&self) -> Duration {
+        Duration::from_micros(self.unit_us() * 3 * self.dit_dah_ratio as u64 / 50)
+    }
+
+    /// Silence between the elements of one character.
+    pub fn element_gap(&self) -> Duration {
+        self.dit()
+    }
+
+    /// Silence between characters (nominally 3 units).
+    pub fn letter_gap(&self) -> Duration {
+        match self.farnsworth_unit_us() {
+            Some(fu) => Duration::from_micros(fu * 3),
+            None => Duration::from_micros(self.unit_us() * 3),
+        }
+    }
+
+    /// Silence between words (7 units, or 6 with contest spacing).
+    pub fn word_gap(&self) -> Duration {
+        let units = if self.contest_spacing { 6 } else { 7 };
+        match self.farnsworth_unit_us() {
+            Some(fu) => Duration::from_micros(fu * units),
+            None => Duration::from_micros(self.unit_us() * units),
+        }
+    }
+
+    /// Key timeline for one character, including the trailing letter gap.
+    ///
+    /// A space yields the extra silence needed to stretch the preceding
+    /// letter gap to a word gap. Returns `None` for unsupported characters.
+    pub fn character(&self, ch: char) -> Option<Vec<KeyStep>> {
+        if ch == ' ' {
+            return Some(vec![KeyStep {
+                key_down: false,
+                duration: self.word_gap().saturating_sub(self.letter_gap()),
+            }]);
+        }
+        morse_pattern(ch).map(|p| self.pattern_steps(p))
+    }
+
+    /// Key timeline for two characters merged into a prosign (no letter
+    /// gap between them), including the trailing letter gap.
+    pub fn merged(&self, c1: char, c2: char) -> Option<Vec<KeyStep>> {
+        let mut pattern = String::from(morse_pattern(c1)?);
+        pattern.push_str(morse_pattern(c2)?);
+        Some(self.pattern_steps(&pattern))
+    }
+
+    /// Expand a dit/dah pattern into weighted key steps.
+    fn pattern_steps(&self, pattern: &str) -> Vec<KeyStep> {
+        let offset = self.weight_offset_us();
+        let mut steps = Vec::with_capacity(pattern.len() * 2);
+        let count = pattern.len();
+
+        for (i, element) in pattern.chars().enumerate() {
+            let on = match element {
+                '-' => self.dah(),
+                _ => self.dit(),
+            };
+            let gap = if i + 1 == count {
+                self.letter_gap()
+            } else {
+                self.element_gap()
+            };
+            let on_us = (on.as_micros() as i64 + offset).max(1) as u64;
+            let off_us = (gap.as_micros() as i64 - offset).max(0) as u64;
+            steps.push(KeyStep {
+                key_down: true,
+                duration: Duration::from_micros(on_us),
+            });
+            steps.push(KeyStep {
+                key_down: false,
+                duration: Duration::from_micros(off_us),
+            });
+        }
+
+        steps
+    }
+}
+
+/// Key compensation and first extension for a rig's T/R switching delay.
+///
+/// A rig that takes `qsk_delay_ms` to switch to transmit clips that much
+/// off the front of an element: every element with full break-in, or just
+/// the first one after a pause with semi break-in. Key compensation
+/// lengthens every element, but each millisecond added also comes out of
+/// the space that follows, so it is capped at a quarter dit. First
+/// extension makes up the rest of the delay on the first element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QskTuning {
+    pub key_compensation_ms: u8,
+    pub first_extension_ms: u8,
+}
+
+impl QskTuning {
+    /// Recommended values for a measured switching delay at `wpm`.
+    pub fn recommend(qsk_delay_ms: u16, wpm: u8) -> Self {
+        let delay = qsk_delay_ms.min(250) as u8;
+        let dit_ms = 1200 / wpm.max(1) as u16;
+        let key_compensation_ms = delay.min((dit_ms / 4).min(u8::MAX as u16) as u8);
+        Self {
+            key_compensation_ms,
+            first_extension_ms: delay - key_compensation_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qsk_tuning_caps_compensation_at_quarter_dit() {
+        // 20 WPM: 60ms dit, so at most 15ms of compensation
+        assert_eq!(
+            QskTuning::recommend(8, 20),
+            QskTuning { key_compensation_ms: 8, first_extension_ms: 0 }
+        );
+        assert_eq!(
+            QskTuning::recommend(40, 20),
+            QskTuning { key_compensation_ms: 15, first_extension_ms: 25 }
+        );
+        // Faster speeds leave less room
+        assert_eq!(QskTuning::recommend(40, 40).key_compensation_ms, 7);
+        assert_eq!(QskTuning::recommend(400, 5).first_extension_ms, 190);
+        // A quarter dit at 1 WPM (300ms) doesn't fit in a byte
+        assert_eq!(
+            QskTuning::recommend(200, 1),
+            QskTuning { key_compensation_ms: 200, first_extension_ms: 0 }
+        );
+    }
+
+    fn ms(v: u64) -> Duration {
+        Duration::from_millis(v)
+    }
+
+    #[test]
+    fn pattern_lookup() {
+        assert_eq!(morse_pattern('A'), Some(".-"));
+        assert_eq!(morse_pattern('a'), Some(".-"));
+        assert_eq!(morse_pattern('0'), Some("-----"));
+        assert_eq!(morse_pattern('?'), Some("..--.."));
+        assert_eq!(morse_pattern(' '), None);
+        assert_eq!(morse_pattern('~'), None);
+    }
+
+    #[test]
+    fn all_valid_cw_chars_have_patterns() {
+        let valid = "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789.,?/!=+-:;'\"()@&_";
+        for ch in valid.chars() {
+            assert!(morse_pattern(ch).is_some(), "missing pattern for {ch:?}");
+        }
+    }
+
+    #[test]
+    fn paris_timing_20_wpm() {
+        let t = MorseTiming::default();
+        assert_eq!(t.dit(), ms(60));
+        assert_eq!(t.dah(), ms(180));
+        assert_eq!(t.letter_gap(), ms(180));
+        assert_eq!(t.word_gap(), ms(420));
+    }
+
+    #[test]
+    fn contest_spacing_shortens_word_gap() {
+        let t = MorseTiming {
+            contest_spacing: true,
+            ..MorseTiming::default()
+        };
+        assert_eq!(t.word_gap(), ms(360));
+    }
+
+    #[test]
+    fn character_steps() {
+        let t = MorseTiming::default();
+        let steps = t.character('A').unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                KeyStep { key_down: true, duration: ms(60) },
+                KeyStep { key_down: false, duration: ms(60) },
+                KeyStep { key_down: true, duration: ms(180) },
+                KeyStep { key_down: false, duration: ms(180) },
+            ]
+        );
+    }
+
+    #[test]
+    fn space_extends_letter_gap_to_word_gap() {
+        let t = MorseTiming::default();
+        let steps = t.character(' ').unwrap();
+        assert_eq!(steps, vec![KeyStep { key_down: false, duration: ms(240) }]);
+    }
+
+    #[test]
+    fn weight_shifts_time_from_space_to_mark() {
+        let t = MorseTiming {
+            weight: 60,
+            ..MorseTiming::default()
+        };
+        let steps = t.character('E').unwrap();
+        // 60 ms dit + 20% of a unit (12 ms); gap shortened by the same
+        assert_eq!(steps[0].duration, ms(72));
+        assert_eq!(steps[1].duration, ms(168));
+    }
+
+    #[test]
+    fn ratio_scales_dah() {
+        let t = MorseTiming {
+            dit_dah_ratio: 66,
+            ..MorseTiming::default()
+        };
+        // 3 * 60 * 66 / 50
+        assert_eq!(t.dah(), Duration::from_micros(237_600));
+    }
+
+    #[test]
+    fn farnsworth_stretches_spacing_only() {
+        let t = MorseTiming {
+            wpm: 10,
+            farnsworth_wpm: 20,
+            ..MorseTiming::default()
+        };
+        // Characters formed at 20 WPM
+        assert_eq!(t.dit(), ms(60));
+        // ta = (60*20 - 37.2*10) / (20*10) = 4.14 s → letter gap 3*ta/19
+        assert_eq!(t.letter_gap(), Duration::from_micros(653_682));
+        assert!(t.word_gap() > t.letter_gap() * 2);
+    }
+
+    #[test]
+    fn farnsworth_ignored_below_speed() {
+        let t = MorseTiming {
+            wpm: 25,
+            farnsworth_wpm: 15,
+            ..MorseTiming::default()
+        };
+        assert_eq!(t.dit(), ms(48));
+        assert_eq!(t.letter_gap(), ms(144));
+    }
+
+    #[test]
+    fn merged_prosign_has_no_inner_letter_gap() {
+        let t = MorseTiming::default();
+        let steps = t.merged('A', 'R').unwrap();
+        // .-.-. = 5 elements
+        assert_eq!(steps.len(), 10);
+        let gaps: Vec<_> = steps.iter().filter(|s| !s.key_down).collect();
+        assert!(gaps[..4].iter().all(|s| s.duration == ms(60)));
+        assert_eq!(gaps[4].duration, ms(180));
+    }
+}
@@ -0,0 +1,221 @@
+//! Human-readable protocol traces from captured sessions.
+//!
+//! [`annotate`] turns a [`Capture`] into a timeline of decoded commands and
+//! responses:
+//!
+//! ```text
+//! 0.000000 host: HOST CLOSE
+//! 0.150871 host: HOST OPEN
+//! 0.201344 wk: VERSION 31
+//! 0.402230 host: SET SPEED 25
+//! 1.032118 wk: STATUS busy
+//! 1.120447 wk: ECHO 'C'
+//! 2.480016 wk: SPEED POT 28 WPM
+//! ```
+//!
+//! Host bytes go through the
+//! [`CommandDecoder`](crate::protocol::decoder::CommandDecoder); keyer
+//! bytes through [`classify_byte`](crate::protocol::response::classify_byte),
+//! except where a query's reply is due. Keyers run in WK2 mode, so a status
+//! byte with bit 3 set is shown as pushbutton status.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+use crate::capture::{Capture, Direction};
+use crate::event::KeyerStatus;
+use crate::protocol::decoder::{Command, CommandDecoder};
+use crate::protocol::response::{classify_byte, decode_speed_pot, ResponseByte};
+
+/// How long a query waits for its reply before the trace stops expecting
+/// it (matches the IO task's read timeout).
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// One line of a trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// Time since the capture started.
+    pub at: Duration,
+    pub direction: Direction,
+    /// Decoded description, e.g. `SET SPEED 25` or `ECHO 'C'`.
+    pub text: String,
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let who = match self.direction {
+            Direction::Host => "host",
+            Direction::Keyer => "wk",
+        };
+        write!(f, "{:.6} {who}: {}", self.at.as_secs_f64(), self.text)
+    }
+}
+
+/// A query still waiting for its reply bytes.
+struct PendingReply {
+    command: Command,
+    len: usize,
+    sent_at: Duration,
+    bytes: Vec<u8>,
+}
+
+/// Decode a captured session into a trace.
+pub fn annotate(capture: &Capture) -> Vec<TraceEntry> {
+    let mut entries = Vec::new();
+    let mut decoder = CommandDecoder::new();
+    let mut replies: VecDeque<PendingReply> = VecDeque::new();
+    // Unknown until the host programs the pot range.
+    let mut min_wpm: Option<u8> = None;
+
+    for record in &capture.records {
+        let entry = |text: String| TraceEntry {
+            at: record.at,
+            direction: record.direction,
+            text,
+        };
+        match record.direction {
+            Direction::Host => {
+                for command in decoder.decode(&record.data) {
+                    match &command {
+                        Command::SetSpeedPot { min, .. } => min_wpm = Some(*min),
+                        Command::LoadDefaults(d) => min_wpm = Some(d.min_wpm),
+                        _ => {}
+                    }
+                    entries.push(entry(command.to_string()));
+                    if let Some(len) = command.reply_len() {
+                        replies.push_back(PendingReply {
+                            command,
+                            len,
+                            sent_at: record.at,
+                            bytes: Vec::new(),
+                        });
+                    }
+                }
+            }
+            Direction::Keyer => {
+                for &byte in &record.data {
+                    // A reply that never came must not swallow later bytes.
+                    while replies.front().is_some_and(|r| {
+                        r.bytes.is_empty() && record.at > r.sent_at + REPLY_TIMEOUT
+                    }) {
+                        replies.pop_front();
+                    }
+                    if let Some(reply) = replies.front_mut() {
+                        reply.bytes.push(byte);
+                        if reply.bytes.len() == reply.len {
+                            let reply = replies.pop_front().unwrap();
+                            entries.push(entry(describe_reply(&reply.command, &reply.bytes)));
+                        }
+                        continue;
+                    }
+                    entries.push(entry(describe_byte(byte, min_wpm)));
+                }
+            }
+        }
+    }
+    entries
+}
+
+/// Describe an unsolicited byte from the keyer.
+fn describe_byte(byte: u8, min_wpm: Option<u8>) -> String {
+    match classify_byte(byte) {
+        // WK2 mode: bit 3 marks pushbutton status, not key-down
+        ResponseByte::Status(status) if status.keydown => format!("PUSHBUTTON 0x{byte:02X}"),
+        ResponseByte::Status(status) => format!("STATUS {}", status_flags(&status)),
+        ResponseByte::SpeedPot { value } => match min_wpm {
+            Some(min) => format!("SPEED POT {} WPM", decode_speed_pot(byte, min)),
+            None => format!("SPEED POT +{value}"),
+        },
+        ResponseByte::Echo(c) => format!("ECHO {c:?}"),
+    }
+}
+
+fn status_flags(status: &KeyerStatus) -> String {
+    let flags: Vec<&str> = [
+        (status.busy, "busy"),
+        (status.breakin, "breakin"),
+        (status.xoff, "xoff"),
+        (status.waiting, "waiting"),
+    ]
+    .into_iter()
+    .filter_map(|(set, name)| set.then_some(name))
+    .collect();
+    if flags.is_empty() {
+        "idle".to_string()
+    } else {
+        flags.join("|")
+    }
+}
+
+/// Describe the reply to a query.
+fn describe_reply(command: &Command, bytes: &[u8]) -> String {
+    let value = bytes[0];
+    match command {
+        Command::HostOpen => format!("VERSION {value}"),
+        Command::EchoTest(_) => format!("ECHO TEST 0x{value:02X}"),
+        Command::PaddleA2d => format!("PADDLE A2D {value}"),
+        Command::SpeedA2d => format!("SPEED A2D {value}"),
+        Command::GetFwMajorRev => format!("FW MAJOR REV {value}"),
+        Command::GetFwMinorRev => format!("FW MINOR REV {value}"),
+        Command::ReadVcc => format!("VCC {value}"),
+        Command::GetIcType => format!("IC TYPE 0x{value:02X}"),
+        Command::DumpEeprom => format!("EEPROM ({} bytes)", bytes.len()),
+        _ => format!("VALUES {bytes:02X?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace(text: &str) -> Vec<String> {
+        annotate(&Capture::parse(text).unwrap())
+            .iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn host_and_keyer_lines() {
+        let lines = trace(
+            "0.0 > 00 02\n\
+             0.2 < 1F\n\
+             0.3 > 05 10 19 00 02 19\n\
+             0.4 > 43 51\n\
+             0.5 < C4 43\n\
+             0.55 < C8\n\
+             0.6 < 92\n\
+             0.7 < C0\n",
+        );
+        assert_eq!(
+            lines,
+            [
+                "0.000000 host: HOST OPEN",
+                "0.200000 wk: VERSION 31",
+                "0.300000 host: SET SPEED POT min 16 range 25",
+                "0.300000 host: SET SPEED 25",
+                "0.400000 host: TEXT \"CQ\"",
+                "0.500000 wk: STATUS busy",
+                "0.500000 wk: ECHO 'C'",
+                "0.550000 wk: PUSHBUTTON 0xC8",
+                "0.600000 wk: SPEED POT 34 WPM",
+                "0.700000 wk: STATUS idle",
+            ]
+        );
+    }
+
+    #[test]
+    fn split_commands_and_multi_byte_replies() {
+        let lines = trace("0.0 > 00\n0.1 > 07\n0.2 < 01 02 03 04 05\n0.3 < 06 07 08 09 0A 0B 0C 0D 0E 0F\n");
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "0.100000 host: GET VALUES");
+        assert!(lines[1].starts_with("0.300000 wk: VALUES [01, 02,"), "{}", lines[1]);
+    }
+
+    #[test]
+    fn missing_reply_expires() {
+        let lines = trace("0.0 > 00 05\n3.0 < 85\n");
+        assert_eq!(lines[1], "3.000000 wk: SPEED POT +5");
+    }
+}
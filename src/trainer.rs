@@ -0,0 +1,512 @@
+//! CW practice: Koch and Farnsworth drills.
+//!
+//! [`TextGenerator`] makes Koch character groups, random callsigns and
+//! QSO-style text. [`Trainer`] sends a drill through any [`Keyer`] at a
+//! character speed and a (slower) effective speed, records what the keyer
+//! echoed as host [`KeyerEvent::CharacterSent`], and scores the student's copy
+//! against it:
+//!
+//! ```no_run
+//! # use std::sync::Arc;
+//! # async fn example(keyer: Arc<dyn winkey::Keyer>) -> winkey::Result<()> {
+//! use winkey::trainer::{DrillConfig, Trainer};
+//!
+//! // Lesson 5 (K M R S U A), 20 WPM characters at 10 WPM effective
+//! let mut trainer = Trainer::new(keyer, DrillConfig::koch(5).items(10).speeds(20, 10))?;
+//! let drill = trainer.next_drill().await?;
+//! let score = drill.score("KMRSU AAKMS");
+//! println!("{:.0}% copied", score.accuracy() * 100.0);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Generated text depends only on the seed, so a drill can be repeated.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{Error, Result};
+use crate::keyer::Keyer;
+
+/// Characters in Koch order (the sequence used by LCWO). Lesson `n`
+/// drills the first `n + 1`.
+pub const KOCH_ORDER: &str = "KMRSUAPTLOWI.NJEF0Y,VG5/Q9ZH38B?427C1D6X";
+
+/// Last Koch lesson: every character in [`KOCH_ORDER`].
+pub const KOCH_LESSONS: usize = KOCH_ORDER.len() - 1;
+
+const NAMES: &[&str] = &["BOB", "ANN", "JIM", "SUE", "TOM", "LIZ", "DAN", "KAY", "JOE", "PAT"];
+const QTHS: &[&str] = &["BOSTON", "DENVER", "OHIO", "TEXAS", "LONDON", "PARIS", "TOKYO", "OSLO"];
+const RIGS: &[&str] = &["K3", "IC7300", "FT991", "TS590", "KX2", "QRP"];
+const WX: &[&str] = &["SUNNY", "CLOUDY", "RAIN", "SNOW", "WINDY", "HOT", "COLD"];
+const RST: &[&str] = &["599", "579", "559", "449", "339"];
+
+/// What a drill is made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrillKind {
+    /// Random groups of `group_len` characters from the Koch lesson.
+    Koch { lesson: usize, group_len: usize },
+    /// Random callsigns.
+    Callsigns,
+    /// QSO-style exchanges (call, RST, name, QTH, rig, weather).
+    Qso,
+}
+
+/// Drill content and speeds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrillConfig {
+    pub kind: DrillKind,
+    /// Groups, callsigns or QSO exchanges per drill.
+    pub items: usize,
+    /// Speed of each character, in WPM.
+    pub char_wpm: u8,
+    /// Overall speed in WPM; below `char_wpm` the spacing is stretched
+    /// (Farnsworth).
+    pub effective_wpm: u8,
+    /// Generator seed; `None` seeds from the clock.
+    pub seed: Option<u64>,
+}
+
+impl DrillConfig {
+    fn new(kind: DrillKind) -> Self {
+        Self {
+            kind,
+            items: 10,
+            char_wpm: 20,
+            effective_wpm: 20,
+            seed: None,
+        }
+    }
+
+    /// Five-character groups from Koch lesson `lesson`.
+    pub fn koch(lesson: usize) -> Self {
+        Self::new(DrillKind::Koch {
+            lesson,
+            group_len: 5,
+        })
+    }
+
+    /// Random callsigns.
+    pub fn callsigns() -> Self {
+        Self::new(DrillKind::Callsigns)
+    }
+
+    /// QSO-style text.
+    pub fn qso() -> Self {
+        Self::new(DrillKind::Qso)
+    }
+
+    /// Items per drill.
+    pub fn items(mut self, items: usize) -> Self {
+        self.items = items;
+        self
+    }
+
+    /// Character and effective speed in WPM.
+    pub fn speeds(mut self, char_wpm: u8, effective_wpm: u8) -> Self {
+        self.char_wpm = char_wpm;
+        self.effective_wpm = effective_wpm;
+        self
+    }
+
+    /// Fixed generator seed, for repeatable drills.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !(5..=99).contains(&self.char_wpm) {
+            return Err(Error::InvalidParameter(format!(
+                "character speed {} WPM out of range (5-99)",
+                self.char_wpm
+            )));
+        }
+        if !(5..=self.char_wpm).contains(&self.effective_wpm) {
+            return Err(Error::InvalidParameter(format!(
+                "effective speed {} WPM must be 5-{} (the character speed)",
+                self.effective_wpm, self.char_wpm
+            )));
+        }
+        if self.items == 0 {
+            return Err(Error::InvalidParameter("drill needs at least one item".into()));
+        }
+        if let DrillKind::Koch { lesson, group_len } = self.kind {
+            if !(1..=KOCH_LESSONS).contains(&lesson) {
+                return Err(Error::InvalidParameter(format!(
+                    "Koch lesson {lesson} out of range (1-{KOCH_LESSONS})"
+                )));
+            }
+            if group_len == 0 {
+                return Err(Error::InvalidParameter("Koch group length must be non-zero".into()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Seeded generator for drill text.
+#[derive(Debug, Clone)]
+pub struct TextGenerator {
+    state: u64,
+}
+
+impl TextGenerator {
+    /// Generator with a fixed seed.
+    pub fn new(seed: u64) -> Self {
+        // xorshift has a fixed point at zero
+        Self {
+            state: seed ^ 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    /// Generator seeded from the clock.
+    pub fn from_clock() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        Self::new(nanos as u64)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        // xorshift64*
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+
+    fn letter(&mut self) -> char {
+        (b'A' + self.below(26) as u8) as char
+    }
+
+    /// `groups` groups of `group_len` characters from Koch lesson `lesson`.
+    pub fn koch_groups(&mut self, lesson: usize, groups: usize, group_len: usize) -> String {
+        let chars: Vec<char> = KOCH_ORDER.chars().take(lesson.clamp(1, KOCH_LESSONS) + 1).collect();
+        (0..groups)
+            .map(|_| (0..group_len).map(|_| chars[self.below(chars.len())]).collect::<String>())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// A callsign such as `K1EL`, `DL2ABC` or `VE3XY`.
+    pub fn callsign(&mut self) -> String {
+        let mut call = String::new();
+        call.push(self.letter());
+        match self.below(3) {
+            0 => {}
+            1 => call.push(self.letter()),
+            _ => call.push((b'0' + self.below(10) as u8) as char),
+        }
+        call.push((b'0' + self.below(10) as u8) as char);
+        for _ in 0..1 + self.below(3) {
+            call.push(self.letter());
+        }
+        call
+    }
+
+    /// `count` callsigns separated by spaces.
+    pub fn callsigns(&mut self, count: usize) -> String {
+        (0..count).map(|_| self.callsign()).collect::<Vec<_>>().join(" ")
+    }
+
+    /// One QSO-style exchange between two random stations.
+    pub fn qso(&mut self) -> String {
+        let (them, me) = (self.callsign(), self.callsign());
+        let rst = self.pick(RST);
+        format!(
+            "{them} DE {me} GM TNX FER CALL UR RST {rst} {rst} NAME {name} QTH {qth} \
+             RIG {rig} WX {wx} HW? {them} DE {me} K",
+            name = self.pick(NAMES),
+            qth = self.pick(QTHS),
+            rig = self.pick(RIGS),
+            wx = self.pick(WX),
+        )
+    }
+
+    /// Text for a drill of `kind` with `items` items.
+    pub fn drill(&mut self, kind: DrillKind, items: usize) -> String {
+        match kind {
+            DrillKind::Koch { lesson, group_len } => self.koch_groups(lesson, items, group_len),
+            DrillKind::Callsigns => self.callsigns(items),
+            DrillKind::Qso => (0..items).map(|_| self.qso()).collect::<Vec<_>>().join(" "),
+        }
+    }
+}
+
+/// A drill that was sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drill {
+    /// Text queued for sending.
+    pub text: String,
+    /// Characters the keyer reported as sent. Shorter than `text` if the
+    /// drill was cut off.
+    pub echoed: String,
+}
+
+impl Drill {
+    /// Score `copy` against what was actually sent.
+    pub fn score(&self, copy: &str) -> Score {
+        Score::compare(&self.echoed, copy)
+    }
+}
+
+/// Copy accuracy, from an edit-distance alignment of sent and copied text.
+///
+/// Case and runs of whitespace are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Score {
+    /// Normalized sent text.
+    pub sent: String,
+    /// Normalized copy.
+    pub copied: String,
+    /// Sent characters copied correctly.
+    pub correct: usize,
+    /// Sent characters copied as something else.
+    pub substitutions: usize,
+    /// Sent characters missing from the copy.
+    pub deletions: usize,
+    /// Copied characters that were never sent.
+    pub insertions: usize,
+    /// Sent characters that were miscopied or missed, with counts.
+    pub missed: BTreeMap<char, usize>,
+}
+
+impl Score {
+    /// Align `copy` against `sent`.
+    pub fn compare(sent: &str, copy: &str) -> Self {
+        let sent = normalize(sent);
+        let copied = normalize(copy);
+        let a: Vec<char> = sent.chars().collect();
+        let b: Vec<char> = copied.chars().collect();
+
+        // dist[i][j]: edits to turn a[..i] into b[..j]
+        let mut dist = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+        for (i, row) in dist.iter_mut().enumerate() {
+            row[0] = i;
+        }
+        for (j, cell) in dist[0].iter_mut().enumerate() {
+            *cell = j;
+        }
+        for i in 1..=a.len() {
+            for j in 1..=b.len() {
+                let sub = dist[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+                dist[i][j] = sub.min(dist[i - 1][j] + 1).min(dist[i][j - 1] + 1);
+            }
+        }
+
+        let mut score = Self {
+            sent: sent.clone(),
+            copied: copied.clone(),
+            ..Self::default()
+        };
+        let (mut i, mut j) = (a.len(), b.len());
+        while i > 0 || j > 0 {
+            if i > 0 && j > 0 && dist[i][j] == dist[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]) {
+                if a[i - 1] == b[j - 1] {
+                    if a[i - 1] != ' ' {
+                        score.correct += 1;
+                    }
+                } else {
+                    score.substitutions += 1;
+                    score.miss(a[i - 1]);
+                }
+                i -= 1;
+                j -= 1;
+            } else if i > 0 && dist[i][j] == dist[i - 1][j] + 1 {
+                score.deletions += 1;
+                score.miss(a[i - 1]);
+                i -= 1;
+            } else {
+                score.insertions += 1;
+                j -= 1;
+            }
+        }
+        score
+    }
+
+    fn miss(&mut self, ch: char) {
+        if ch != ' ' {
+            *self.missed.entry(ch).or_default() += 1;
+        }
+    }
+
+    /// Characters sent, not counting spaces.
+    pub fn total(&self) -> usize {
+        self.sent.chars().filter(|&c| c != ' ').count()
+    }
+
+    /// Fraction of sent characters copied correctly, less a penalty for
+    /// extra characters (0.0-1.0).
+    pub fn accuracy(&self) -> f32 {
+        let total = self.total();
+        if total == 0 {
+            return if self.copied.is_empty() { 1.0 } else { 0.0 };
+        }
+        let right = self.correct.saturating_sub(self.insertions);
+        right as f32 / total as f32
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_ascii_uppercase()
+}
+
+/// Sends drills through a keyer.
+pub struct Trainer {
+    keyer: Arc<dyn Keyer>,
+    config: DrillConfig,
+    generator: TextGenerator,
+}
+
+impl std::fmt::Debug for Trainer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Trainer")
+            .field("keyer", &self.keyer.info().name)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl Trainer {
+    /// Validate `config` for `keyer`. A Farnsworth drill (effective speed
+    /// below character speed) needs [`KeyerCapabilities::farnsworth`](crate::KeyerCapabilities::farnsworth).
+    pub fn new(keyer: Arc<dyn Keyer>, config: DrillConfig) -> Result<Self> {
+        config.validate()?;
+        if config.effective_wpm < config.char_wpm && !keyer.capabilities().farnsworth {
+            return Err(Error::Unsupported("Farnsworth".into()));
+        }
+        let generator = match config.seed {
+            Some(seed) => TextGenerator::new(seed),
+            None => TextGenerator::from_clock(),
+        };
+        Ok(Self {
+            keyer,
+            config,
+            generator,
+        })
+    }
+
+    /// Drill settings.
+    pub fn config(&self) -> &DrillConfig {
+        &self.config
+    }
+
+    /// Generate the next drill and send it.
+    pub async fn next_drill(&mut self) -> Result<Drill> {
+        let text = self.generator.drill(self.config.kind, self.config.items);
+        self.send(&text).await
+    }
+
+    /// Send `text` at the drill speeds and wait until it has gone out. The
+    /// keyer is left at those speeds.
+    pub async fn send(&self, text: &str) -> Result<Drill> {
+        crate::protocol::command::validate_cw_text(text).map_err(Error::InvalidParameter)?;
+        let (char_wpm, effective_wpm) = (self.config.char_wpm, self.config.effective_wpm);
+        if effective_wpm < char_wpm {
+            self.keyer.set_speed(effective_wpm).await?;
+            self.keyer.set_farnsworth(char_wpm).await?;
+        } else {
+            self.keyer.set_speed(char_wpm).await?;
+            if self.keyer.capabilities().farnsworth {
+                self.keyer.set_farnsworth(0).await?;
+            }
+        }
+
+        let mut events = self.keyer.events();
+        self.keyer.send_message(text).await?;
+        let echoed = events.host_text_until_idle().await?;
+        Ok(Drill {
+            text: text.to_string(),
+            echoed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timing::MorseTiming;
+    use crate::virtual_keyer::VirtualKeyer;
+
+    #[test]
+    fn koch_groups_use_lesson_characters() {
+        let mut generator = TextGenerator::new(7);
+        let text = generator.koch_groups(3, 20, 5);
+        assert_eq!(text.split(' ').count(), 20);
+        assert!(text.split(' ').all(|g| g.len() == 5));
+        assert!(text.chars().all(|c| "KMRS ".contains(c)));
+        assert_eq!(TextGenerator::new(7).koch_groups(3, 20, 5), text);
+    }
+
+    #[test]
+    fn generated_text_is_sendable() {
+        let mut generator = TextGenerator::new(42);
+        for _ in 0..50 {
+            let call = generator.callsign();
+            assert!((3..=6).contains(&call.len()), "{call}");
+            assert!(call.chars().any(|c| c.is_ascii_digit()), "{call}");
+            let qso = generator.qso();
+            crate::protocol::command::validate_cw_text(&qso).unwrap();
+        }
+        let all = generator.koch_groups(KOCH_LESSONS, 50, 5);
+        crate::protocol::command::validate_cw_text(&all).unwrap();
+    }
+
+    #[test]
+    fn score_alignment() {
+        let perfect = Score::compare("KMRSU AAKMS", "kmrsu  aakms");
+        assert_eq!(perfect.correct, 10);
+        assert_eq!(perfect.accuracy(), 1.0);
+
+        // U missed, S read as H, extra E
+        let score = Score::compare("KMRSU AAKMS", "KMRS AAKMHE");
+        assert_eq!(score.deletions, 1);
+        assert_eq!(score.substitutions, 1);
+        assert_eq!(score.insertions, 1);
+        assert_eq!(score.correct, 8);
+        assert_eq!(score.missed.get(&'U'), Some(&1));
+        assert_eq!(score.missed.get(&'S'), Some(&1));
+        assert!((score.accuracy() - 0.7).abs() < 1e-6);
+
+        assert_eq!(Score::compare("", "").accuracy(), 1.0);
+        assert_eq!(Score::compare("K", "").accuracy(), 0.0);
+    }
+
+    #[test]
+    fn config_validation() {
+        assert!(DrillConfig::koch(0).validate().is_err());
+        assert!(DrillConfig::koch(KOCH_LESSONS + 1).validate().is_err());
+        assert!(DrillConfig::koch(2).speeds(15, 18).validate().is_err());
+        assert!(DrillConfig::callsigns().items(0).validate().is_err());
+        assert!(DrillConfig::qso().speeds(25, 12).validate().is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn drill_on_virtual_keyer() {
+        let keyer = Arc::new(VirtualKeyer::new(MorseTiming::default()));
+        let config = DrillConfig::koch(2).items(3).speeds(20, 10).seed(1);
+        let mut trainer = Trainer::new(keyer.clone(), config).unwrap();
+
+        let drill = trainer.next_drill().await.unwrap();
+        assert_eq!(drill.echoed, drill.text);
+        assert_eq!(keyer.get_speed().await.unwrap(), 10);
+        assert_eq!(keyer.timeline().text(), drill.text);
+        assert_eq!(drill.score(&drill.text.to_lowercase()).accuracy(), 1.0);
+
+        keyer.close().await.unwrap();
+    }
+}
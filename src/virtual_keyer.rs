@@ -0,0 +1,611 @@
+//! Pure-software keyer that renders CW to a timeline or WAV.
+//!
+//! [`VirtualKeyer`] implements [`Keyer`] without hardware: it plays the same
+//! buffered byte stream as the WinKeyer (text, prosigns, buffered speed
+//! changes) through the software engine, emits `CharacterSent` at the
+//! virtual time each character starts, and records key/PTT edges. Under
+//! tokio's paused clock it runs instantly, which makes it useful in CI and
+//! as a reference for expected WinKeyer timing.
+//!
+//! [`Timeline::render`] computes the same timeline offline, and
+//! [`Timeline::to_wav`] synthesizes sidetone audio from it.
+
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+
+use crate::error::Result;
+use crate::event::EventReceiver;
+use crate::keyer::{Keyer, KeyerCapabilities, KeyerInfo};
+use crate::soft::{Action, Control, LineDriver, Plan, Planner, PttTiming, SoftKeyer};
+use crate::timing::MorseTiming;
+
+/// One entry in a keying timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineEvent {
+    /// Key went down (true) or up (false).
+    Key(bool),
+    /// PTT asserted (true) or released (false).
+    Ptt(bool),
+    /// A character started keying.
+    Character(char),
+}
+
+/// Timestamped key, PTT and character events, relative to the start.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timeline {
+    pub events: Vec<(Duration, TimelineEvent)>,
+    /// Total length, including trailing spacing.
+    pub duration: Duration,
+}
+
+impl Timeline {
+    /// Compute the timeline for a WinKeyer buffered byte stream without
+    /// waiting in real time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use winkey::timing::MorseTiming;
+    /// use winkey::virtual_keyer::Timeline;
+    ///
+    /// let timeline = Timeline::render(b"E", MorseTiming::default());
+    /// assert_eq!(timeline.key_intervals().len(), 1);
+    /// ```
+    pub fn render(data: &[u8], timing: MorseTiming) -> Self {
+        let mut planner = Planner::new(timing, PttTiming::default());
+        let mut buffer: VecDeque<u8> = data.iter().copied().collect();
+        let mut steps = VecDeque::new();
+        let mut timeline = Timeline::default();
+        let mut at = Duration::ZERO;
+        let mut key = false;
+        let mut ptt = false;
+
+        while planner.next(&mut buffer, ptt, &mut steps) != Plan::Idle {
+            while let Some(step) = steps.pop_front() {
+                match step.action {
+                    Action::Key(down) if down != key => {
+                        key = down;
+                        timeline.events.push((at, TimelineEvent::Key(down)));
+                    }
+                    Action::Ptt(on) if on != ptt => {
+                        ptt = on;
+                        timeline.events.push((at, TimelineEvent::Ptt(on)));
+                    }
+                    Action::Echo(ch) => {
+                        timeline.events.push((at, TimelineEvent::Character(ch)));
+                    }
+                    _ => {}
+                }
+                at += step.hold;
+            }
+        }
+
+        timeline.duration = at;
+        timeline
+    }
+
+    /// Key-down intervals. An interval still open at the end of the
+    /// timeline is closed at `duration`.
+    pub fn key_intervals(&self) -> Vec<Range<Duration>> {
+        let mut intervals = Vec::new();
+        let mut down_at = None;
+        for &(at, event) in &self.events {
+            match event {
+                TimelineEvent::Key(true) => down_at = Some(at),
+                TimelineEvent::Key(false) => {
+                    if let Some(start) = down_at.take() {
+                        intervals.push(start..at);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(start) = down_at {
+            intervals.push(start..self.duration.max(start));
+        }
+        intervals
+    }
+
+    /// Characters in the order they were keyed.
+    pub fn text(&self) -> String {
+        self.events
+            .iter()
+            .filter_map(|(_, e)| match e {
+                TimelineEvent::Character(ch) => Some(*ch),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Render sidetone audio as a 16-bit mono PCM WAV file.
+    pub fn to_wav(&self, sidetone: &Sidetone) -> Vec<u8> {
+        let samples = sidetone.synthesize(&self.key_intervals(), self.duration);
+        encode_wav(&samples, sidetone.sample_rate)
+    }
+}
+
+/// Sidetone synthesis parameters for [`Timeline::to_wav`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sidetone {
+    /// Tone frequency in Hz.
+    pub frequency_hz: f32,
+    /// Raised-cosine rise/fall time applied to every key edge.
+    pub rise_time: Duration,
+    /// Output sample rate in Hz.
+    pub sample_rate: u32,
+    /// Peak amplitude, 0.0-1.0 of full scale.
+    pub amplitude: f32,
+}
+
+impl Default for Sidetone {
+    fn default() -> Self {
+        Self {
+            frequency_hz: 800.0,
+            rise_time: Duration::from_millis(5),
+            sample_rate: 16_000,
+            amplitude: 0.5,
+        }
+    }
+}
+
+impl Sidetone {
+    /// Generate samples for the given key-down intervals.
+    fn synthesize(&self, intervals: &[Range<Duration>], duration: Duration) -> Vec<i16> {
+        let rate = self.sample_rate as f32;
+        let to_sample = |d: Duration| (d.as_secs_f32() * rate).round() as usize;
+        let total = to_sample(duration);
+        let rise = to_sample(self.rise_time).max(1);
+        let ramp = |n: usize| {
+            if n >= rise {
+                1.0
+            } else {
+                0.5 - 0.5 * (PI * n as f32 / rise as f32).cos()
+            }
+        };
+
+        let mut envelope = vec![0.0f32; total];
+        for interval in intervals {
+            let start = to_sample(interval.start);
+            let end = to_sample(interval.end);
+            for (n, slot) in envelope
+                .iter_mut()
+                .enumerate()
+                .take(end.saturating_add(rise).min(total))
+                .skip(start)
+            {
+                let up = ramp(n - start);
+                let down = if n < end { 1.0 } else { 1.0 - ramp(n - end) };
+                *slot = slot.max(up * down);
+            }
+        }
+
+        let step = 2.0 * PI * self.frequency_hz / rate;
+        let scale = self.amplitude.clamp(0.0, 1.0) * i16::MAX as f32;
+        envelope
+            .iter()
+            .enumerate()
+            .map(|(n, env)| (env * scale * (step * n as f32).sin()) as i16)
+            .collect()
+    }
+}
+
+/// Wrap PCM samples in a RIFF/WAVE container.
+fn encode_wav(samples: &[i16], sample_rate: u32) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVE");
+    out.extend_from_slice(b"fmt ");
+    out.extend_from_slice(&16u32.to_le_bytes()); // fmt chunk size
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&1u16.to_le_bytes()); // mono
+    out.extend_from_slice(&sample_rate.to_le_bytes());
+    out.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // byte rate
+    out.extend_from_slice(&2u16.to_le_bytes()); // block align
+    out.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for s in samples {
+        out.extend_from_slice(&s.to_le_bytes());
+    }
+    out
+}
+
+/// Recorded events from a running [`VirtualKeyer`].
+struct Recording {
+    start: Instant,
+    events: Vec<(Duration, TimelineEvent)>,
+}
+
+/// [`LineDriver`] that records edges into a shared [`Recording`].
+struct RecordingLines {
+    recording: Arc<Mutex<Recording>>,
+}
+
+impl RecordingLines {
+    fn push(&self, event: TimelineEvent) {
+        let mut rec = self.recording.lock().unwrap();
+        let at = rec.start.elapsed();
+        rec.events.push((at, event));
+    }
+}
+
+impl LineDriver for RecordingLines {
+    fn set_key(&mut self, down: bool) -> std::io::Result<()> {
+        self.push(TimelineEvent::Key(down));
+        Ok(())
+    }
+
+    fn set_ptt(&mut self, on: bool) -> std::io::Result<()> {
+        self.push(TimelineEvent::Ptt(on));
+        Ok(())
+    }
+
+    fn character(&mut self, ch: char) {
+        self.push(TimelineEvent::Character(ch));
+    }
+}
+
+/// Keyer backend with no hardware, for training tools and tests.
+///
+/// Must be created inside a tokio runtime.
+///
+/// # Example
+///
+/// ```
+/// # use winkey::{Keyer, timing::MorseTiming, virtual_keyer::VirtualKeyer};
+/// # #[tokio::main(flavor = "current_thread", start_paused = true)]
+/// # async fn main() -> winkey::Result<()> {
+/// let keyer = VirtualKeyer::new(MorseTiming::default());
+/// keyer.send_message("TEST").await?;
+/// tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+/// assert_eq!(keyer.timeline().text(), "TEST");
+/// # Ok(())
+/// # }
+/// ```
+pub struct VirtualKeyer {
+    soft: SoftKeyer,
+    recording: Arc<Mutex<Recording>>,
+    info: KeyerInfo,
+    capabilities: KeyerCapabilities,
+}
+
+impl std::fmt::Debug for VirtualKeyer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VirtualKeyer")
+            .field("timing", &self.soft.timing())
+            .finish()
+    }
+}
+
+impl VirtualKeyer {
+    /// Start a virtual keyer with the given timing.
+    pub fn new(timing: MorseTiming) -> Self {
+        let recording = Arc::new(Mutex::new(Recording {
+            start: Instant::now(),
+            events: Vec::new(),
+        }));
+        let lines = RecordingLines {
+            recording: recording.clone(),
+        };
+        let soft = SoftKeyer::spawn(lines, timing, PttTiming::default());
+
+        Self {
+            soft,
+            recording,
+            info: KeyerInfo {
+                name: "Virtual keyer".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                port: None,
+            },
+            capabilities: KeyerCapabilities {
+                speed_pot: false,
+                sidetone: false,
+                ptt_control: true,
+                paddle_echo: false,
+                prosigns: true,
+                buffered_speed: true,
+                farnsworth: true,
+                contest_spacing: true,
+                pause: true,
+                weight: true,
+                key_state: true,
+            },
+        }
+    }
+
+    /// Everything keyed since creation (or the last [`clear_timeline`]).
+    ///
+    /// [`clear_timeline`]: Self::clear_timeline
+    pub fn timeline(&self) -> Timeline {
+        let rec = self.recording.lock().unwrap();
+        Timeline {
+            events: rec.events.clone(),
+            duration: rec.start.elapsed(),
+        }
+    }
+
+    /// Discard recorded events and restart the timeline clock.
+    pub fn clear_timeline(&self) {
+        let mut rec = self.recording.lock().unwrap();
+        rec.start = Instant::now();
+        rec.events.clear();
+    }
+
+    /// Set dit/dah ratio (33-66, default 50 = 3:1).
+    pub async fn set_ratio(&self, ratio: u8) -> Result<()> {
+        self.soft.set_ratio(ratio).await
+    }
+
+    /// Queue raw WinKeyer buffered bytes.
+    pub async fn raw_write(&self, data: &[u8]) -> Result<()> {
+        self.soft.engine.buffered(data.to_vec()).await
+    }
+}
+
+#[async_trait]
+impl Keyer for VirtualKeyer {
+    fn info(&self) -> &KeyerInfo {
+        &self.info
+    }
+
+    fn capabilities(&self) -> &KeyerCapabilities {
+        &self.capabilities
+    }
+
+    async fn send_message(&self, text: &str) -> Result<()> {
+        self.soft.send_message(text).await
+    }
+
+    async fn abort(&self) -> Result<()> {
+        self.soft.engine.control(Control::Abort).await
+    }
+
+    async fn set_speed(&self, wpm: u8) -> Result<()> {
+        self.soft.set_speed(wpm).await
+    }
+
+    async fn get_speed(&self) -> Result<u8> {
+        Ok(self.soft.timing().wpm)
+    }
+
+    async fn set_tune(&self, on: bool) -> Result<()> {
+        self.soft.engine.control(Control::Tune(on)).await
+    }
+
+    async fn set_ptt(&self, on: bool) -> Result<()> {
+        self.soft.set_ptt(on).await
+    }
+
+    fn subscribe(&self) -> EventReceiver {
+        self.soft.event_tx.subscribe()
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.soft.close().await
+    }
+
+    async fn send_prosign(&self, c1: u8, c2: u8) -> Result<()> {
+        self.soft.send_prosign(c1, c2).await
+    }
+
+    async fn set_buffered_speed(&self, wpm: u8) -> Result<()> {
+        self.soft.set_buffered_speed(wpm).await
+    }
+
+    async fn cancel_buffered_speed(&self) -> Result<()> {
+        self.soft.cancel_buffered_speed().await
+    }
+
+    async fn set_pause(&self, paused: bool) -> Result<()> {
+        self.soft.set_pause(paused).await
+    }
+
+    async fn set_weight(&self, weight: u8) -> Result<()> {
+        self.soft.set_weight(weight).await
+    }
+
+    async fn set_farnsworth(&self, wpm: u8) -> Result<()> {
+        self.soft.set_farnsworth(wpm).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::KeyerEvent;
+    use crate::message::build_contest_message;
+
+    fn ms(v: u64) -> Duration {
+        Duration::from_millis(v)
+    }
+
+    #[test]
+    fn render_single_character() {
+        let timeline = Timeline::render(b"A", MorseTiming::default());
+        assert_eq!(timeline.key_intervals(), vec![ms(0)..ms(60), ms(120)..ms(300)]);
+        assert_eq!(timeline.text(), "A");
+        assert_eq!(timeline.duration, ms(480));
+    }
+
+    #[test]
+    fn render_word_gap() {
+        let timeline = Timeline::render(b"E E", MorseTiming::default());
+        // dit, letter gap (180) + extra word space (240), dit
+        assert_eq!(timeline.key_intervals(), vec![ms(0)..ms(60), ms(480)..ms(540)]);
+        assert_eq!(timeline.text(), "E E");
+    }
+
+    #[test]
+    fn render_contest_message() {
+        let bytes = build_contest_message("E{40}E{0}<AR>");
+        let timeline = Timeline::render(&bytes, MorseTiming::default());
+        let intervals = timeline.key_intervals();
+        // E at 20 WPM, E at 40 WPM, then AR (5 elements) back at 20 WPM
+        assert_eq!(intervals.len(), 7);
+        assert_eq!(intervals[1], ms(240)..ms(270));
+        assert_eq!(intervals[2].start, ms(360));
+        // Prosigns don't echo as characters
+        assert_eq!(timeline.text(), "EE");
+    }
+
+    #[test]
+    fn render_buffered_key_and_ptt() {
+        let mut bytes = Vec::new();
+        bytes.extend(crate::protocol::command::buffered_ptt(true));
+        bytes.extend(crate::protocol::command::key_buffered(2));
+        bytes.extend(crate::protocol::command::buffered_ptt(false));
+        let timeline = Timeline::render(&bytes, MorseTiming::default());
+        assert_eq!(
+            timeline.events,
+            vec![
+                (ms(0), TimelineEvent::Ptt(true)),
+                (ms(0), TimelineEvent::Key(true)),
+                (ms(2000), TimelineEvent::Key(false)),
+                (ms(2000), TimelineEvent::Ptt(false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn wav_header_and_length() {
+        let timeline = Timeline::render(b"E", MorseTiming::default());
+        let sidetone = Sidetone {
+            sample_rate: 8000,
+            ..Sidetone::default()
+        };
+        let wav = timeline.to_wav(&sidetone);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(&wav[8..12], b"WAVE");
+        assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), 8000);
+        // 240 ms at 8 kHz, 2 bytes per sample
+        let data_len = u32::from_le_bytes(wav[40..44].try_into().unwrap());
+        assert_eq!(data_len, 1920 * 2);
+        assert_eq!(wav.len(), 44 + 1920 * 2);
+    }
+
+    #[test]
+    fn sidetone_silent_when_key_up_and_ramps() {
+        let sidetone = Sidetone {
+            sample_rate: 8000,
+            rise_time: ms(5),
+            amplitude: 1.0,
+            ..Sidetone::default()
+        };
+        let samples = sidetone.synthesize(&[ms(10)..ms(60)], ms(100));
+        // Silent before key-down and after the fall time
+        assert!(samples[..80].iter().all(|&s| s == 0));
+        assert!(samples[520..].iter().all(|&s| s == 0));
+        // Peak reaches near full scale once the ramp completes
+        let peak = samples[120..480].iter().map(|s| s.unsigned_abs()).max().unwrap();
+        assert!(peak > 30_000, "peak {peak}");
+        // First ramp samples are quieter than the steady state
+        let early = samples[80..84].iter().map(|s| s.unsigned_abs()).max().unwrap();
+        assert!(early < peak / 4, "early {early}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn live_keyer_matches_offline_render() {
+        let keyer = VirtualKeyer::new(MorseTiming::default());
+        let mut rx = keyer.subscribe();
+
+        keyer.send_message("CQ").await.unwrap();
+        tokio::time::sleep(Duration::from_secs(3)).await;
+
+        let live = keyer.timeline();
+        let offline = Timeline::render(b"CQ", MorseTiming::default());
+        assert_eq!(live.key_intervals(), offline.key_intervals());
+        assert_eq!(live.text(), "CQ");
+
+        let mut echoed = String::new();
+        while let Ok(ev) = rx.try_recv() {
+            if let KeyerEvent::CharacterSent { ch, .. } = ev {
+                echoed.push(ch);
+            }
+        }
+        assert_eq!(echoed, "CQ");
+
+        keyer.close().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn character_sent_at_virtual_times() {
+        let keyer = VirtualKeyer::new(MorseTiming::default());
+        let mut rx = keyer.subscribe();
+        let start = Instant::now();
+
+        keyer.send_message("EE").await.unwrap();
+
+        let mut times = Vec::new();
+        while times.len() < 2 {
+            if let Ok(KeyerEvent::CharacterSent { .. }) = rx.recv().await {
+                times.push(start.elapsed());
+            }
+        }
+        assert_eq!(times, vec![ms(0), ms(240)]);
+
+        keyer.close().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ptt_is_queued_behind_text() {
+        let keyer = VirtualKeyer::new(MorseTiming::default());
+        keyer.set_ptt(true).await.unwrap();
+        keyer.send_message("EE").await.unwrap();
+        keyer.set_ptt(false).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let timeline = keyer.timeline();
+        let ptt: Vec<(Duration, bool)> = timeline
+            .events
+            .iter()
+            .filter_map(|&(at, ref e)| match e {
+                TimelineEvent::Ptt(on) => Some((at, *on)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ptt.len(), 2, "{ptt:?}");
+        assert!(ptt[0].1 && !ptt[1].1);
+        let last_key_up = timeline.key_intervals().last().unwrap().end;
+        assert!(ptt[1].0 >= last_key_up, "PTT dropped at {:?}", ptt[1].0);
+
+        keyer.close().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn clear_timeline_restarts_clock() {
+        let keyer = VirtualKeyer::new(MorseTiming::default());
+        keyer.send_message("E").await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        keyer.clear_timeline();
+        assert!(keyer.timeline().events.is_empty());
+        keyer.close().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn buffered_speed_through_trait_matches_render() {
+        let keyer = VirtualKeyer::new(MorseTiming::default());
+        let dyn_keyer: &dyn Keyer = &keyer;
+
+        dyn_keyer.set_buffered_speed(30).await.unwrap();
+        dyn_keyer.send_message("E").await.unwrap();
+        dyn_keyer.cancel_buffered_speed().await.unwrap();
+        dyn_keyer.send_message("E").await.unwrap();
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        let offline = Timeline::render(&[0x1C, 30, b'E', 0x1E, b'E'], MorseTiming::default());
+        let live = keyer.timeline().key_intervals();
+        assert_eq!(live, offline.key_intervals());
+        assert_eq!(live[0], ms(0)..ms(40));
+        assert!(dyn_keyer.set_sidetone(700).await.is_err());
+
+        keyer.close().await.unwrap();
+    }
+}
@@ -1,16 +1,25 @@
 //! WinKeyer struct: implements the `Keyer` trait and exposes WK-specific methods.
 
 use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
 
 use async_trait::async_trait;
-use tokio::sync::broadcast;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
 use tracing::debug;
 
 use crate::error::{Error, Result};
-use crate::event::KeyerEvent;
+use crate::event::{EventReceiver, EventSender, KeyerEvent};
 use crate::io::IoHandle;
 use crate::keyer::{Keyer, KeyerCapabilities, KeyerInfo};
-use crate::protocol::{command, types::WinKeyerVersion};
+use crate::protocol::decoder::Command;
+use crate::protocol::types::{hscw_speed_byte, WinKeyerVersion};
+use crate::paddle_a2d::PaddleA2dSamples;
+use crate::protocol::command;
+use crate::rtty::{validate_rtty_text, RttyConfig};
+use crate::settings::{load_defaults_followup, KeyerSettings, SettingsChange};
+use crate::speed_pot::{PotFollower, SpeedPotCalibration, SpeedPotPolicy};
+use crate::timing::QskTuning;
 
 /// WinKeyer hardware handle.
 ///
@@ -22,18 +31,19 @@ pub struct WinKeyer {
     pub(crate) info: KeyerInfo,
     pub(crate) capabilities: KeyerCapabilities,
     pub(crate) version: WinKeyerVersion,
-    pub(crate) event_tx: broadcast::Sender<KeyerEvent>,
-    pub(crate) speed: std::sync::atomic::AtomicU8,
-    pub(crate) mode_register: std::sync::atomic::AtomicU8,
+    pub(crate) event_tx: EventSender,
+    pub(crate) settings: watch::Sender<KeyerSettings>,
+    /// Follows speed pot events into `settings`.
+    pub(crate) settings_task: JoinHandle<()>,
+    pub(crate) speed_pot: Arc<Mutex<PotFollower>>,
 }
 
-
 impl std::fmt::Debug for WinKeyer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("WinKeyer")
             .field("info", &self.info)
             .field("version", &self.version)
-            .field("speed", &self.speed.load(Ordering::Relaxed))
+            .field("speed", &self.settings.borrow().speed_wpm)
             .finish()
     }
 }
@@ -44,42 +54,82 @@ impl WinKeyer {
         self.version
     }
 
+    /// Snapshot of the settings last sent to the keyer.
+    pub fn settings(&self) -> KeyerSettings {
+        self.settings.borrow().clone()
+    }
+
+    /// Receiver that sees every settings change, including speed pot moves.
+    pub fn watch_settings(&self) -> watch::Receiver<KeyerSettings> {
+        self.settings.subscribe()
+    }
+
+    /// The current speed pot policy.
+    pub fn speed_pot_policy(&self) -> SpeedPotPolicy {
+        self.speed_pot.lock().unwrap().policy
+    }
+
+    /// Change what speed pot moves do. The last pot reading, if any, is
+    /// applied under the new policy straight away.
+    pub async fn set_speed_pot_policy(&self, policy: SpeedPotPolicy) -> Result<()> {
+        let target = {
+            let mut follower = self.speed_pot.lock().unwrap();
+            follower.set_policy(policy);
+            let settings = self.settings.borrow();
+            match settings.speed_pot_wpm {
+                Some(pot) => follower.on_reading(pot, &settings),
+                None => None,
+            }
+        };
+        match target {
+            Some(wpm) if wpm != self.settings.borrow().speed_wpm => self.set_speed(wpm).await,
+            _ => Ok(()),
+        }
+    }
+
     // ------------------------------------------------------------------
-    // WK-specific methods (not in Keyer trait)
+    // Keyer trait methods, callable without importing the trait
     // ------------------------------------------------------------------
 
     /// Send a prosign (merged letters) via the buffer.
     pub async fn send_prosign(&self, c1: u8, c2: u8) -> Result<()> {
-        self.wait_xoff().await?;
-        let cmd = command::buffered_merge(c1, c2);
-        self.io.bg_command(cmd.to_vec()).await
+        <Self as Keyer>::send_prosign(self, c1, c2).await
     }
 
     /// Set buffered speed change (takes effect in-buffer).
     pub async fn set_buffered_speed(&self, wpm: u8) -> Result<()> {
-        self.wait_xoff().await?;
-        let cmd = command::buffered_speed_change(wpm);
-        self.io.bg_command(cmd.to_vec()).await
+        <Self as Keyer>::set_buffered_speed(self, wpm).await
     }
 
     /// Cancel buffered speed change (restore original speed).
     pub async fn cancel_buffered_speed(&self) -> Result<()> {
-        self.wait_xoff().await?;
-        let cmd = command::cancel_buffered_speed();
-        self.io.bg_command(cmd.to_vec()).await
+        <Self as Keyer>::cancel_buffered_speed(self).await
     }
 
     /// Set keying weight (10-90, default 50).
     pub async fn set_weight(&self, weight: u8) -> Result<()> {
-        if !(10..=90).contains(&weight) {
-            return Err(Error::InvalidParameter(format!(
-                "weight must be 10-90, got {weight}"
-            )));
-        }
-        let cmd = command::set_weight(weight);
-        self.io.rt_command(cmd.to_vec()).await
+        <Self as Keyer>::set_weight(self, weight).await
+    }
+
+    /// Set Farnsworth speed (0 = disable).
+    pub async fn set_farnsworth(&self, wpm: u8) -> Result<()> {
+        <Self as Keyer>::set_farnsworth(self, wpm).await
+    }
+
+    /// Set sidetone frequency in Hz (500-4000).
+    pub async fn set_sidetone(&self, freq_hz: u16) -> Result<()> {
+        <Self as Keyer>::set_sidetone(self, freq_hz).await
     }
 
+    /// Pause or resume CW output.
+    pub async fn set_pause(&self, paused: bool) -> Result<()> {
+        <Self as Keyer>::set_pause(self, paused).await
+    }
+
+    // ------------------------------------------------------------------
+    // WK-specific methods (not in Keyer trait)
+    // ------------------------------------------------------------------
+
     /// Set dit/dah ratio (33-66, default 50 = 3:1).
     pub async fn set_ratio(&self, ratio: u8) -> Result<()> {
         if !(33..=66).contains(&ratio) {
@@ -88,13 +138,9 @@ impl WinKeyer {
             )));
         }
         let cmd = command::set_ratio(ratio);
-        self.io.rt_command(cmd.to_vec()).await
-    }
-
-    /// Set Farnsworth speed (0 = disable).
-    pub async fn set_farnsworth(&self, wpm: u8) -> Result<()> {
-        let cmd = command::set_farnsworth(wpm);
-        self.io.rt_command(cmd.to_vec()).await
+        self.io.rt_command(cmd.to_vec()).await?;
+        self.update_settings(|s| s.dit_dah_ratio = ratio);
+        Ok(())
     }
 
     /// Set paddle mode (IambicA, IambicB, Ultimatic, Bug).
@@ -102,50 +148,223 @@ impl WinKeyer {
     /// Preserves all other mode register bits (contest spacing, auto space, etc.)
     /// by doing a read-modify-write on the cached mode register value.
     pub async fn set_paddle_mode(&self, mode: crate::PaddleMode) -> Result<()> {
-        let current = self.mode_register.load(Ordering::Acquire);
+        let current = self.settings.borrow().mode_register;
         let new_byte = (current & !0x30) | mode.to_mode_bits();
         let cmd = command::set_mode_register(new_byte);
         self.io.rt_command(cmd.to_vec()).await?;
-        self.mode_register.store(new_byte, Ordering::Release);
+        self.update_settings(|s| s.mode_register = new_byte);
         Ok(())
     }
 
-    /// Set sidetone frequency in Hz (500-4000).
-    ///
-    /// Automatically encodes for WK2 (1-10 steps) or WK3 (continuous, 62500/freq).
-    pub async fn set_sidetone(&self, freq_hz: u16) -> Result<()> {
-        if !(500..=4000).contains(&freq_hz) {
-            return Err(Error::InvalidParameter(format!(
-                "sidetone must be 500-4000 Hz, got {freq_hz}"
-            )));
-        }
-        let byte = crate::protocol::types::sidetone_byte(freq_hz, self.version);
-        let cmd = command::sidetone_control(byte);
-        self.io.rt_command(cmd.to_vec()).await
-    }
-
     /// Set sidetone volume (WK3 only). Values: 1-2 = low, 3-4 = normal/high.
     pub async fn set_sidetone_volume(&self, value: u8) -> Result<()> {
         let cmd = command::admin_set_sidetone_volume(value);
-        self.io.rt_command(cmd.to_vec()).await
+        self.io.rt_command(cmd.to_vec()).await?;
+        self.update_settings(|s| s.sidetone_volume = Some(value));
+        Ok(())
     }
 
     /// Set pin configuration register.
     pub async fn set_pin_config(&self, config: crate::PinConfig) -> Result<()> {
         let cmd = command::set_pin_config(config.bits());
-        self.io.rt_command(cmd.to_vec()).await
+        self.io.rt_command(cmd.to_vec()).await?;
+        self.update_settings(|s| s.pin_config = config);
+        Ok(())
     }
 
     /// Set PTT lead-in and tail times (in 10ms units).
     pub async fn set_ptt_timing(&self, lead_in: u8, tail: u8) -> Result<()> {
         let cmd = command::set_ptt_timing(lead_in, tail);
-        self.io.rt_command(cmd.to_vec()).await
+        self.io.rt_command(cmd.to_vec()).await?;
+        self.update_settings(|s| {
+            s.ptt_lead_in = lead_in;
+            s.ptt_tail = tail;
+        });
+        Ok(())
     }
 
-    /// Pause or resume CW output.
-    pub async fn set_pause(&self, paused: bool) -> Result<()> {
-        let cmd = command::set_pause(paused);
-        self.io.rt_command(cmd.to_vec()).await
+    /// Change the speed pot range at runtime.
+    ///
+    /// Pot readings are decoded against the new minimum from here on. The
+    /// last reading was on the old scale, so it is cleared until the keyer
+    /// reports the pot again (see [`read_speed_pot`](Self::read_speed_pot)).
+    pub async fn set_speed_pot_range(&self, min_wpm: u8, wpm_range: u8) -> Result<()> {
+        crate::builder::validate_speed_pot_range(min_wpm, wpm_range)?;
+        let cmd = command::set_speed_pot(min_wpm, wpm_range);
+        self.io.rt_command(cmd.to_vec()).await?;
+        self.io.min_wpm.store(min_wpm, Ordering::Release);
+        self.note_command(&Command::SetSpeedPot {
+            min: min_wpm,
+            range: wpm_range,
+        });
+        self.update_settings(|s| s.speed_pot_wpm = None);
+        Ok(())
+    }
+
+    /// Ask the keyer for the speed pot setting, in WPM.
+    ///
+    /// The reply is an ordinary speed pot byte, so it is also delivered as
+    /// [`KeyerEvent::SpeedPotChanged`] and goes through the speed pot policy.
+    pub async fn read_speed_pot(&self) -> Result<u8> {
+        let mut pot = self.events().speed_pot();
+        self.io.rt_command(command::get_speed_pot().to_vec()).await?;
+        match tokio::time::timeout(std::time::Duration::from_secs(2), pot.recv()).await {
+            Ok(Some(wpm)) => Ok(wpm),
+            Ok(None) => Err(Error::NotConnected),
+            Err(_) => Err(Error::Timeout),
+        }
+    }
+
+    /// Sample the speed pot for calibration: the raw A/D reading (admin
+    /// Speed A2D) alongside the speed the keyer decodes from it.
+    ///
+    /// Turn the pot end to end while sampling to check the range covers its
+    /// travel.
+    pub async fn calibrate_speed_pot(&self) -> Result<SpeedPotCalibration> {
+        let cmd = command::admin_speed_a2d();
+        let raw = self.io.rt_command_read_binary(cmd.to_vec(), 1).await?[0];
+        let wpm = self.read_speed_pot().await?;
+        let settings = self.settings.borrow();
+        Ok(SpeedPotCalibration {
+            raw,
+            wpm,
+            min_wpm: settings.min_wpm,
+            wpm_range: settings.wpm_range,
+        })
+    }
+
+    /// Set key compensation in milliseconds (0-250), added to every dit and
+    /// dah to make up for a rig that shortens them.
+    pub async fn set_key_compensation(&self, ms: u8) -> Result<()> {
+        if ms > 250 {
+            return Err(Error::InvalidParameter(format!(
+                "key compensation must be 0-250 ms, got {ms}"
+            )));
+        }
+        let cmd = command::set_key_compensation(ms);
+        self.io.rt_command(cmd.to_vec()).await?;
+        self.update_settings(|s| s.key_compensation = ms);
+        Ok(())
+    }
+
+    /// Set first extension in milliseconds (0-250), added to the first
+    /// element after the keyer has been idle so slow T/R switching doesn't
+    /// clip it.
+    pub async fn set_first_extension(&self, ms: u8) -> Result<()> {
+        if ms > 250 {
+            return Err(Error::InvalidParameter(format!(
+                "first extension must be 0-250 ms, got {ms}"
+            )));
+        }
+        let cmd = command::set_first_extension(ms);
+        self.io.rt_command(cmd.to_vec()).await?;
+        self.update_settings(|s| s.first_extension = ms);
+        Ok(())
+    }
+
+    /// Set the paddle switchpoint (10-90, default 50).
+    ///
+    /// Lower values latch the opposite paddle earlier in the space after an
+    /// element; raise it if the keyer adds unwanted elements.
+    pub async fn set_paddle_switchpoint(&self, value: u8) -> Result<()> {
+        if !(10..=90).contains(&value) {
+            return Err(Error::InvalidParameter(format!(
+                "paddle switchpoint must be 10-90, got {value}"
+            )));
+        }
+        let cmd = command::set_paddle_switchpoint(value);
+        self.io.rt_command(cmd.to_vec()).await?;
+        self.update_settings(|s| s.paddle_switchpoint = value);
+        Ok(())
+    }
+
+    /// Set key compensation and first extension for a rig with the given
+    /// T/R switching delay, at the current speed. See [`QskTuning`].
+    ///
+    /// Returns the values applied.
+    pub async fn tune_qsk(&self, qsk_delay_ms: u16) -> Result<QskTuning> {
+        let wpm = self.settings.borrow().speed_wpm;
+        let tuning = QskTuning::recommend(qsk_delay_ms, wpm);
+        self.set_key_compensation(tuning.key_compensation_ms).await?;
+        self.set_first_extension(tuning.first_extension_ms).await?;
+        Ok(tuning)
+    }
+
+    /// Switch to high-speed CW (meteor scatter) at `lpm` letters per minute.
+    ///
+    /// The keyer encodes HSCW speed in hundreds of LPM, so `lpm` must be a
+    /// whole hundred from 1000 to 8000. Calling it again changes the speed.
+    /// To switch mid-message, use `{H4000}` in a
+    /// [message template](crate::message::build_contest_message).
+    pub async fn enter_hscw(&self, lpm: u16) -> Result<()> {
+        let byte = hscw_speed_byte(lpm).ok_or_else(|| {
+            Error::InvalidParameter(format!(
+                "HSCW speed must be 1000-8000 LPM in steps of 100, got {lpm}"
+            ))
+        })?;
+        let cmd = command::set_hscw_speed(byte);
+        self.io.rt_command(cmd.to_vec()).await?;
+        self.note_command(&Command::SetHscwSpeed(byte));
+        Ok(())
+    }
+
+    /// Leave HSCW mode and return to the cached WPM speed.
+    pub async fn exit_hscw(&self) -> Result<()> {
+        let wpm = self.settings.borrow().speed_wpm;
+        let cmd = command::set_speed(wpm);
+        self.io.rt_command(cmd.to_vec()).await?;
+        self.note_command(&Command::SetSpeed(wpm));
+        Ok(())
+    }
+
+    /// HSCW speed in LPM, or `None` when sending at the normal WPM speed.
+    pub fn hscw_lpm(&self) -> Option<u16> {
+        self.settings.borrow().hscw_lpm
+    }
+
+    /// Switch to RTTY (FSK) keying. WK3.1 only.
+    ///
+    /// Text queued with [`send_rtty`](Self::send_rtty) is then sent as
+    /// Baudot through the keyer's FSK output, and echoed back as
+    /// [`KeyerEvent::CharacterSent`] like CW.
+    pub async fn enter_rtty(&self, config: RttyConfig) -> Result<()> {
+        if self.version != WinKeyerVersion::Wk31 {
+            return Err(Error::Unsupported(format!(
+                "RTTY mode needs WinKeyer 3.1 firmware, found {:?}",
+                self.version
+            )));
+        }
+        let (p1, p2) = config.registers();
+        let cmd = command::admin_set_rtty_registers(p1, p2);
+        self.io.rt_command(cmd.to_vec()).await?;
+        self.note_command(&Command::SetRttyRegisters(p1, p2));
+        Ok(())
+    }
+
+    /// Return to CW keying.
+    pub async fn exit_rtty(&self) -> Result<()> {
+        if self.settings.borrow().rtty.is_none() {
+            return Ok(());
+        }
+        let cmd = command::admin_set_rtty_registers(0, 0);
+        self.io.rt_command(cmd.to_vec()).await?;
+        self.note_command(&Command::SetRttyRegisters(0, 0));
+        Ok(())
+    }
+
+    /// Queue text for RTTY transmission.
+    ///
+    /// The keyer adds LTRS/FIGS shifts itself. Text is checked against the
+    /// ITA2 character set first (see [`validate_rtty_text`]).
+    pub async fn send_rtty(&self, text: &str) -> Result<()> {
+        if self.settings.borrow().rtty.is_none() {
+            return Err(Error::Protocol(
+                "not in RTTY mode; call enter_rtty first".to_string(),
+            ));
+        }
+        validate_rtty_text(text).map_err(Error::InvalidParameter)?;
+        self.wait_xoff().await?;
+        self.io.bg_command(command::encode_text(text)).await
     }
 
     /// Insert a timed wait into the buffer (seconds).
@@ -178,12 +397,101 @@ impl WinKeyer {
         Ok(response[0])
     }
 
+    /// Read the paddle input A/D value (admin Paddle A2D).
+    pub async fn read_paddle_a2d(&self) -> Result<u8> {
+        let cmd = command::admin_paddle_a2d();
+        let response = self.io.rt_command_read_binary(cmd.to_vec(), 1).await?;
+        Ok(response[0])
+    }
+
+    /// Read the paddle A/D value `count` times, `interval` apart. Hold one
+    /// paddle closed while sampling to check that contact.
+    pub async fn sample_paddle_a2d(
+        &self,
+        count: usize,
+        interval: std::time::Duration,
+    ) -> Result<PaddleA2dSamples> {
+        let start = tokio::time::Instant::now();
+        let mut samples = PaddleA2dSamples::default();
+        for i in 0..count {
+            if i > 0 {
+                tokio::time::sleep_until(start + interval * i as u32).await;
+            }
+            let value = self.read_paddle_a2d().await?;
+            samples.trace.push((start.elapsed(), value));
+        }
+        Ok(samples)
+    }
+
     /// Load defaults (15-parameter block).
     pub async fn load_defaults(&self, defaults: &crate::LoadDefaults) -> Result<()> {
         let cmd = command::load_defaults(defaults);
         self.io.rt_command(cmd.to_vec()).await?;
-        self.mode_register
-            .store(defaults.mode_register, Ordering::Release);
+        self.io.min_wpm.store(defaults.min_wpm, Ordering::Release);
+        self.note_command(&Command::LoadDefaults(defaults.clone()));
+        Ok(())
+    }
+
+    /// Change several settings as one transaction.
+    ///
+    /// Only settings that differ from the cached ones are sent, as a single
+    /// real-time write (or one Load Defaults block when that is shorter,
+    /// followed by the same mode register, pin config and sidetone commands
+    /// as [`apply_profile`](Self::apply_profile)).
+    /// Everything is validated before anything is sent, and the settings
+    /// snapshot is left untouched if the write fails.
+    ///
+    /// ```no_run
+    /// # async fn example(keyer: &winkey::WinKeyer) -> winkey::Result<()> {
+    /// use winkey::PaddleMode;
+    ///
+    /// keyer
+    ///     .configure(|c| c.speed(18).weight(55).paddle_mode(PaddleMode::IambicA))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn configure(
+        &self,
+        build: impl FnOnce(SettingsChange) -> SettingsChange,
+    ) -> Result<()> {
+        let change = build(SettingsChange::new());
+        let current = self.settings();
+        let mut target = current.clone();
+        change.apply(&mut target);
+        target.validate()?;
+
+        let bytes = current.update_bytes(&target, self.version);
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        debug!("configure: {} bytes {:02X?}", bytes.len(), bytes);
+        self.io.rt_command(bytes).await?;
+        self.io.min_wpm.store(target.min_wpm, Ordering::Release);
+        self.update_settings(|s| change.apply(s));
+        Ok(())
+    }
+
+    /// Switch to all settings in `profile`.
+    ///
+    /// The profile is validated with the builder's rules first, then sent as
+    /// a Load Defaults block with the same follow-up commands the builder
+    /// uses at connect time.
+    pub async fn apply_profile(&self, profile: &crate::KeyerProfile) -> Result<()> {
+        profile.validate()?;
+        let current = self.settings.borrow().mode_register;
+        let defaults = profile.load_defaults(self.version, current);
+        if self.version.supports_wk3() {
+            let cmd = command::admin_load_x2mode(defaults.x2_mode);
+            self.io.rt_command(cmd.to_vec()).await?;
+        }
+        self.load_defaults(&defaults).await?;
+        self.io.rt_command(load_defaults_followup(&defaults)).await?;
+        if profile.first_extension_ms != self.settings.borrow().first_extension {
+            self.set_first_extension(profile.first_extension_ms).await?;
+        }
+        // Keep the exact frequency rather than the one decoded from the byte.
+        self.update_settings(|s| s.sidetone_hz = profile.sidetone_hz);
         Ok(())
     }
 
@@ -202,6 +510,20 @@ impl WinKeyer {
     // Internal helpers
     // ------------------------------------------------------------------
 
+    /// Change the settings snapshot, notifying watchers only on a change.
+    pub(crate) fn update_settings(&self, f: impl FnOnce(&mut KeyerSettings)) {
+        self.settings.send_if_modified(|s| {
+            let before = s.clone();
+            f(s);
+            *s != before
+        });
+    }
+
+    /// Record the effect of a host command sent on the keyer's behalf.
+    pub(crate) fn note_command(&self, cmd: &Command) {
+        self.update_settings(|s| s.apply_command(cmd, self.version));
+    }
+
     /// Wait for XOFF to clear, with a timeout.
     async fn wait_xoff(&self) -> Result<()> {
         if !self.io.xoff.load(Ordering::Acquire) {
@@ -209,20 +531,21 @@ impl WinKeyer {
         }
 
         debug!("XOFF active, waiting for buffer space...");
-        let mut rx = self.event_tx.subscribe();
+        let mut events = self.events();
         let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(10);
 
         loop {
             if !self.io.xoff.load(Ordering::Acquire) {
                 return Ok(());
             }
-            match tokio::time::timeout_at(deadline, rx.recv()).await {
-                Ok(Ok(KeyerEvent::StatusChanged(status))) if !status.xoff => {
+            match tokio::time::timeout_at(deadline, events.recv()).await {
+                Ok(Some(KeyerEvent::StatusChanged(status))) if !status.xoff => {
                     return Ok(());
                 }
-                Ok(Ok(_)) => continue,
-                Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
-                Ok(Err(broadcast::error::RecvError::Closed)) => return Err(Error::NotConnected),
+                // Includes EventsDropped: a missed XOFF clear is caught by
+                // the flag check above on the next pass
+                Ok(Some(_)) => continue,
+                Ok(None) => return Err(Error::NotConnected),
                 Err(_) => return Err(Error::BufferFull),
             }
         }
@@ -259,12 +582,12 @@ impl Keyer for WinKeyer {
         }
         let cmd = command::set_speed(wpm);
         self.io.rt_command(cmd.to_vec()).await?;
-        self.speed.store(wpm, Ordering::Release);
+        self.update_settings(|s| s.speed_wpm = wpm);
         Ok(())
     }
 
     async fn get_speed(&self) -> Result<u8> {
-        Ok(self.speed.load(Ordering::Acquire))
+        Ok(self.settings.borrow().speed_wpm)
     }
 
     async fn set_tune(&self, on: bool) -> Result<()> {
@@ -272,13 +595,15 @@ impl Keyer for WinKeyer {
         self.io.rt_command(cmd.to_vec()).await
     }
 
+    /// Queue a PTT change in the keyer buffer, so it takes effect in order
+    /// with text already queued. WinKeyer has no immediate PTT command.
     async fn set_ptt(&self, on: bool) -> Result<()> {
-        // Use buffered PTT for sequenced operation
+        self.wait_xoff().await?;
         let cmd = command::buffered_ptt(on);
-        self.io.rt_command(cmd.to_vec()).await
+        self.io.bg_command(cmd.to_vec()).await
     }
 
-    fn subscribe(&self) -> broadcast::Receiver<KeyerEvent> {
+    fn subscribe(&self) -> EventReceiver {
         self.event_tx.subscribe()
     }
 
@@ -288,12 +613,79 @@ impl Keyer for WinKeyer {
         let _ = self.io.rt_command(cmd.to_vec()).await;
         self.io.shutdown().await
     }
+
+    /// Send a prosign (merged letters) via the buffer.
+    async fn send_prosign(&self, c1: u8, c2: u8) -> Result<()> {
+        self.wait_xoff().await?;
+        let cmd = command::buffered_merge(c1, c2);
+        self.io.bg_command(cmd.to_vec()).await
+    }
+
+    /// Set buffered speed change (takes effect in-buffer).
+    async fn set_buffered_speed(&self, wpm: u8) -> Result<()> {
+        self.wait_xoff().await?;
+        let cmd = command::buffered_speed_change(wpm);
+        self.io.bg_command(cmd.to_vec()).await
+    }
+
+    /// Cancel buffered speed change (restore original speed).
+    async fn cancel_buffered_speed(&self) -> Result<()> {
+        self.wait_xoff().await?;
+        let cmd = command::cancel_buffered_speed();
+        self.io.bg_command(cmd.to_vec()).await
+    }
+
+    /// Pause or resume CW output.
+    async fn set_pause(&self, paused: bool) -> Result<()> {
+        let cmd = command::set_pause(paused);
+        self.io.rt_command(cmd.to_vec()).await?;
+        self.update_settings(|s| s.paused = paused);
+        Ok(())
+    }
+
+    /// Set keying weight (10-90, default 50).
+    async fn set_weight(&self, weight: u8) -> Result<()> {
+        if !(10..=90).contains(&weight) {
+            return Err(Error::InvalidParameter(format!(
+                "weight must be 10-90, got {weight}"
+            )));
+        }
+        let cmd = command::set_weight(weight);
+        self.io.rt_command(cmd.to_vec()).await?;
+        self.update_settings(|s| s.weight = weight);
+        Ok(())
+    }
+
+    /// Set Farnsworth speed (0 = disable).
+    async fn set_farnsworth(&self, wpm: u8) -> Result<()> {
+        let cmd = command::set_farnsworth(wpm);
+        self.io.rt_command(cmd.to_vec()).await?;
+        self.update_settings(|s| s.farnsworth_wpm = wpm);
+        Ok(())
+    }
+
+    /// Set sidetone frequency in Hz (500-4000).
+    ///
+    /// Automatically encodes for WK2 (1-10 steps) or WK3 (continuous, 62500/freq).
+    async fn set_sidetone(&self, freq_hz: u16) -> Result<()> {
+        if !(500..=4000).contains(&freq_hz) {
+            return Err(Error::InvalidParameter(format!(
+                "sidetone must be 500-4000 Hz, got {freq_hz}"
+            )));
+        }
+        let byte = crate::protocol::types::sidetone_byte(freq_hz, self.version);
+        let cmd = command::sidetone_control(byte);
+        self.io.rt_command(cmd.to_vec()).await?;
+        self.update_settings(|s| s.sidetone_hz = freq_hz);
+        Ok(())
+    }
 }
 
 impl Drop for WinKeyer {
     fn drop(&mut self) {
         self.io.cancel.cancel();
         self.io.task.abort();
+        self.settings_task.abort();
     }
 }
 
@@ -301,7 +693,6 @@ impl Drop for WinKeyer {
 mod tests {
     use super::*;
     use std::sync::atomic::AtomicU8;
-    use std::sync::Arc;
 
     use crate::event::KeyerStatus;
     use crate::io::{IoHandle, Request};
@@ -333,7 +724,7 @@ mod tests {
             }
         });
 
-        let (event_tx, _) = broadcast::channel::<KeyerEvent>(cap);
+        let (event_tx, _) = crate::event::channel(cap);
 
         WinKeyer {
             io: IoHandle {
@@ -342,6 +733,7 @@ mod tests {
                 cancel,
                 task,
                 xoff,
+                min_wpm: Arc::new(AtomicU8::new(10)),
             },
             info: KeyerInfo {
                 name: "test".into(),
@@ -351,8 +743,13 @@ mod tests {
             capabilities: KeyerCapabilities::default(),
             version: WinKeyerVersion::Wk2,
             event_tx,
-            speed: AtomicU8::new(20),
-            mode_register: AtomicU8::new(0x44),
+            settings: watch::channel(KeyerSettings::from_load_defaults(
+                &crate::LoadDefaults::default(),
+                WinKeyerVersion::Wk2,
+            ))
+            .0,
+            settings_task: tokio::spawn(async {}),
+            speed_pot: Arc::new(Mutex::new(PotFollower::default())),
         }
     }
 
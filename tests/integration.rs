@@ -3,7 +3,7 @@
 use std::time::Duration;
 
 use winkey::{
-    Keyer, KeyerEvent, LoadDefaults, MockPort, ModeRegister, PaddleMode, WinKeyerBuilder,
+    EchoSource, Keyer, KeyerEvent, LoadDefaults, MockPort, ModeRegister, PaddleMode, WinKeyerBuilder,
     WinKeyerVersion,
 };
 
@@ -63,13 +63,19 @@ async fn send_message_and_receive_echo() {
         .await
         .unwrap()
         .unwrap();
-    assert!(matches!(ev1, KeyerEvent::CharacterSent('C')));
+    assert!(matches!(
+        ev1,
+        KeyerEvent::CharacterSent { ch: 'C', source: EchoSource::Host }
+    ));
 
     let ev2 = tokio::time::timeout(Duration::from_millis(200), rx.recv())
         .await
         .unwrap()
         .unwrap();
-    assert!(matches!(ev2, KeyerEvent::CharacterSent('Q')));
+    assert!(matches!(
+        ev2,
+        KeyerEvent::CharacterSent { ch: 'Q', source: EchoSource::Host }
+    ));
 
     keyer.close().await.unwrap();
 }
@@ -343,3 +349,795 @@ async fn echo_test_high_byte() {
 
     keyer.close().await.unwrap();
 }
+
+#[tokio::test]
+async fn optional_trait_methods_through_dyn_keyer() {
+    let mock = mock_wk(23);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+    let dyn_keyer: &dyn Keyer = &keyer;
+    assert!(dyn_keyer.capabilities().prosigns);
+    assert!(dyn_keyer.capabilities().buffered_speed);
+
+    dyn_keyer.set_buffered_speed(15).await.unwrap();
+    dyn_keyer.send_prosign(b'A', b'R').await.unwrap();
+    dyn_keyer.cancel_buffered_speed().await.unwrap();
+    dyn_keyer.set_weight(55).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let written = mock.written_data();
+    assert!(written.windows(2).any(|w| w == [0x1C, 15]));
+    assert!(written.windows(3).any(|w| w == [0x1B, b'A', b'R']));
+    assert!(written.contains(&0x1E));
+    assert!(written.windows(2).any(|w| w == [0x03, 55]));
+
+    keyer.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn decoded_writes_match_api_calls() {
+    use winkey::protocol::decoder::{decode_commands, Command};
+
+    let mock = mock_wk(23);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .speed(22)
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+    let setup = mock.written_data().len();
+
+    keyer.set_speed(30).await.unwrap();
+    keyer.send_message("cq").await.unwrap();
+    keyer.send_prosign(b'A', b'R').await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    assert_eq!(
+        decode_commands(&mock.written_data()[setup..]),
+        vec![
+            Command::SetSpeed(30),
+            Command::Text("CQ".into()),
+            Command::Merge(b'A', b'R'),
+        ]
+    );
+
+    keyer.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn captured_session_replays() {
+    let path = std::env::temp_dir().join(format!("winkey-session-{}.txt", std::process::id()));
+
+    let mock = mock_wk(31);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .capture(&path)
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+    keyer.send_message("CQ").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    mock.queue_read(b"CQ");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    keyer.close().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let capture = winkey::capture::Capture::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(capture.bytes(winkey::capture::Direction::Host), mock.written_data());
+
+    // Same API calls against the replayed keyer side
+    let replay = winkey::capture::ReplayPort::new(&capture);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .build_with_port(replay.clone())
+        .await
+        .unwrap();
+    assert_eq!(keyer.version(), WinKeyerVersion::Wk31);
+    let mut rx = keyer.subscribe();
+    keyer.send_message("CQ").await.unwrap();
+
+    let mut echoed = String::new();
+    while echoed.len() < 2 {
+        match tokio::time::timeout(Duration::from_secs(1), rx.recv()).await {
+            Ok(Ok(KeyerEvent::CharacterSent { ch, .. })) => echoed.push(ch),
+            Ok(Ok(_)) => {}
+            other => panic!("no echo from replay: {other:?}"),
+        }
+    }
+    assert_eq!(echoed, "CQ");
+    assert!(replay.is_finished());
+    keyer.close().await.unwrap();
+    assert_eq!(replay.first_mismatch(), None);
+}
+
+#[tokio::test]
+async fn profile_at_connect_and_at_runtime() {
+    use winkey::KeyerProfile;
+    use winkey::protocol::decoder::{decode_commands, Command};
+
+    let contest = KeyerProfile {
+        speed_wpm: 32,
+        paddle_mode: PaddleMode::IambicA,
+        contest_spacing: true,
+        ..Default::default()
+    };
+    let mock = mock_wk(31);
+    let keyer = WinKeyerBuilder::from_profile("/dev/ttyUSB0", &contest)
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+    assert_eq!(keyer.get_speed().await.unwrap(), 32);
+    let setup = decode_commands(&mock.written_data());
+    let defaults = setup
+        .iter()
+        .find_map(|c| match c {
+            Command::LoadDefaults(d) => Some(d.clone()),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(defaults.speed_wpm, 32);
+    assert_eq!(defaults.mode_register, 0x40 | 0x04 | 0x10 | 0x01);
+
+    let ragchew = KeyerProfile {
+        speed_wpm: 18,
+        farnsworth_wpm: 12,
+        ..Default::default()
+    };
+    let before = mock.written_data().len();
+    keyer.apply_profile(&ragchew).await.unwrap();
+    let sent = decode_commands(&mock.written_data()[before..]);
+    assert_eq!(sent[0], Command::LoadX2Mode(0));
+    match &sent[1] {
+        Command::LoadDefaults(d) => {
+            assert_eq!(d.speed_wpm, 18);
+            assert_eq!(d.farnsworth_wpm, 12);
+            assert_eq!(d.mode_register, 0x40 | 0x04);
+        }
+        other => panic!("expected load defaults, got {other:?}"),
+    }
+    assert_eq!(sent[2], Command::SetModeRegister(0x44));
+    assert_eq!(keyer.get_speed().await.unwrap(), 18);
+
+    // Rejected before anything is sent
+    let before = mock.written_data().len();
+    let bad = KeyerProfile {
+        weight: 95,
+        ..Default::default()
+    };
+    assert!(keyer.apply_profile(&bad).await.is_err());
+    assert_eq!(mock.written_data().len(), before);
+}
+
+#[tokio::test]
+async fn settings_snapshot_follows_setters_and_pot() {
+    let mock = mock_wk(30);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .speed(22)
+        .sidetone(650)
+        .min_wpm(15)
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+    let initial = keyer.settings();
+    assert_eq!(initial.speed_wpm, 22);
+    assert_eq!(initial.sidetone_hz, 650);
+    assert_eq!(initial.paddle_mode(), PaddleMode::IambicB);
+    assert_eq!(initial.speed_pot_wpm, None);
+
+    let mut rx = keyer.watch_settings();
+    keyer.set_weight(60).await.unwrap();
+    keyer.set_paddle_mode(PaddleMode::Ultimatic).await.unwrap();
+    keyer.set_ptt_timing(4, 2).await.unwrap();
+    assert!(rx.has_changed().unwrap());
+    let settings = rx.borrow_and_update().clone();
+    assert_eq!(settings.weight, 60);
+    assert_eq!(settings.paddle_mode(), PaddleMode::Ultimatic);
+    assert_eq!((settings.ptt_lead_in, settings.ptt_tail), (4, 2));
+
+    // Failed validation leaves the snapshot alone
+    assert!(keyer.set_weight(95).await.is_err());
+    assert!(!rx.has_changed().unwrap());
+
+    mock.queue_read(&[0x85]);
+    tokio::time::timeout(Duration::from_secs(1), rx.changed())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(rx.borrow().speed_pot_wpm, Some(20));
+    assert_eq!(keyer.get_speed().await.unwrap(), 22);
+
+    // A new pot range clears the reading taken on the old scale
+    keyer.configure(|c| c.weight(55)).await.unwrap();
+    assert_eq!(keyer.settings().speed_pot_wpm, Some(20));
+    keyer.configure(|c| c.speed_pot(12, 20)).await.unwrap();
+    assert_eq!(keyer.settings().speed_pot_wpm, None);
+
+    mock.queue_read(&[0x85]);
+    tokio::time::timeout(Duration::from_secs(1), rx.wait_for(|s| s.speed_pot_wpm.is_some()))
+        .await
+        .unwrap()
+        .unwrap();
+    let profile = winkey::KeyerProfile {
+        min_wpm: 18,
+        ..Default::default()
+    };
+    keyer.apply_profile(&profile).await.unwrap();
+    assert_eq!(keyer.settings().speed_pot_wpm, None);
+}
+
+/// Wait until the settings snapshot shows a speed pot reading of `wpm`.
+async fn wait_for_pot(rx: &mut tokio::sync::watch::Receiver<winkey::KeyerSettings>, wpm: u8) {
+    tokio::time::timeout(
+        Duration::from_secs(1),
+        rx.wait_for(|s| s.speed_pot_wpm == Some(wpm)),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+}
+
+#[tokio::test]
+async fn speed_pot_policy_drives_speed() {
+    use winkey::protocol::decoder::{decode_commands, Command};
+    use winkey::SpeedPotPolicy;
+
+    let mock = mock_wk(23);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .speed(22)
+        .min_wpm(10)
+        .speed_pot_policy(SpeedPotPolicy::direct().hysteresis(1))
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+    let mut rx = keyer.watch_settings();
+
+    let before = mock.written_data().len();
+    mock.queue_read(&[0x85]);
+    wait_for_pot(&mut rx, 15).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(keyer.get_speed().await.unwrap(), 15);
+
+    // One step of jitter is ignored
+    mock.queue_read(&[0x86]);
+    wait_for_pot(&mut rx, 16).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(keyer.get_speed().await.unwrap(), 15);
+    let sent = decode_commands(&mock.written_data()[before..]);
+    assert_eq!(sent, [Command::SetSpeed(15)]);
+
+    // Switching to offset mode applies the last reading at once:
+    // pot 16 is 6 below the centre of 10..=35
+    keyer.set_speed_pot_policy(SpeedPotPolicy::offset(30)).await.unwrap();
+    assert_eq!(keyer.get_speed().await.unwrap(), 24);
+
+    keyer.set_speed_pot_policy(SpeedPotPolicy::ignore()).await.unwrap();
+    mock.queue_read(&[0x94]);
+    wait_for_pot(&mut rx, 30).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(keyer.get_speed().await.unwrap(), 24);
+}
+
+/// Queue `reply` once the keyer has written `trigger` after offset `from`.
+fn reply_after(mock: &MockPort, from: usize, trigger: &'static [u8], reply: &'static [u8]) {
+    let mock = mock.clone();
+    tokio::spawn(async move {
+        loop {
+            let written = mock.written_data();
+            let written = written.get(from..).unwrap_or_default();
+            if written.windows(trigger.len()).any(|w| w == trigger) {
+                mock.queue_read(reply);
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    });
+}
+
+#[tokio::test]
+async fn speed_pot_range_read_and_calibrate() {
+    use winkey::protocol::decoder::{decode_commands, Command};
+
+    let mock = mock_wk(23);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+
+    assert!(keyer.set_speed_pot_range(80, 30).await.is_err());
+    let before = mock.written_data().len();
+    keyer.set_speed_pot_range(15, 20).await.unwrap();
+    assert_eq!(
+        decode_commands(&mock.written_data()[before..]),
+        [Command::SetSpeedPot { min: 15, range: 20 }]
+    );
+    let settings = keyer.settings();
+    assert_eq!((settings.min_wpm, settings.wpm_range), (15, 20));
+
+    // Pot value 5 is now decoded against the new minimum
+    let before = mock.written_data().len();
+    reply_after(&mock, before, &[0x07], &[0x85]);
+    assert_eq!(keyer.read_speed_pot().await.unwrap(), 20);
+    assert_eq!(keyer.settings().speed_pot_wpm, Some(20));
+
+    let before = mock.written_data().len();
+    reply_after(&mock, before, &[0x00, 0x06], &[0x66]);
+    reply_after(&mock, before, &[0x07], &[0x8A]);
+    let cal = keyer.calibrate_speed_pot().await.unwrap();
+    assert_eq!((cal.raw, cal.wpm), (0x66, 25));
+    assert_eq!((cal.min_wpm, cal.wpm_range), (15, 20));
+    assert_eq!(cal.range_position(), 0.5);
+}
+
+#[tokio::test]
+async fn paddle_timing_at_connect_and_runtime() {
+    use winkey::protocol::decoder::{decode_commands, Command};
+
+    let mock = mock_wk(23);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .speed(20)
+        .key_compensation(6)
+        .first_extension(30)
+        .paddle_switchpoint(40)
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+
+    let sent = decode_commands(&mock.written_data());
+    let defaults = sent
+        .iter()
+        .find_map(|c| match c {
+            Command::LoadDefaults(d) => Some(d.clone()),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!((defaults.key_compensation, defaults.paddle_setpoint), (6, 40));
+    assert!(sent.contains(&Command::SetFirstExtension(30)));
+    let settings = keyer.settings();
+    assert_eq!(
+        (settings.key_compensation, settings.first_extension, settings.paddle_switchpoint),
+        (6, 30, 40)
+    );
+
+    assert!(keyer.set_paddle_switchpoint(95).await.is_err());
+    assert!(keyer.set_key_compensation(251).await.is_err());
+
+    let before = mock.written_data().len();
+    keyer.set_paddle_switchpoint(55).await.unwrap();
+    let tuning = keyer.tune_qsk(40).await.unwrap();
+    assert_eq!((tuning.key_compensation_ms, tuning.first_extension_ms), (15, 25));
+    assert_eq!(
+        decode_commands(&mock.written_data()[before..]),
+        [
+            Command::SetPaddleSwitchpoint(55),
+            Command::SetKeyCompensation(15),
+            Command::SetFirstExtension(25),
+        ]
+    );
+    let settings = keyer.settings();
+    assert_eq!(
+        (settings.key_compensation, settings.first_extension, settings.paddle_switchpoint),
+        (15, 25, 55)
+    );
+
+    assert!(
+        WinKeyerBuilder::new("/dev/ttyUSB0")
+            .paddle_switchpoint(5)
+            .build_with_port(MockPort::new())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn hscw_enter_and_exit() {
+    use winkey::protocol::decoder::{decode_commands, Command};
+
+    let mock = mock_wk(23);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .speed(22)
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+
+    assert!(keyer.enter_hscw(4050).await.is_err());
+    assert!(keyer.enter_hscw(9000).await.is_err());
+    assert_eq!(keyer.hscw_lpm(), None);
+
+    let before = mock.written_data().len();
+    keyer.enter_hscw(4000).await.unwrap();
+    assert_eq!(keyer.hscw_lpm(), Some(4000));
+    keyer.exit_hscw().await.unwrap();
+    assert_eq!(keyer.hscw_lpm(), None);
+    assert_eq!(
+        decode_commands(&mock.written_data()[before..]),
+        [Command::SetHscwSpeed(40), Command::SetSpeed(22)]
+    );
+    assert_eq!(keyer.get_speed().await.unwrap(), 22);
+}
+
+#[tokio::test]
+async fn keying_monitor_refuses_winkeyer() {
+    use winkey::KeyingMonitor;
+
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .build_with_port(mock_wk(23))
+        .await
+        .unwrap();
+    assert!(!keyer.capabilities().key_state);
+    assert!(matches!(
+        KeyingMonitor::start(&keyer),
+        Err(winkey::Error::Unsupported(_))
+    ));
+}
+
+#[tokio::test]
+async fn rtty_mode_on_wk31() {
+    use winkey::protocol::decoder::{decode_commands, Command};
+    use winkey::{RttyBaud, RttyConfig};
+
+    let wk2 = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .build_with_port(mock_wk(23))
+        .await
+        .unwrap();
+    assert!(matches!(
+        wk2.enter_rtty(RttyConfig::default()).await,
+        Err(winkey::Error::Unsupported(_))
+    ));
+
+    let mock = mock_wk(31);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+    assert!(keyer.send_rtty("RYRY").await.is_err());
+
+    let config = RttyConfig {
+        baud: RttyBaud::Baud50,
+        ..RttyConfig::default()
+    };
+    let mut rx = keyer.subscribe();
+    let before = mock.written_data().len();
+    keyer.enter_rtty(config).await.unwrap();
+    assert_eq!(keyer.settings().rtty, Some(config));
+    assert!(keyer.send_rtty("cq\n").await.is_err());
+    keyer.send_rtty("de k1el #5").await.unwrap();
+    keyer.exit_rtty().await.unwrap();
+    assert_eq!(keyer.settings().rtty, None);
+
+    let sent = decode_commands(&mock.written_data()[before..]);
+    assert_eq!(sent[0], Command::SetRttyRegisters(0x91, 0x01));
+    assert_eq!(sent.last(), Some(&Command::SetRttyRegisters(0, 0)));
+    let text: String = sent
+        .iter()
+        .filter_map(|c| match c {
+            Command::Text(t) => Some(t.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(text, "DE K1EL #5");
+
+    // Echo comes back as characters, same as CW
+    mock.queue_read(b"DE");
+    let ev = tokio::time::timeout(Duration::from_millis(500), rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        ev,
+        KeyerEvent::CharacterSent { ch: 'D', source: EchoSource::Host }
+    ));
+}
+
+#[tokio::test]
+async fn paddle_a2d_sampling() {
+    let mock = mock_wk(23);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+
+    // Requests are two bytes each and sent one at a time, so request `i`
+    // starts at `start + 2 * i`; answer each with the next reading.
+    let start = mock.written_data().len();
+    let readings: [&'static [u8]; 4] = [&[20], &[21], &[180], &[20]];
+    for (i, reading) in readings.into_iter().enumerate() {
+        reply_after(&mock, start + 2 * i, &[0x00, 0x05], reading);
+    }
+
+    let samples = keyer
+        .sample_paddle_a2d(4, Duration::from_millis(10))
+        .await
+        .unwrap();
+    let values: Vec<u8> = samples.trace.iter().map(|&(_, v)| v).collect();
+    assert_eq!(values, [20, 21, 180, 20]);
+    assert!(samples.trace.windows(2).all(|w| w[0].0 < w[1].0));
+    assert_eq!(samples.crossings(0x80), 2);
+    assert!(samples.is_noisy(10));
+}
+
+#[tokio::test]
+async fn ptt_is_queued_with_text() {
+    use winkey::message::build_contest_message;
+    use winkey::protocol::decoder::{decode_commands, Command};
+
+    let mock = mock_wk(23);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+
+    let before = mock.written_data().len();
+    keyer.set_ptt(true).await.unwrap();
+    keyer.send_message("VVV").await.unwrap();
+    keyer.set_ptt(false).await.unwrap();
+    keyer
+        .raw_write(&build_contest_message("{P1}{K3}{W2}{P0}"))
+        .await
+        .unwrap();
+    assert_eq!(
+        decode_commands(&mock.written_data()[before..]),
+        [
+            Command::BufferedPtt(true),
+            Command::Text("VVV".into()),
+            Command::BufferedPtt(false),
+            Command::BufferedPtt(true),
+            Command::KeyBuffered(3),
+            Command::BufferedWait(2),
+            Command::BufferedPtt(false),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn configure_sends_minimal_batch() {
+    use winkey::protocol::decoder::{decode_commands, Command};
+
+    let mock = mock_wk(31);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .speed(20)
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+
+    let before = mock.written_data().len();
+    keyer
+        .configure(|c| c.speed(26).weight(50).paddle_mode(PaddleMode::IambicA))
+        .await
+        .unwrap();
+    let sent = decode_commands(&mock.written_data()[before..]);
+    assert_eq!(sent, [Command::SetModeRegister(0x54), Command::SetSpeed(26)]);
+    assert_eq!(keyer.settings().paddle_mode(), PaddleMode::IambicA);
+
+    // Nothing to do
+    let before = mock.written_data().len();
+    keyer.configure(|c| c.speed(26)).await.unwrap();
+    assert_eq!(mock.written_data().len(), before);
+
+    // Many changes collapse into one Load Defaults
+    keyer
+        .configure(|c| {
+            c.speed(18)
+                .weight(55)
+                .dit_dah_ratio(45)
+                .farnsworth(12)
+                .ptt_timing(3, 2)
+                .speed_pot(8, 30)
+                .sidetone(600)
+        })
+        .await
+        .unwrap();
+    let sent = decode_commands(&mock.written_data()[before..]);
+    assert_eq!(sent.len(), 4);
+    let Command::LoadDefaults(d) = &sent[0] else {
+        panic!("expected Load Defaults, got {:?}", sent[0]);
+    };
+    assert_eq!((d.speed_wpm, d.min_wpm), (18, 8));
+    // Followed by what apply_profile re-asserts
+    assert_eq!(
+        sent[1..],
+        [
+            Command::SetModeRegister(d.mode_register),
+            Command::SetPinConfig(d.pin_config),
+            Command::SidetoneControl(d.sidetone),
+        ]
+    );
+
+    // Invalid: nothing sent, nothing cached
+    let snapshot = keyer.settings();
+    let before = mock.written_data().len();
+    assert!(keyer.configure(|c| c.speed(30).weight(5)).await.is_err());
+    assert_eq!(mock.written_data().len(), before);
+    assert_eq!(keyer.settings(), snapshot);
+
+    // Failed write: cache rolled back
+    mock.close();
+    assert!(keyer.configure(|c| c.speed(40)).await.is_err());
+    assert_eq!(keyer.settings(), snapshot);
+}
+
+#[tokio::test]
+async fn beacon_reports_cycles_and_survives_failures() {
+    use std::sync::Arc;
+    use winkey::protocol::decoder::{decode_commands, Command};
+    use winkey::{Beacon, BeaconConfig, BeaconEvent, BeaconSchedule};
+
+    let mock = mock_wk(23);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+    let before = mock.written_data().len();
+
+    let config = BeaconConfig::new("VVV DE K1EL", BeaconSchedule::every(Duration::from_millis(100)))
+        .ptt(true);
+    let beacon = Beacon::start(Arc::new(keyer), config).unwrap();
+    let mut rx = beacon.subscribe();
+
+    loop {
+        let event = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        if event == (BeaconEvent::CycleSent { cycle: 1 }) {
+            break;
+        }
+    }
+    assert_eq!(beacon.cycles_sent(), 1);
+    assert_eq!(
+        decode_commands(&mock.written_data()[before..])[..3],
+        [
+            Command::BufferedPtt(true),
+            Command::Text("VVV DE K1EL".into()),
+            Command::BufferedPtt(false),
+        ]
+    );
+
+    // Unplugged: cycles keep coming and fail instead of stopping the beacon
+    mock.close();
+    let mut failed = 0;
+    while failed < 2 {
+        let event = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        if let BeaconEvent::CycleFailed { .. } = event {
+            failed += 1;
+        }
+    }
+    assert_eq!(beacon.cycles_sent(), 1);
+}
+
+#[tokio::test]
+async fn paddle_echo_told_apart_from_host_echo() {
+    let mock = mock_wk(23);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+    let mut rx = keyer.subscribe();
+
+    keyer.send_message("CQ TEST").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // "C" goes out, then the operator breaks in with "K1EL W1AW" and lets go
+    mock.queue_read(b"C");
+    mock.queue_read(&[0xC6]);
+    mock.queue_read(b"K1EL W1AW");
+    mock.queue_read(&[0xC0]);
+
+    let mut chars = Vec::new();
+    let mut words = Vec::new();
+    while words.len() < 2 {
+        match tokio::time::timeout(Duration::from_millis(500), rx.recv())
+            .await
+            .unwrap()
+            .unwrap()
+        {
+            KeyerEvent::CharacterSent { ch, source } => chars.push((ch, source)),
+            KeyerEvent::PaddleWord(word) => words.push(word),
+            _ => {}
+        }
+    }
+    assert_eq!(chars[0], ('C', EchoSource::Host));
+    assert!(chars[1..].iter().all(|&(_, source)| source == EchoSource::Paddle));
+    assert_eq!(words, ["K1EL", "W1AW"]);
+
+    // Host text after the break-in is recognised again
+    keyer.send_message("TU").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    mock.queue_read(b"T");
+    loop {
+        let ev = tokio::time::timeout(Duration::from_millis(500), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        if let KeyerEvent::CharacterSent { ch, source } = ev {
+            assert_eq!((ch, source), ('T', EchoSource::Host));
+            break;
+        }
+    }
+}
+
+#[tokio::test]
+async fn events_are_sequenced_and_lag_reported() {
+    let mock = mock_wk(23);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+    let mut rx = keyer.subscribe();
+
+    // More speed pot bytes than the event channel holds, unread
+    let burst: Vec<u8> = (0..300).map(|i| 0x80 | (i % 32) as u8).collect();
+    mock.queue_read(&burst);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let first = rx.recv_timed().await.unwrap();
+    let KeyerEvent::EventsDropped { count } = first.event else {
+        panic!("expected EventsDropped, got {:?}", first.event);
+    };
+    assert!(count > 0);
+
+    let mut last = rx.recv_timed().await.unwrap();
+    assert_eq!(last.seq, first.seq + count);
+    let mut received = count + 1;
+    while let Ok(timed) = rx.try_recv_timed() {
+        assert_eq!(timed.seq, last.seq + 1);
+        assert!(timed.at >= last.at);
+        last = timed;
+        received += 1;
+    }
+    assert_eq!(received, 300);
+}
+
+#[tokio::test]
+async fn event_streams_collect_text_and_filter() {
+    let mock = mock_wk(23);
+    let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+        .build_with_port(mock.clone())
+        .await
+        .unwrap();
+    let mut pot = keyer.events().speed_pot();
+    let mut events = keyer.events();
+
+    keyer.send_message("CQ").await.unwrap();
+    let echo = mock.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        echo.queue_read(&[0xC4]);
+        echo.queue_read(b"CQ");
+        echo.queue_read(&[0x85]);
+        echo.queue_read(&[0xC0]);
+    });
+    let text = tokio::time::timeout(Duration::from_millis(500), events.host_text_until_idle())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(text, "CQ");
+
+    let wpm = tokio::time::timeout(Duration::from_millis(500), pot.recv())
+        .await
+        .unwrap();
+    assert!(wpm.is_some());
+}
+
+/// WinKeyer's own methods still work for callers that don't import `Keyer`.
+mod without_keyer_trait {
+    use winkey::WinKeyerBuilder;
+
+    #[tokio::test]
+    async fn inherent_methods_forward_to_keyer() {
+        let mock = super::mock_wk(23);
+        let keyer = WinKeyerBuilder::new("/dev/ttyUSB0")
+            .build_with_port(mock.clone())
+            .await
+            .unwrap();
+
+        keyer.set_weight(60).await.unwrap();
+        keyer.set_farnsworth(15).await.unwrap();
+        keyer.send_prosign(b'S', b'K').await.unwrap();
+        assert_eq!(keyer.settings().weight, 60);
+        assert_eq!(keyer.settings().farnsworth_wpm, 15);
+        assert!(keyer.set_weight(95).await.is_err());
+    }
+}